@@ -50,10 +50,10 @@ pub enum DescriptorType {
 
 #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct DescriptorSetLayoutBinding {
-    binding: u32,
-    descriptor_type: DescriptorType,
-    count: u32,
-    stage_flags: ShaderStageFlags,
+    pub binding: u32,
+    pub descriptor_type: DescriptorType,
+    pub count: u32,
+    pub stage_flags: ShaderStageFlags,
 }
 
 impl DescriptorSetLayoutBinding {
@@ -85,9 +85,9 @@ impl DescriptorSetLayout {
 
 #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct PushConstant {
-    offset: u32,
-    size: u32,
-    stage_flags: ShaderStageFlags,
+    pub offset: u32,
+    pub size: u32,
+    pub stage_flags: ShaderStageFlags,
 }
 
 impl PushConstant {
@@ -102,9 +102,9 @@ impl PushConstant {
 
 #[derive(Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct VertexAttribute {
-    location: u32,
-    stride: u32,
-    format: Format,
+    pub location: u32,
+    pub stride: u32,
+    pub format: Format,
 }
 
 impl VertexAttribute {
@@ -19,7 +19,9 @@ use rvr_assets::AssetType;
 
 impl_generational_list!(ShaderList<Shader, ShaderData>);
 
-pub struct ShaderData;
+pub struct ShaderData {
+    resident: bool,
+}
 
 pub struct AssetManager {
     index: AssetIndex,
@@ -45,12 +47,17 @@ impl AssetManager {
                 return Err(AssetError::AssetHasWrongType(AssetType::Shader, location.asset_type).into());
             }
 
+            let shader = self.shader.insert(ShaderData {
+                resident: false,
+            });
+
             self.worker.enqueue(LoadingTask::LoadShader {
+                shader,
+                name: name.to_string(),
                 path: location.path.to_string(),
                 offset: location.offset,
                 length: location.length,
             })?;
-            let shader = Shader::new(0, 0);
 
             Ok(shader)
         } else {
@@ -59,7 +66,10 @@ impl AssetManager {
     }
 
     pub fn is_shader_resident(&self, shader: Shader) -> Result<bool, Error> {
-        Ok(true)
+        match self.shader.get(&shader) {
+            Some(data) => Ok(data.resident),
+            None => Err(AssetError::InvalidShader.into()),
+        }
     }
 
     pub fn update(&mut self, renderer: &mut Renderer) -> Result<(), Error> {
@@ -67,8 +77,11 @@ impl AssetManager {
             let result = result?;
 
             match result {
-                LoadingResult::ShaderLoaded(shader_asset) => {
-                    dbg!(shader_asset);
+                LoadingResult::ShaderLoaded(shader, description) => {
+                    if let Some(data) = self.shader.get_mut(&shader) {
+                        renderer.add_shader(shader, description);
+                        data.resident = true;
+                    }
                 }
             }
         }
@@ -9,5 +9,8 @@ pub enum AssetError {
 
     #[error("Asset has wrong type, expected {0}, found {1}")]
     AssetHasWrongType(AssetType, AssetType),
+
+    #[error("Shader was never loaded")]
+    InvalidShader,
     
 }
@@ -1,7 +1,8 @@
 use crate::{
     Error,
     threading::Worker,
-    graphics::TransferDevice,
+    graphics::{TransferDevice, ShaderDescription},
+    assets::Shader,
 };
 use std::{io::SeekFrom, fs::File, io::prelude::*};
 use rvr_assets::shader::ShaderAsset;
@@ -22,6 +23,8 @@ fn read_from_file<T: DeserializeOwned>(path: &str, offset: usize, length: usize)
 #[derive(Debug)]
 pub enum LoadingTask {
     LoadShader {
+        shader: Shader,
+        name: String,
         path: String,
         offset: usize,
         length: usize,
@@ -30,7 +33,7 @@ pub enum LoadingTask {
 
 #[derive(Debug)]
 pub enum LoadingResult {
-    ShaderLoaded(()),
+    ShaderLoaded(Shader, ShaderDescription),
 }
 
 pub struct LoadingWorker {
@@ -51,10 +54,10 @@ impl Worker for LoadingWorker {
 
     fn execute(&mut self, task: Self::Task) -> Result<Self::TaskResult, Error> {
         match task {
-            LoadingTask::LoadShader { path, offset, length, } => {
-                let shader_asset = read_from_file(&path, offset, length)?;
-                let pipeline = self.transfer_device.create_pipeline(&shader_asset)?;
-                Ok(LoadingResult::ShaderLoaded(pipeline))
+            LoadingTask::LoadShader { shader, name, path, offset, length, } => {
+                let shader_asset: ShaderAsset = read_from_file(&path, offset, length)?;
+                let description = self.transfer_device.create_shader(&name, &shader_asset)?;
+                Ok(LoadingResult::ShaderLoaded(shader, description))
             },
         }
     }
@@ -1,21 +1,35 @@
 use crate::{
     platform::Window,
-    graphics::Renderer,
+    graphics::{Renderer, GpuProfiler, ValidationMessage},
     assets::AssetManager,
 };
 
 pub struct Context {
-    pub(crate) window: Window,
+    pub(crate) window: Option<Window>,
     pub(crate) renderer: Renderer,
     pub(crate) assets: AssetManager,
 }
 
 impl Context {
-    pub(crate) fn new(window: Window, renderer: Renderer, assets: AssetManager) -> Self {
+    pub(crate) fn new(window: Option<Window>, renderer: Renderer, assets: AssetManager) -> Self {
         Self {
             window,
             renderer,
             assets,
         }
     }
+
+    /// GPU timings of the passes of recently finished frames
+    pub fn get_gpu_profiler(&self) -> &GpuProfiler {
+        self.renderer.get_gpu_profiler()
+    }
+
+    pub fn get_gpu_profiler_mut(&mut self) -> &mut GpuProfiler {
+        self.renderer.get_gpu_profiler_mut()
+    }
+
+    /// Validation messages since the last call, empty when validation is off
+    pub fn take_validation_messages(&self) -> Vec<ValidationMessage> {
+        self.renderer.take_validation_messages()
+    }
 }
@@ -0,0 +1,78 @@
+use ash::vk;
+
+/// The value an attachment gets cleared to when a pass begins
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearValue {
+    Color([f32; 4]),
+    DepthStencil {
+        depth: f32,
+        stencil: u32,
+    },
+}
+
+impl Into<vk::ClearValue> for ClearValue {
+    fn into(self) -> vk::ClearValue {
+        match self {
+            ClearValue::Color(color) => vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: color,
+                },
+            },
+            ClearValue::DepthStencil { depth, stencil } => vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth,
+                    stencil,
+                },
+            },
+        }
+    }
+}
+
+/// What happens to the contents of an attachment when a render pass begins
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoadOp {
+    Load,
+    Clear(ClearValue),
+    DontCare,
+}
+
+impl Into<vk::AttachmentLoadOp> for LoadOp {
+    fn into(self) -> vk::AttachmentLoadOp {
+        match self {
+            LoadOp::Load => vk::AttachmentLoadOp::LOAD,
+            LoadOp::Clear(_) => vk::AttachmentLoadOp::CLEAR,
+            LoadOp::DontCare => vk::AttachmentLoadOp::DONT_CARE,
+        }
+    }
+}
+
+/// What happens to the contents of an attachment when a render pass ends
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StoreOp {
+    Store,
+    DontCare,
+}
+
+impl Into<vk::AttachmentStoreOp> for StoreOp {
+    fn into(self) -> vk::AttachmentStoreOp {
+        match self {
+            StoreOp::Store => vk::AttachmentStoreOp::STORE,
+            StoreOp::DontCare => vk::AttachmentStoreOp::DONT_CARE,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AttachmentOps {
+    pub load_op: LoadOp,
+    pub store_op: StoreOp,
+}
+
+impl AttachmentOps {
+    pub fn new(load_op: LoadOp, store_op: StoreOp) -> Self {
+        Self {
+            load_op,
+            store_op,
+        }
+    }
+}
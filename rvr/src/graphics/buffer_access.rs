@@ -0,0 +1,64 @@
+use super::PipelineBindPoint;
+use ash::vk;
+
+/// The ways a pass can use a buffer
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum BufferAccess {
+    VertexRead,
+    IndexRead,
+    IndirectRead,
+    UniformRead,
+    StorageRead,
+    StorageWrite,
+}
+
+impl BufferAccess {
+    /// Shader accesses happen in the vertex and fragment shaders of graphics passes and the compute shader of compute passes
+    pub fn get_stage_mask(&self, bind_point: PipelineBindPoint) -> vk::PipelineStageFlags {
+        let shader_stages = match bind_point {
+            PipelineBindPoint::Graphics => vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            PipelineBindPoint::Compute => vk::PipelineStageFlags::COMPUTE_SHADER,
+        };
+
+        match self {
+            BufferAccess::VertexRead | BufferAccess::IndexRead => vk::PipelineStageFlags::VERTEX_INPUT,
+            BufferAccess::IndirectRead => vk::PipelineStageFlags::DRAW_INDIRECT,
+            BufferAccess::UniformRead | BufferAccess::StorageRead | BufferAccess::StorageWrite => shader_stages,
+        }
+    }
+
+    /// All memory accesses, used as the destination of a barrier
+    pub fn get_access_mask(&self) -> vk::AccessFlags {
+        match self {
+            BufferAccess::VertexRead => vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            BufferAccess::IndexRead => vk::AccessFlags::INDEX_READ,
+            BufferAccess::IndirectRead => vk::AccessFlags::INDIRECT_COMMAND_READ,
+            BufferAccess::UniformRead => vk::AccessFlags::UNIFORM_READ,
+            BufferAccess::StorageRead => vk::AccessFlags::SHADER_READ,
+            BufferAccess::StorageWrite => vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+        }
+    }
+
+    /// Only the writes, which have to be made available before anyone else touches the buffer
+    pub fn get_write_access_mask(&self) -> vk::AccessFlags {
+        match self {
+            BufferAccess::StorageWrite => vk::AccessFlags::SHADER_WRITE,
+            _ => vk::AccessFlags::empty(),
+        }
+    }
+
+    pub fn is_write(&self) -> bool {
+        !self.get_write_access_mask().is_empty()
+    }
+
+    /// How a buffer has to be created to allow this access
+    pub fn get_usage(&self) -> vk::BufferUsageFlags {
+        match self {
+            BufferAccess::VertexRead => vk::BufferUsageFlags::VERTEX_BUFFER,
+            BufferAccess::IndexRead => vk::BufferUsageFlags::INDEX_BUFFER,
+            BufferAccess::IndirectRead => vk::BufferUsageFlags::INDIRECT_BUFFER,
+            BufferAccess::UniformRead => vk::BufferUsageFlags::UNIFORM_BUFFER,
+            BufferAccess::StorageRead | BufferAccess::StorageWrite => vk::BufferUsageFlags::STORAGE_BUFFER,
+        }
+    }
+}
@@ -1,11 +1,179 @@
+use ash::vk;
+use serde_derive::*;
+use std::{env, fs, path::Path};
+use crate::{
+    Error,
+    graphics::{GraphicsError, PresentMode, ValidationMode},
+};
+
+/// Selects a physical device by its index in the list of devices or by its name
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DevicePreference {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Configuration {
+    /// Present modes in order of preference, FIFO is used when the surface supports none of them
+    pub present_modes: Vec<PresentMode>,
+    pub swapchain_image_count: u32,
+    pub frames_in_flight: u32,
+    /// The device with the highest score is used when this is not set, creating the renderer fails when no device matches
+    pub device: Option<DevicePreference>,
+    /// Measures the GPU time of every pass with timestamp queries
+    pub gpu_profiling: bool,
+    pub validation: ValidationMode,
+}
 
+impl Default for Configuration {
+    fn default() -> Self {
+        Self {
+            present_modes: vec![PresentMode::Mailbox, PresentMode::Fifo],
+            swapchain_image_count: 2,
+            frames_in_flight: 2,
+            device: None,
+            gpu_profiling: false,
+            validation: ValidationMode::Log,
+        }
+    }
 }
 
 impl Configuration {
     pub fn new() -> Self {
-        Self {
-            
+        Self::default()
+    }
+
+    /// Reads the JSON file if it exists and applies the `RVR_*` environment variables on top
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut configuration = if path.as_ref().exists() {
+            Self::from_json(&fs::read_to_string(path)?)?
+        } else {
+            Self::new()
+        };
+
+        configuration.apply_overrides(|key| env::var(key).ok())?;
+
+        Ok(configuration)
+    }
+
+    /// Parses a configuration, missing fields keep their defaults
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let configuration: Self = serde_json::from_str(json)?;
+        configuration.validate()?;
+
+        Ok(configuration)
+    }
+
+    /// Enabling vsync limits the present modes to the ones that wait for the vertical blank
+    pub fn set_vsync(&mut self, enabled: bool) {
+        self.present_modes = if enabled {
+            vec![PresentMode::Fifo]
+        } else {
+            vec![PresentMode::Immediate, PresentMode::Mailbox, PresentMode::FifoRelaxed]
+        };
+    }
+
+    pub(crate) fn get_vk_present_modes(&self) -> Vec<vk::PresentModeKHR> {
+        self.present_modes.iter().map(|present_mode| (*present_mode).into()).collect()
+    }
+
+    fn apply_overrides<F: Fn(&str) -> Option<String>>(&mut self, var: F) -> Result<(), Error> {
+        if let Some(value) = var("RVR_VSYNC") {
+            match value.trim() {
+                "1" | "true" | "on" => self.set_vsync(true),
+                "0" | "false" | "off" => self.set_vsync(false),
+                _ => return Err(GraphicsError::InvalidConfiguration("vsync".to_owned(), value).into()),
+            }
+        }
+
+        if let Some(value) = var("RVR_PRESENT_MODES") {
+            self.present_modes = value.split(',')
+                .map(|present_mode| present_mode.parse())
+                .collect::<Result<_, Error>>()?;
+        }
+
+        if let Some(value) = var("RVR_SWAPCHAIN_IMAGES") {
+            self.swapchain_image_count = value.trim().parse()
+                .map_err(|_| GraphicsError::InvalidConfiguration("swapchain image count".to_owned(), value.clone()))?;
         }
+
+        if let Some(value) = var("RVR_FRAMES_IN_FLIGHT") {
+            self.frames_in_flight = value.trim().parse()
+                .map_err(|_| GraphicsError::InvalidConfiguration("frames in flight".to_owned(), value.clone()))?;
+        }
+
+        if let Some(value) = var("RVR_DEVICE") {
+            self.device = match value.trim().parse() {
+                Ok(index) => Some(DevicePreference::Index(index)),
+                Err(_) => Some(DevicePreference::Name(value)),
+            };
+        }
+
+        if let Some(value) = var("RVR_GPU_PROFILING") {
+            self.gpu_profiling = match value.trim() {
+                "1" | "true" | "on" => true,
+                "0" | "false" | "off" => false,
+                _ => return Err(GraphicsError::InvalidConfiguration("gpu profiling".to_owned(), value).into()),
+            };
+        }
+
+        if let Some(value) = var("RVR_VALIDATION") {
+            self.validation = value.parse()?;
+        }
+
+        self.validate()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.swapchain_image_count == 0 {
+            return Err(GraphicsError::InvalidConfiguration("swapchain image count".to_owned(), "0".to_owned()).into());
+        }
+
+        if self.frames_in_flight == 0 {
+            return Err(GraphicsError::InvalidConfiguration("frames in flight".to_owned(), "0".to_owned()).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_defaults_for_missing_fields() {
+        let configuration = Configuration::from_json(r#"{ "present_modes": ["fifo_relaxed", "fifo"], "device": "llvmpipe" }"#).unwrap();
+
+        assert_eq!(configuration.present_modes, vec![PresentMode::FifoRelaxed, PresentMode::Fifo]);
+        assert_eq!(configuration.device, Some(DevicePreference::Name("llvmpipe".to_owned())));
+        assert_eq!(configuration.swapchain_image_count, 2);
+        assert_eq!(configuration.frames_in_flight, 2);
+        assert!(!configuration.gpu_profiling);
+    }
+
+    #[test]
+    fn it_applies_overrides() {
+        let mut configuration = Configuration::new();
+        configuration.apply_overrides(|key| match key {
+            "RVR_VSYNC" => Some("1".to_owned()),
+            "RVR_FRAMES_IN_FLIGHT" => Some("3".to_owned()),
+            "RVR_DEVICE" => Some("1".to_owned()),
+            "RVR_VALIDATION" => Some("panic".to_owned()),
+            _ => None,
+        }).unwrap();
+
+        assert_eq!(configuration.present_modes, vec![PresentMode::Fifo]);
+        assert_eq!(configuration.frames_in_flight, 3);
+        assert_eq!(configuration.device, Some(DevicePreference::Index(1)));
+        assert_eq!(configuration.validation, ValidationMode::Panic);
+
+        assert!(configuration.apply_overrides(|key| match key {
+            "RVR_PRESENT_MODES" => Some("mailbox,triple".to_owned()),
+            _ => None,
+        }).is_err());
     }
 }
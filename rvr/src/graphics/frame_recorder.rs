@@ -0,0 +1,834 @@
+use ash::vk;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::{
+    Error,
+    threading::{fork_join, get_thread_count},
+    graphics::*,
+    graphics::rendergraph::*,
+    graphics::vulkan::*,
+};
+
+/// An image the render graph can use, either imported or created for the current frame
+#[derive(Debug, Copy, Clone)]
+pub struct PhysicalImage {
+    pub image: vk::Image,
+    pub format: vk::Format,
+    pub width: u32,
+    pub height: u32,
+    pub samples: vk::SampleCountFlags,
+}
+
+impl PhysicalImage {
+    pub fn new(image: vk::Image, format: vk::Format, width: u32, height: u32, samples: vk::SampleCountFlags) -> Self {
+        Self {
+            image,
+            format,
+            width,
+            height,
+            samples,
+        }
+    }
+
+    fn from_image(image: &Image) -> Self {
+        Self::new(image.get_inner(), image.get_format(), image.get_width(), image.get_height(), image.get_samples())
+    }
+}
+
+/// A buffer the render graph can use, either imported or created for the current frame
+#[derive(Debug, Copy, Clone)]
+pub struct PhysicalBuffer {
+    pub buffer: vk::Buffer,
+    pub size: usize,
+}
+
+impl PhysicalBuffer {
+    pub fn new(buffer: vk::Buffer, size: usize) -> Self {
+        Self {
+            buffer,
+            size,
+        }
+    }
+
+    fn from_buffer(buffer: &Buffer) -> Self {
+        Self::new(buffer.get_inner(), buffer.get_size())
+    }
+}
+
+/// The images and buffers a schedule creates, together with the memory the aliased images share
+///
+/// Later frames that create the same resources reuse them, which keeps the framebuffers of their render passes cached.
+pub struct TransientResources {
+    // the create instructions the resources were made for
+    instructions: Vec<Instruction>,
+    images: HashMap<u32, Image>,
+    buffers: HashMap<u32, Buffer>,
+    memory: Vec<Memory>,
+}
+
+impl TransientResources {
+    pub fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+            images: HashMap::new(),
+            buffers: HashMap::new(),
+            memory: Vec::new(),
+        }
+    }
+
+    /// Hands the resources over to the frame resources, frames in flight might still use them
+    pub fn retire(self, render_pass_cache: &mut RenderPassCache, frame_resources: &mut FrameResourceBuilder) {
+        let images: Vec<vk::Image> = self.images.values().map(|image| image.get_inner()).collect();
+        render_pass_cache.evict_images(&images, frame_resources);
+
+        self.images.into_iter().for_each(|(_, image)| frame_resources.add_image(image));
+        self.buffers.into_iter().for_each(|(_, buffer)| frame_resources.add_buffer(buffer));
+        self.memory.into_iter().for_each(|memory| frame_resources.add_memory(memory));
+    }
+}
+
+/// The images and buffers of the graph in the current frame
+pub struct PhysicalResources {
+    imported_images: HashMap<u32, PhysicalImage>,
+    imported_buffers: HashMap<u32, PhysicalBuffer>,
+    transient: TransientResources,
+}
+
+impl PhysicalResources {
+    fn new() -> Self {
+        Self {
+            imported_images: HashMap::new(),
+            imported_buffers: HashMap::new(),
+            transient: TransientResources::new(),
+        }
+    }
+
+    pub fn get_buffer(&self, id: u32) -> PhysicalBuffer {
+        if let Some(buffer) = self.imported_buffers.get(&id) {
+            *buffer
+        } else {
+            PhysicalBuffer::from_buffer(self.transient.buffers.get(&id).expect("buffer was not created or imported"))
+        }
+    }
+
+    pub fn get_image(&self, id: u32) -> PhysicalImage {
+        if let Some(image) = self.imported_images.get(&id) {
+            *image
+        } else {
+            PhysicalImage::from_image(self.transient.images.get(&id).expect("image was not created or imported"))
+        }
+    }
+}
+
+/// A pass that gets recorded into its own secondary command buffer
+struct RecordingJob {
+    id: u32,
+    executor: Box<dyn Executor>,
+    queue_family_index: u32,
+    subpass: Option<SubpassInfo>,
+    sample_count: vk::SampleCountFlags,
+    // the first of the two queries the pass writes its begin and end timestamps to
+    timestamp_query: Option<(vk::QueryPool, u32)>,
+}
+
+/// A render pass that was created for the subpasses of an instruction, ready to be begun
+struct RenderPassInstance {
+    render_pass: vk::RenderPass,
+    framebuffer: vk::Framebuffer,
+    width: u32,
+    height: u32,
+    clear_values: Vec<vk::ClearValue>,
+    passes: Vec<u32>,
+}
+
+/// Records the instructions of a schedule into a command buffer
+pub struct FrameRecorder<'a> {
+    device: &'a Device,
+    executors: HashMap<u32, Box<dyn Executor>>,
+    resources: PhysicalResources,
+    render_pass_cache: &'a mut RenderPassCache,
+    pipeline_cache: &'a Mutex<PipelineCache>,
+    command_buffers: HashMap<u32, CommandBuffer>,
+    render_pass_instances: HashMap<u32, RenderPassInstance>,
+    timestamp_queries: Option<(vk::QueryPool, HashMap<u32, u32>)>,
+    pass_names: HashMap<u32, String>,
+    resource_names: HashMap<u32, String>,
+}
+
+impl<'a> FrameRecorder<'a> {
+    pub fn new(device: &'a Device, executors: HashMap<u32, Box<dyn Executor>>, render_pass_cache: &'a mut RenderPassCache, pipeline_cache: &'a Mutex<PipelineCache>) -> Self {
+        Self {
+            device,
+            executors,
+            resources: PhysicalResources::new(),
+            render_pass_cache,
+            pipeline_cache,
+            command_buffers: HashMap::new(),
+            render_pass_instances: HashMap::new(),
+            timestamp_queries: None,
+            pass_names: HashMap::new(),
+            resource_names: HashMap::new(),
+        }
+    }
+
+    /// Names transient resources and command buffers and labels the passes with their names in the graph
+    pub fn set_names(&mut self, pass_names: HashMap<u32, String>, resource_names: HashMap<u32, String>) {
+        self.pass_names = pass_names;
+        self.resource_names = resource_names;
+    }
+
+    fn get_pass_name(&self, id: u32) -> &str {
+        self.pass_names.get(&id).map(String::as_str).unwrap_or("")
+    }
+
+    /// Makes the passes write timestamps to the queries, keyed by pass id
+    pub fn write_timestamps(&mut self, query_pool: vk::QueryPool, queries: HashMap<u32, u32>) {
+        self.timestamp_queries = Some((query_pool, queries));
+    }
+
+    fn get_timestamp_query(&self, id: u32) -> Option<(vk::QueryPool, u32)> {
+        let (query_pool, queries) = self.timestamp_queries.as_ref()?;
+        queries.get(&id).map(|query| (*query_pool, *query))
+    }
+
+    pub fn import_image(&mut self, id: u32, image: PhysicalImage) {
+        self.resources.imported_images.insert(id, image);
+    }
+
+    pub fn import_buffer(&mut self, id: u32, buffer: PhysicalBuffer) {
+        self.resources.imported_buffers.insert(id, buffer);
+    }
+
+    /// Records every pass of the schedule into its own secondary command buffer, the passes get split between worker threads
+    pub fn record_passes(&mut self, schedule: &Schedule, pools: &mut Resources, frame_resources: &mut FrameResourceBuilder) -> Result<(), Error> {
+        let mut jobs = Vec::new();
+
+        for submission in schedule.get_submissions() {
+            // secondary command buffers have to be allocated for the queue family that executes them
+            let queue_family_index = submission.get_queue().get_queue(self.device).get_family_index();
+
+            for instruction in submission.get_instructions() {
+                match instruction {
+                    Instruction::ExecutePass { id, bind_point, color_attachments, depth_stencil_attachment, input_attachments, resolve_attachments, attachment_ops } => {
+                        // compute passes don't need a render pass, graph validation makes sure every graphics pass has an attachment
+                        if *bind_point == PipelineBindPoint::Compute {
+                            jobs.push(RecordingJob {
+                                id: *id,
+                                executor: self.executors.remove(id).expect("pass was executed twice"),
+                                queue_family_index,
+                                subpass: None,
+                                sample_count: vk::SampleCountFlags::TYPE_1,
+                                timestamp_query: self.get_timestamp_query(*id),
+                            });
+                        } else {
+                            let subpass = Subpass {
+                                id: *id,
+                                color_attachments: color_attachments.clone(),
+                                depth_stencil_attachment: *depth_stencil_attachment,
+                                input_attachments: input_attachments.clone(),
+                                resolve_attachments: resolve_attachments.clone(),
+                                attachment_ops: attachment_ops.clone(),
+                            };
+
+                            self.prepare_render_pass(&[subpass], &[], queue_family_index, &mut jobs)?;
+                        }
+                    },
+                    Instruction::ExecuteSubpasses { subpasses, dependencies } => {
+                        self.prepare_render_pass(subpasses, dependencies, queue_family_index, &mut jobs)?;
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        // every thread records a contiguous share of the passes
+        let thread_count = get_thread_count();
+        let chunk_size = ((jobs.len() + thread_count - 1) / thread_count).max(1);
+        let mut chunks = Vec::new();
+        while !jobs.is_empty() {
+            let rest = jobs.split_off(chunk_size.min(jobs.len()));
+
+            // every thread gets its own pool for each queue family it records for
+            let mut command_pools: HashMap<u32, CommandPool> = HashMap::new();
+            for job in jobs.iter() {
+                if !command_pools.contains_key(&job.queue_family_index) {
+                    command_pools.insert(job.queue_family_index, pools.get_command_pool(self.device, job.queue_family_index)?);
+                }
+            }
+
+            chunks.push((jobs, command_pools));
+            jobs = rest;
+        }
+
+        let device = self.device;
+        let resources = &self.resources;
+        let pipeline_cache = self.pipeline_cache;
+        let results = fork_join(chunks, |(jobs, command_pools)| record_jobs(device, resources, pipeline_cache, jobs, command_pools));
+
+        for result in results {
+            let (command_buffers, thread_resources) = result?;
+            for (id, command_buffer) in command_buffers.iter() {
+                self.device.set_object_name(command_buffer.get_inner(), self.get_pass_name(*id))?;
+            }
+
+            self.command_buffers.extend(command_buffers);
+            frame_resources.append(thread_resources);
+        }
+
+        Ok(())
+    }
+
+    /// Executes the secondary command buffer of a pass, or those of all subpasses of the render pass that starts with it
+    fn execute_pass(&mut self, command_buffer: &mut CommandBuffer, id: u32) -> Result<(), Error> {
+        match self.render_pass_instances.remove(&id) {
+            Some(instance) => {
+                command_buffer.begin_render_pass(instance.render_pass, instance.framebuffer, instance.width, instance.height, &instance.clear_values);
+                for (index, pass) in instance.passes.iter().enumerate() {
+                    if index > 0 {
+                        command_buffer.next_subpass();
+                    }
+
+                    let secondary = self.command_buffers.remove(pass).expect("pass was executed twice");
+                    self.device.begin_label(command_buffer, self.get_pass_name(*pass))?;
+                    command_buffer.execute_commands(&[&secondary]);
+                    self.device.end_label(command_buffer);
+                }
+                command_buffer.end_render_pass();
+            },
+            None => {
+                let secondary = self.command_buffers.remove(&id).expect("pass was executed twice");
+                self.device.begin_label(command_buffer, self.get_pass_name(id))?;
+                command_buffer.execute_commands(&[&secondary]);
+                self.device.end_label(command_buffer);
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Records the instructions of a submission into the command buffer
+    pub fn record(
+        &mut self,
+        command_buffer: &mut CommandBuffer,
+        submission: &Submission,
+        present_layout: vk::ImageLayout,
+    ) -> Result<(), Error> {
+
+        let to_vk_layout = |layout: ImageLayout| match layout {
+            ImageLayout::Present => present_layout,
+            layout => layout.into(),
+        };
+
+        // presenting to a transfer source layout means the image gets copied afterwards
+        let to_present_masks = |to: ImageLayout, stage_mask: vk::PipelineStageFlags, access_mask: vk::AccessFlags| {
+            if to == ImageLayout::Present && present_layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+                (vk::PipelineStageFlags::TRANSFER, vk::AccessFlags::TRANSFER_READ)
+            } else {
+                (stage_mask, access_mask)
+            }
+        };
+
+        // ownership only has to be transferred if the queues belong to different families
+        let device = self.device;
+        let family_index = submission.get_queue().get_queue(device).get_family_index();
+        let get_other_family_index = |other: QueueType| {
+            let other_index = other.get_queue(device).get_family_index();
+            if other_index == family_index {
+                None
+            } else {
+                Some(other_index)
+            }
+        };
+
+        // the passes of the submission write their timestamps from secondary command buffers, which can be inside render passes
+        if let Some((query_pool, queries)) = &self.timestamp_queries {
+            for instruction in submission.get_instructions().iter() {
+                let ids = match instruction {
+                    Instruction::ExecutePass { id, .. } => vec![*id],
+                    Instruction::ExecuteSubpasses { subpasses, .. } => subpasses.iter().map(|subpass| subpass.id).collect(),
+                    _ => continue,
+                };
+
+                for query in ids.iter().filter_map(|id| queries.get(id)) {
+                    command_buffer.reset_query_pool(*query_pool, *query, 2);
+                }
+            }
+        }
+
+        // consecutive barriers get recorded with a single pipeline barrier
+        let mut src_stage_mask = vk::PipelineStageFlags::empty();
+        let mut dst_stage_mask = vk::PipelineStageFlags::empty();
+        let mut buffer_barriers = Vec::new();
+        let mut image_barriers = Vec::new();
+
+        for instruction in submission.get_instructions().iter() {
+            match instruction {
+                Instruction::ImageLayoutBarrier { id, subresource, from, to, src_stage_mask: src, dst_stage_mask: dst, src_access_mask, dst_access_mask } => {
+                    let image = self.resources.get_image(*id);
+                    let (dst, dst_access_mask) = to_present_masks(*to, *dst, *dst_access_mask);
+
+                    src_stage_mask |= *src;
+                    dst_stage_mask |= dst;
+                    image_barriers.push(ImageBarrier {
+                        image: image.image,
+                        subresource_range: get_subresource_range(image.format, subresource),
+                        old_layout: to_vk_layout(*from),
+                        new_layout: to_vk_layout(*to),
+                        src_access_mask: *src_access_mask,
+                        dst_access_mask,
+                        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    });
+                    continue;
+                },
+                Instruction::BufferBarrier { id, src_stage_mask: src, dst_stage_mask: dst, src_access_mask, dst_access_mask } => {
+                    src_stage_mask |= *src;
+                    dst_stage_mask |= *dst;
+                    buffer_barriers.push(BufferBarrier {
+                        buffer: self.resources.get_buffer(*id).buffer,
+                        src_access_mask: *src_access_mask,
+                        dst_access_mask: *dst_access_mask,
+                        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    });
+                    continue;
+                },
+                // within the same family the semaphore alone is enough, the acquire does the layout transition
+                Instruction::ImageOwnershipRelease { id, subresource, from, to, src_stage_mask: src, src_access_mask, dst_queue } => {
+                    if let Some(dst_family_index) = get_other_family_index(*dst_queue) {
+                        let image = self.resources.get_image(*id);
+
+                        src_stage_mask |= *src;
+                        dst_stage_mask |= vk::PipelineStageFlags::BOTTOM_OF_PIPE;
+                        image_barriers.push(ImageBarrier {
+                            image: image.image,
+                            subresource_range: get_subresource_range(image.format, subresource),
+                            old_layout: to_vk_layout(*from),
+                            new_layout: to_vk_layout(*to),
+                            src_access_mask: *src_access_mask,
+                            dst_access_mask: vk::AccessFlags::empty(),
+                            src_queue_family_index: family_index,
+                            dst_queue_family_index: dst_family_index,
+                        });
+                    }
+                    continue;
+                },
+                // the acquire waits on the same stages as the semaphore, which makes the release visible
+                Instruction::ImageOwnershipAcquire { id, subresource, from, to, dst_stage_mask: dst, dst_access_mask, src_queue } => {
+                    let image = self.resources.get_image(*id);
+                    let (dst, dst_access_mask) = to_present_masks(*to, *dst, *dst_access_mask);
+                    let (src_queue_family_index, dst_queue_family_index) = match get_other_family_index(*src_queue) {
+                        Some(src_family_index) => (src_family_index, family_index),
+                        None => (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED),
+                    };
+
+                    src_stage_mask |= dst;
+                    dst_stage_mask |= dst;
+                    image_barriers.push(ImageBarrier {
+                        image: image.image,
+                        subresource_range: get_subresource_range(image.format, subresource),
+                        old_layout: to_vk_layout(*from),
+                        new_layout: to_vk_layout(*to),
+                        src_access_mask: vk::AccessFlags::empty(),
+                        dst_access_mask,
+                        src_queue_family_index,
+                        dst_queue_family_index,
+                    });
+                    continue;
+                },
+                Instruction::BufferOwnershipRelease { id, src_stage_mask: src, src_access_mask, dst_queue } => {
+                    if let Some(dst_family_index) = get_other_family_index(*dst_queue) {
+                        src_stage_mask |= *src;
+                        dst_stage_mask |= vk::PipelineStageFlags::BOTTOM_OF_PIPE;
+                        buffer_barriers.push(BufferBarrier {
+                            buffer: self.resources.get_buffer(*id).buffer,
+                            src_access_mask: *src_access_mask,
+                            dst_access_mask: vk::AccessFlags::empty(),
+                            src_queue_family_index: family_index,
+                            dst_queue_family_index: dst_family_index,
+                        });
+                    }
+                    continue;
+                },
+                Instruction::BufferOwnershipAcquire { id, dst_stage_mask: dst, dst_access_mask, src_queue } => {
+                    // the semaphore already made the writes visible if no ownership gets transferred
+                    if let Some(src_family_index) = get_other_family_index(*src_queue) {
+                        src_stage_mask |= *dst;
+                        dst_stage_mask |= *dst;
+                        buffer_barriers.push(BufferBarrier {
+                            buffer: self.resources.get_buffer(*id).buffer,
+                            src_access_mask: vk::AccessFlags::empty(),
+                            dst_access_mask: *dst_access_mask,
+                            src_queue_family_index: src_family_index,
+                            dst_queue_family_index: family_index,
+                        });
+                    }
+                    continue;
+                },
+                _ => {},
+            }
+
+            if !buffer_barriers.is_empty() || !image_barriers.is_empty() {
+                command_buffer.pipeline_barrier(src_stage_mask, dst_stage_mask, &buffer_barriers, &image_barriers);
+                src_stage_mask = vk::PipelineStageFlags::empty();
+                dst_stage_mask = vk::PipelineStageFlags::empty();
+                buffer_barriers.clear();
+                image_barriers.clear();
+            }
+
+            match instruction {
+                // transient images and buffers were created up front
+                Instruction::CreateImage { .. } | Instruction::CreateBuffer { .. } => {},
+                Instruction::ExecutePass { id, .. } => {
+                    self.execute_pass(command_buffer, *id)?;
+                },
+                Instruction::ExecuteSubpasses { subpasses, .. } => {
+                    self.execute_pass(command_buffer, subpasses[0].id)?;
+                },
+                // transient images and buffers stay alive for the next frame that creates the same resources
+                Instruction::ReleaseImage { .. } | Instruction::ReleaseBuffer { .. } => {},
+                Instruction::Present { .. } => {},
+                Instruction::ImageLayoutBarrier { .. }
+                | Instruction::BufferBarrier { .. }
+                | Instruction::ImageOwnershipRelease { .. }
+                | Instruction::ImageOwnershipAcquire { .. }
+                | Instruction::BufferOwnershipRelease { .. }
+                | Instruction::BufferOwnershipAcquire { .. } => unreachable!(),
+            }
+        }
+
+        if !buffer_barriers.is_empty() || !image_barriers.is_empty() {
+            command_buffer.pipeline_barrier(src_stage_mask, dst_stage_mask, &buffer_barriers, &image_barriers);
+        }
+
+        Ok(())
+    }
+
+    /// Creates all transient images and buffers of the schedule before any pass gets recorded, images in the same allocation share one block of memory
+    pub fn create_transient_resources(&mut self, schedule: &Schedule, frame_resources: &mut FrameResourceBuilder) -> Result<(), Error> {
+        let instructions: Vec<Instruction> = schedule.get_instructions().into_iter()
+            .filter(|instruction| match instruction {
+                Instruction::CreateImage { .. } | Instruction::CreateBuffer { .. } => true,
+                _ => false,
+            })
+            .cloned()
+            .collect();
+
+        if self.resources.transient.instructions == instructions {
+            return Ok(());
+        }
+
+        // the schedule creates different resources, the old ones might still be used by frames in flight
+        let previous = std::mem::replace(&mut self.resources.transient, TransientResources::new());
+        previous.retire(self.render_pass_cache, frame_resources);
+
+        let mut allocations: HashMap<u32, Vec<u32>> = HashMap::new();
+
+        for instruction in instructions.iter() {
+            match instruction {
+                Instruction::CreateImage { id, description, usage, allocation } => {
+                    let image = self.device.create_unbound_image(&description.get_create_description(*usage))?;
+                    if let Some(name) = self.resource_names.get(id) {
+                        self.device.set_object_name(image.get_inner(), name)?;
+                    }
+
+                    self.resources.transient.images.insert(*id, image);
+                    allocations.entry(*allocation).or_insert_with(Vec::new).push(*id);
+                },
+                Instruction::CreateBuffer { id, description, usage } => {
+                    let buffer = self.device.create_buffer(description.size as usize, *usage)?;
+                    if let Some(name) = self.resource_names.get(id) {
+                        self.device.set_object_name(buffer.get_inner(), name)?;
+                    }
+
+                    self.resources.transient.buffers.insert(*id, buffer);
+                },
+                _ => {},
+            }
+        }
+
+        for (_, images) in allocations.iter() {
+            let mut memory_requirements = vk::MemoryRequirements {
+                size: 0,
+                alignment: 1,
+                memory_type_bits: !0,
+            };
+
+            for id in images.iter() {
+                let requirements = self.resources.transient.images[id].get_memory_requirements().ok_or(GraphicsError::ImageHasDedicatedMemory)?;
+                memory_requirements.size = memory_requirements.size.max(requirements.size);
+                memory_requirements.alignment = memory_requirements.alignment.max(requirements.alignment);
+                memory_requirements.memory_type_bits &= requirements.memory_type_bits;
+            }
+
+            if memory_requirements.memory_type_bits == 0 {
+                return Err(GraphicsError::NoAliasableMemoryType.into());
+            }
+
+            let memory = self.device.allocate_memory(&memory_requirements)?;
+            for id in images.iter() {
+                memory.bind_image(&self.resources.transient.images[id])?;
+            }
+
+            self.resources.transient.memory.push(memory);
+        }
+
+        self.resources.transient.instructions = instructions;
+
+        Ok(())
+    }
+
+    /// Records into the transient resources of an earlier frame, they get replaced if the schedule creates different ones
+    pub fn reuse_transient_resources(&mut self, transient: TransientResources) {
+        self.resources.transient = transient;
+    }
+
+    /// Returns the transient resources, so a later frame can reuse them once this one is finished
+    pub fn finish(self) -> TransientResources {
+        self.resources.transient
+    }
+
+    /// Creates the render pass and framebuffer of the subpasses and adds a job for each of them
+    fn prepare_render_pass(
+        &mut self,
+        subpasses: &[Subpass],
+        dependencies: &[SubpassDependency],
+        queue_family_index: u32,
+        jobs: &mut Vec<RecordingJob>,
+    ) -> Result<(), Error> {
+
+        // every subresource gets a single attachment, in the order the subpasses first use them
+        let mut handles: Vec<ImageSubresourceHandle> = Vec::new();
+        let mut layouts: Vec<(vk::ImageLayout, vk::ImageLayout)> = Vec::new();
+        let mut uses: Vec<(usize, usize)> = Vec::new();
+        // the first use decides how an attachment gets loaded, the last one how it gets stored
+        let mut ops: Vec<(LoadOp, StoreOp)> = Vec::new();
+
+        let mut subpass_references = Vec::new();
+        for (index, subpass) in subpasses.iter().enumerate() {
+            let references = subpass.color_attachments.iter()
+                .map(|handle| (handle, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL))
+                .chain(subpass.depth_stencil_attachment.iter().map(|handle| (handle, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)))
+                .chain(subpass.input_attachments.iter().map(|handle| (handle, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)))
+                .chain(subpass.resolve_attachments.iter().map(|(_, target)| (target, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)))
+                .zip(subpass.attachment_ops.iter())
+                .map(|((handle, layout), attachment_ops)| {
+                    let attachment = match handles.iter().position(|other| other.image.id == handle.image.id && other.range == handle.range) {
+                        Some(attachment) => {
+                            layouts[attachment].1 = layout;
+                            uses[attachment].1 = index;
+                            ops[attachment].1 = attachment_ops.store_op;
+                            attachment
+                        },
+                        None => {
+                            handles.push(*handle);
+                            layouts.push((layout, layout));
+                            uses.push((index, index));
+                            ops.push((attachment_ops.load_op, attachment_ops.store_op));
+                            handles.len() - 1
+                        },
+                    };
+
+                    AttachmentReferenceDescription {
+                        attachment: attachment as u32,
+                        layout,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            subpass_references.push(references);
+        }
+
+        let mut attachments = Vec::new();
+        let mut clear_values = Vec::new();
+        let mut images = Vec::new();
+
+        for ((handle, (initial_layout, final_layout)), (load_op, store_op)) in handles.iter().zip(layouts.iter()).zip(ops.iter()) {
+            let image = self.resources.get_image(handle.image.id);
+
+            // attachments that don't get cleared ignore their clear value
+            clear_values.push(match load_op {
+                LoadOp::Clear(clear_value) => (*clear_value).into(),
+                _ => ClearValue::Color([0.0; 4]).into(),
+            });
+
+            attachments.push(AttachmentDescription {
+                format: image.format,
+                samples: image.samples,
+                load_op: (*load_op).into(),
+                store_op: (*store_op).into(),
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                // the schedule already transitioned the image with a barrier
+                initial_layout: *initial_layout,
+                final_layout: *final_layout,
+            });
+
+            images.push((image, handle.range));
+        }
+
+        let subpass_descriptions = subpass_references.into_iter()
+            .zip(subpasses.iter())
+            .enumerate()
+            .map(|(index, (mut references, subpass))| {
+                // references are in the order of the subpass attachments
+                let resolve_targets = references.split_off(references.len() - subpass.resolve_attachments.len());
+                let input_attachments = references.split_off(references.len() - subpass.input_attachments.len());
+                let depth_stencil_attachment = match subpass.depth_stencil_attachment {
+                    Some(_) => references.pop(),
+                    None => None,
+                };
+                let color_attachments = references;
+
+                // resolve attachments have to line up with the color attachments, the ones without a resolve are unused
+                let resolve_attachments = if resolve_targets.is_empty() {
+                    Vec::new()
+                } else {
+                    subpass.color_attachments.iter()
+                        .map(|color| match subpass.resolve_attachments.iter().position(|(msaa, _)| msaa == color) {
+                            Some(position) => resolve_targets[position].clone(),
+                            None => AttachmentReferenceDescription {
+                                attachment: vk::ATTACHMENT_UNUSED,
+                                layout: vk::ImageLayout::UNDEFINED,
+                            },
+                        })
+                        .collect()
+                };
+
+                // attachments used before and after this subpass have to keep their contents
+                let preserve_attachments = uses.iter()
+                    .enumerate()
+                    .filter(|(_, (first, last))| *first < index && index < *last)
+                    .filter(|(attachment, _)| {
+                        let attachment = *attachment as u32;
+                        !color_attachments.iter().chain(depth_stencil_attachment.iter()).chain(input_attachments.iter()).chain(resolve_attachments.iter())
+                            .any(|reference| reference.attachment == attachment)
+                    })
+                    .map(|(attachment, _)| attachment as u32)
+                    .collect();
+
+                SubpassDescription {
+                    color_attachments,
+                    input_attachments,
+                    resolve_attachments,
+                    preserve_attachments,
+                    depth_stencil_attachment,
+                }
+            })
+            .collect();
+
+        let dependencies = dependencies.iter()
+            .map(|dependency| SubpassDependencyDescription {
+                src_subpass: dependency.src_subpass,
+                dst_subpass: dependency.dst_subpass,
+                src_stage_mask: dependency.src_stage_mask,
+                dst_stage_mask: dependency.dst_stage_mask,
+                src_access_mask: dependency.src_access_mask,
+                dst_access_mask: dependency.dst_access_mask,
+                dependency_flags: vk::DependencyFlags::BY_REGION,
+            })
+            .collect();
+
+        let render_pass_description = RenderPassDescription {
+            attachments,
+            subpasses: subpass_descriptions,
+            dependencies,
+        };
+
+        // pipelines have to rasterize with the sample count of the attachments they render to
+        let sample_counts: Vec<vk::SampleCountFlags> = subpasses.iter()
+            .map(|subpass| self.resources.get_image(subpass.get_attachments()[0].image.id).samples)
+            .collect();
+
+        let timestamp_queries: Vec<Option<(vk::QueryPool, u32)>> = subpasses.iter()
+            .map(|subpass| self.get_timestamp_query(subpass.id))
+            .collect();
+
+        // later frames with the same passes and images reuse the render pass and framebuffer
+        let (render_pass, framebuffer) = self.render_pass_cache.get(self.device, render_pass_description, &images)?;
+
+        for (index, subpass) in subpasses.iter().enumerate() {
+            jobs.push(RecordingJob {
+                id: subpass.id,
+                executor: self.executors.remove(&subpass.id).expect("pass was executed twice"),
+                queue_family_index,
+                subpass: Some(SubpassInfo {
+                    render_pass: render_pass.get_inner(),
+                    framebuffer: framebuffer.get_inner(),
+                    index: index as u32,
+                    color_attachment_count: subpass.color_attachments.len() as u32,
+                    width: framebuffer.get_width(),
+                    height: framebuffer.get_height(),
+                }),
+                sample_count: sample_counts[index],
+                timestamp_query: timestamp_queries[index],
+            });
+        }
+
+        self.render_pass_instances.insert(subpasses[0].id, RenderPassInstance {
+            render_pass: render_pass.get_inner(),
+            framebuffer: framebuffer.get_inner(),
+            width: framebuffer.get_width(),
+            height: framebuffer.get_height(),
+            clear_values,
+            passes: subpasses.iter().map(|subpass| subpass.id).collect(),
+        });
+
+        Ok(())
+    }
+}
+
+/// Records passes into secondary command buffers, allocated from command pools that only the current thread uses
+fn record_jobs(
+    device: &Device,
+    resources: &PhysicalResources,
+    pipeline_cache: &Mutex<PipelineCache>,
+    jobs: Vec<RecordingJob>,
+    mut command_pools: HashMap<u32, CommandPool>,
+) -> Result<(Vec<(u32, CommandBuffer)>, FrameResourceBuilder), Error> {
+
+    let mut frame_resources = FrameResources::builder();
+    let mut command_buffers = Vec::new();
+
+    for job in jobs {
+        let command_pool = command_pools.get_mut(&job.queue_family_index).unwrap();
+        let mut command_buffer = command_pool.allocate_command_buffers(1, false)?.remove(0);
+        command_buffer.begin_secondary(job.subpass.map(|subpass| (subpass.render_pass, subpass.index, subpass.framebuffer)))?;
+
+        if let Some((query_pool, query)) = job.timestamp_query {
+            command_buffer.write_timestamp(vk::PipelineStageFlags::TOP_OF_PIPE, query_pool, query);
+        }
+
+        job.executor.execute(&mut ExecuteContext::new(
+            device,
+            &mut command_buffer,
+            resources,
+            pipeline_cache,
+            &mut frame_resources,
+            job.subpass,
+            job.sample_count,
+        ));
+
+        if let Some((query_pool, query)) = job.timestamp_query {
+            command_buffer.write_timestamp(vk::PipelineStageFlags::BOTTOM_OF_PIPE, query_pool, query + 1);
+        }
+
+        command_buffer.end()?;
+        command_buffers.push((job.id, command_buffer));
+    }
+
+    command_pools.into_iter().for_each(|(_, command_pool)| frame_resources.add_command_pool(command_pool));
+
+    Ok((command_buffers, frame_resources))
+}
+
+/// The mip levels and layers of an image in all aspects of its format
+pub(crate) fn get_subresource_range(format: vk::Format, range: &ImageSubresourceRange) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: get_aspect_mask(format),
+        base_mip_level: range.base_mip_level,
+        level_count: range.level_count,
+        base_array_layer: range.base_array_layer,
+        layer_count: range.layer_count,
+    }
+}
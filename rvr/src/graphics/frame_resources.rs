@@ -1,10 +1,17 @@
-use ash::vk;
-use crate::graphics::Resources;
+use crate::Error;
+use crate::graphics::{QueueType, Resources, TimestampQueries};
 use crate::graphics::vulkan::*;
 
 pub struct FrameResourceBuilder {
     semaphores: Vec<Semaphore>,
     command_pools: Vec<CommandPool>,
+    images: Vec<Image>,
+    buffers: Vec<Buffer>,
+    memories: Vec<Memory>,
+    image_views: Vec<ImageView>,
+    render_passes: Vec<RenderPass>,
+    framebuffers: Vec<Framebuffer>,
+    timestamp_queries: Option<TimestampQueries>,
 }
 
 impl FrameResourceBuilder {
@@ -12,6 +19,13 @@ impl FrameResourceBuilder {
         Self {
             semaphores: Vec::new(),
             command_pools: Vec::new(),
+            images: Vec::new(),
+            buffers: Vec::new(),
+            memories: Vec::new(),
+            image_views: Vec::new(),
+            render_passes: Vec::new(),
+            framebuffers: Vec::new(),
+            timestamp_queries: None,
         }
     }
 
@@ -23,19 +37,74 @@ impl FrameResourceBuilder {
         self.command_pools.push(command_pool);
     }
 
-    pub fn build(self, fence: Fence) -> FrameResources {
+    pub fn add_image(&mut self, image: Image) {
+        self.images.push(image);
+    }
+
+    pub fn add_buffer(&mut self, buffer: Buffer) {
+        self.buffers.push(buffer);
+    }
+
+    pub fn add_memory(&mut self, memory: Memory) {
+        self.memories.push(memory);
+    }
+
+    pub fn add_image_view(&mut self, image_view: ImageView) {
+        self.image_views.push(image_view);
+    }
+
+    pub fn add_render_pass(&mut self, render_pass: RenderPass) {
+        self.render_passes.push(render_pass);
+    }
+
+    pub fn add_framebuffer(&mut self, framebuffer: Framebuffer) {
+        self.framebuffers.push(framebuffer);
+    }
+
+    pub(crate) fn set_timestamp_queries(&mut self, timestamp_queries: TimestampQueries) {
+        self.timestamp_queries = Some(timestamp_queries);
+    }
+
+    /// Takes over the resources another builder collected, e.g. on a worker thread
+    pub fn append(&mut self, other: FrameResourceBuilder) {
+        self.semaphores.extend(other.semaphores);
+        self.command_pools.extend(other.command_pools);
+        self.images.extend(other.images);
+        self.buffers.extend(other.buffers);
+        self.memories.extend(other.memories);
+        self.image_views.extend(other.image_views);
+        self.render_passes.extend(other.render_passes);
+        self.framebuffers.extend(other.framebuffers);
+    }
+
+    /// The frame is finished once the timelines of the queues reached the values of its last submissions
+    pub fn build(self, timeline_values: Vec<(QueueType, u64)>) -> FrameResources {
         FrameResources::new(
-            fence,
+            timeline_values,
             self.semaphores,
             self.command_pools,
+            self.images,
+            self.buffers,
+            self.memories,
+            self.image_views,
+            self.render_passes,
+            self.framebuffers,
+            self.timestamp_queries,
         )
     }
 }
 
 pub struct FrameResources {
-    fence: Fence,
+    timeline_values: Vec<(QueueType, u64)>,
     semaphores: Vec<Semaphore>,
     command_pools: Vec<CommandPool>,
+    images: Vec<Image>,
+    buffers: Vec<Buffer>,
+    memories: Vec<Memory>,
+    image_views: Vec<ImageView>,
+    render_passes: Vec<RenderPass>,
+    framebuffers: Vec<Framebuffer>,
+    timestamp_queries: Option<TimestampQueries>,
 }
 
 impl FrameResources {
@@ -44,24 +113,68 @@ impl FrameResources {
     }
     
     fn new(
-        fence: Fence,
+        timeline_values: Vec<(QueueType, u64)>,
         semaphores: Vec<Semaphore>,
         command_pools: Vec<CommandPool>,
+        images: Vec<Image>,
+        buffers: Vec<Buffer>,
+        memories: Vec<Memory>,
+        image_views: Vec<ImageView>,
+        render_passes: Vec<RenderPass>,
+        framebuffers: Vec<Framebuffer>,
+        timestamp_queries: Option<TimestampQueries>,
     ) -> Self {
         Self {
-            fence,
+            timeline_values,
             semaphores,
             command_pools,
+            images,
+            buffers,
+            memories,
+            image_views,
+            render_passes,
+            framebuffers,
+            timestamp_queries,
         }
     }
 
-    pub fn get_fence(&self) -> &Fence {
-        &self.fence
+    pub fn is_finished(&self, device: &Device) -> Result<bool, Error> {
+        for (queue, value) in self.timeline_values.iter() {
+            if !queue.get_queue(device).get_timeline().is_completed(*value)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// The queries can only be read once the frame is finished
+    pub(crate) fn take_timestamp_queries(&mut self) -> Option<TimestampQueries> {
+        self.timestamp_queries.take()
+    }
+
+    /// Blocks until all submissions of the frame are finished
+    pub fn wait(&self, device: &Device) -> Result<(), Error> {
+        for (queue, value) in self.timeline_values.iter() {
+            queue.get_queue(device).get_timeline().wait(*value)?;
+        }
+
+        Ok(())
     }
 
     pub fn recycle(self, resources: &mut Resources) {
+        // framebuffers reference views and render passes, so they go first
+        drop(self.framebuffers);
+        drop(self.render_passes);
+        drop(self.image_views);
+        drop(self.images);
+        drop(self.buffers);
+        drop(self.memories);
+
         self.semaphores.into_iter().for_each(|semaphore| resources.return_semaphore(semaphore));
         self.command_pools.into_iter().for_each(|command_pool| resources.return_command_pool(command_pool));
-        resources.return_fence(self.fence);
+        if let Some(timestamp_queries) = self.timestamp_queries {
+            resources.return_query_pool(timestamp_queries.into_query_pool());
+        }
     }
 }
@@ -0,0 +1,221 @@
+use ash::vk;
+use serde_derive::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    time::Duration,
+};
+use crate::{
+    Error,
+    graphics::{QueueType, Resources},
+    graphics::vulkan::*,
+};
+
+// enough frames to see spikes in a trace, without growing the history forever
+const MAX_RECORDED_FRAMES: usize = 120;
+
+/// The GPU time of a pass, the start is relative to the first timestamp the profiler read
+#[derive(Debug, Clone)]
+pub struct PassTiming {
+    pub name: String,
+    pub queue: QueueType,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameTimings {
+    pub frame: u64,
+    pub passes: Vec<PassTiming>,
+}
+
+/// The queries a frame writes a begin and an end timestamp of every pass to
+pub(crate) struct TimestampQueries {
+    frame: u64,
+    query_pool: QueryPool,
+    passes: Vec<(String, QueueType)>,
+}
+
+impl TimestampQueries {
+    pub(crate) fn get_query_pool(&self) -> vk::QueryPool {
+        self.query_pool.get_inner()
+    }
+
+    pub(crate) fn into_query_pool(self) -> QueryPool {
+        self.query_pool
+    }
+}
+
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    cat: &'static str,
+    ph: &'static str,
+    pid: u32,
+    tid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    args: HashMap<&'static str, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: Vec<TraceEvent<'a>>,
+    display_time_unit: &'static str,
+}
+
+/// Measures the GPU time of every pass with timestamp queries, results arrive once a frame is finished
+pub struct GpuProfiler {
+    enabled: bool,
+    timestamp_period: f64,
+    origin: Option<u64>,
+    next_frame: u64,
+    frames: VecDeque<FrameTimings>,
+}
+
+impl GpuProfiler {
+    pub(crate) fn new(device: &Device, enabled: bool) -> Self {
+        Self {
+            enabled,
+            timestamp_period: device.get_timestamp_period() as f64,
+            origin: None,
+            next_frame: 0,
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Assigns two queries to every pass that runs on a queue with timestamp support, keyed by pass id
+    pub(crate) fn begin_frame(
+        &mut self,
+        device: &Device,
+        resources: &mut Resources,
+        passes: Vec<(u32, String, QueueType)>,
+    ) -> Result<Option<(TimestampQueries, HashMap<u32, u32>)>, Error> {
+
+        let passes: Vec<(u32, String, QueueType)> = passes.into_iter()
+            .filter(|(_, _, queue)| device.supports_timestamps(queue.get_queue(device)))
+            .collect();
+
+        if !self.enabled || passes.is_empty() {
+            return Ok(None);
+        }
+
+        let query_pool = resources.get_query_pool(device, passes.len() as u32 * 2)?;
+        let queries = passes.iter()
+            .enumerate()
+            .map(|(index, (id, _, _))| (*id, index as u32 * 2))
+            .collect();
+
+        let frame = self.next_frame;
+        self.next_frame += 1;
+
+        Ok(Some((TimestampQueries {
+            frame,
+            query_pool,
+            passes: passes.into_iter().map(|(_, name, queue)| (name, queue)).collect(),
+        }, queries)))
+    }
+
+    /// Reads the timestamps of a finished frame and hands the query pool back for reuse
+    pub(crate) fn resolve(&mut self, queries: TimestampQueries, resources: &mut Resources) -> Result<(), Error> {
+        let timestamps = queries.query_pool.get_timestamps(0, queries.passes.len() as u32 * 2)?;
+
+        if let Some(timestamps) = timestamps {
+            let origin = *self.origin.get_or_insert_with(|| timestamps.iter().copied().min().unwrap_or(0));
+            let to_duration = |ticks: u64| Duration::from_nanos((ticks as f64 * self.timestamp_period) as u64);
+
+            let passes = queries.passes.iter()
+                .enumerate()
+                .map(|(index, (name, queue))| {
+                    let begin = timestamps[index * 2];
+                    let end = timestamps[index * 2 + 1];
+
+                    PassTiming {
+                        name: name.clone(),
+                        queue: *queue,
+                        start: to_duration(begin.saturating_sub(origin)),
+                        duration: to_duration(end.saturating_sub(begin)),
+                    }
+                })
+                .collect();
+
+            if self.frames.len() == MAX_RECORDED_FRAMES {
+                self.frames.pop_front();
+            }
+            self.frames.push_back(FrameTimings { frame: queries.frame, passes });
+        }
+
+        resources.return_query_pool(queries.into_query_pool());
+
+        Ok(())
+    }
+
+    /// The timings of the last finished frame
+    pub fn get_last_frame(&self) -> Option<&FrameTimings> {
+        self.frames.back()
+    }
+
+    /// The GPU time of the pass in the last finished frame
+    pub fn get_pass_time(&self, name: &str) -> Option<Duration> {
+        self.get_last_frame()?
+            .passes.iter()
+            .find(|pass| pass.name == name)
+            .map(|pass| pass.duration)
+    }
+
+    /// The timings of the recently finished frames, oldest first
+    pub fn get_frames(&self) -> impl Iterator<Item = &FrameTimings> {
+        self.frames.iter()
+    }
+
+    /// Writes the recorded frames in the Chrome trace event format, every queue is shown as its own thread
+    pub fn write_chrome_trace(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        let get_thread = |queue: QueueType| match queue {
+            QueueType::Graphics => 0,
+            QueueType::Compute => 1,
+        };
+
+        let mut trace_events: Vec<TraceEvent> = [QueueType::Graphics, QueueType::Compute].iter()
+            .map(|queue| TraceEvent {
+                name: "thread_name",
+                cat: "__metadata",
+                ph: "M",
+                pid: 0,
+                tid: get_thread(*queue),
+                ts: None,
+                dur: None,
+                args: vec![("name", format!("{:?} Queue", queue))].into_iter().collect(),
+            })
+            .collect();
+
+        for frame in self.frames.iter() {
+            for pass in frame.passes.iter() {
+                trace_events.push(TraceEvent {
+                    name: &pass.name,
+                    cat: "gpu",
+                    ph: "X",
+                    pid: 0,
+                    tid: get_thread(pass.queue),
+                    ts: Some(pass.start.as_nanos() as f64 / 1000.0),
+                    dur: Some(pass.duration.as_nanos() as f64 / 1000.0),
+                    args: vec![("frame", frame.frame.to_string())].into_iter().collect(),
+                });
+            }
+        }
+
+        serde_json::to_writer_pretty(writer, &Trace { trace_events, display_time_unit: "ns" })?;
+
+        Ok(())
+    }
+}
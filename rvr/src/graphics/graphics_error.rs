@@ -1,10 +1,14 @@
 use thiserror::Error;
+use crate::assets::Shader;
 
 #[derive(Error, Debug)]
 pub enum GraphicsError {
     #[error("No suitable graphics device found")]
     NoSuitableDevice,
 
+    #[error("No device with a graphics queue matches {0}, the available devices are {}", .1.join(", "))]
+    NoPreferredDevice(String, Vec<String>),
+
     #[error("No suitable graphics queue found")]
     NoSuitableGraphicsQueue,
 
@@ -16,4 +20,98 @@ pub enum GraphicsError {
 
     #[error("No suitable surface format found")]
     NoSuitableSurfaceFormat,
+
+    #[error("Device was created without a surface")]
+    NoSurface,
+
+    #[error("Renderer has no offscreen target to read back")]
+    NoReadbackTarget,
+
+    #[error("Compute pass {0} can not have attachments")]
+    AttachmentInComputePass(String),
+
+    #[error("Graphics pass {0} has no color or depth stencil attachment")]
+    GraphicsPassWithoutAttachments(String),
+
+    #[error("Pass {0} has more than one depth stencil attachment")]
+    MultipleDepthStencilAttachments(String),
+
+    #[error("Attachments of pass {0} differ in size")]
+    AttachmentSizeMismatch(String),
+
+    #[error("Attachments of pass {0} differ in sample count")]
+    AttachmentSampleCountMismatch(String),
+
+    #[error("Pass {0} renders to more than one mip level or layer of an attachment")]
+    MultipleSubresourceAttachment(String),
+
+    #[error("Pass {pass} uses mip levels or layers that {resource} doesn't have")]
+    InvalidSubresource {
+        pass: String,
+        resource: String,
+    },
+
+    #[error("Image {0} has an invalid combination of type, extent, layers and mip levels")]
+    InvalidImageDescription(String),
+
+    #[error("Pass {0} resolves an image that is not one of its color attachments")]
+    ResolveOfNonColorAttachment(String),
+
+    #[error("Pass {0} resolves a multisampled attachment into an image of a different size, format or sample count")]
+    InvalidResolveTarget(String),
+
+    #[error("Pass {pass} writes version {version} of {resource}, but version {latest} already exists")]
+    OutdatedVersionWrite {
+        pass: String,
+        resource: String,
+        version: u32,
+        latest: u32,
+    },
+
+    #[error("Passes {first_pass} and {second_pass} both write version {version} of {resource}")]
+    ConflictingWrites {
+        first_pass: String,
+        second_pass: String,
+        resource: String,
+        version: u32,
+    },
+
+    #[error("No physical buffer was given for imported buffer {0}")]
+    MissingImportedBuffer(String),
+
+    #[error("Physical buffer of imported buffer {0} is smaller than its description")]
+    ImportedBufferTooSmall(String),
+
+    #[error("Result image {0} is never produced")]
+    UnproducedResult(String),
+
+    #[error("Passes {} depend on each other", .0.join(", "))]
+    DependencyCycle(Vec<String>),
+
+    #[error("Persistent image {0} was added twice")]
+    DuplicatePersistentImage(String),
+
+    #[error("Shader {0:?} is not resident")]
+    ShaderNotResident(Shader),
+
+    #[error("Descriptor sets or push constants were used before a pipeline was bound")]
+    NoBoundPipeline,
+
+    #[error("Shader {0:?} does not match the bind point of the pass")]
+    ShaderBindPointMismatch(Shader),
+
+    #[error("Image already has dedicated memory and can not be aliased")]
+    ImageHasDedicatedMemory,
+
+    #[error("Aliased images have no memory type in common")]
+    NoAliasableMemoryType,
+
+    #[error("Window handle is not supported on this platform")]
+    UnsupportedWindowHandle,
+
+    #[error("Invalid {0} {1:?} in configuration")]
+    InvalidConfiguration(String, String),
+
+    #[error("Validation failed: {0}")]
+    ValidationFailed(String),
 }
@@ -0,0 +1,82 @@
+use super::{ImageLayout, PipelineBindPoint};
+use ash::vk;
+
+/// The ways a pass can use an image
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ImageAccess {
+    ColorAttachment,
+    DepthStencilAttachment,
+    ShaderSample,
+    /// Reading the same pixel another subpass of the render pass wrote
+    InputAttachment,
+    StorageRead,
+    StorageWrite,
+    Present,
+}
+
+impl ImageAccess {
+    pub fn get_layout(&self) -> ImageLayout {
+        match self {
+            ImageAccess::ColorAttachment => ImageLayout::ColorAttachment,
+            ImageAccess::DepthStencilAttachment => ImageLayout::DepthStencilAttachment,
+            ImageAccess::ShaderSample | ImageAccess::InputAttachment => ImageLayout::ShaderSample,
+            ImageAccess::StorageRead | ImageAccess::StorageWrite => ImageLayout::General,
+            ImageAccess::Present => ImageLayout::Present,
+        }
+    }
+
+    /// Shader accesses happen in the fragment shader of graphics passes and the compute shader of compute passes
+    pub fn get_stage_mask(&self, bind_point: PipelineBindPoint) -> vk::PipelineStageFlags {
+        let shader_stage = match bind_point {
+            PipelineBindPoint::Graphics => vk::PipelineStageFlags::FRAGMENT_SHADER,
+            PipelineBindPoint::Compute => vk::PipelineStageFlags::COMPUTE_SHADER,
+        };
+
+        match self {
+            ImageAccess::ColorAttachment => vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ImageAccess::DepthStencilAttachment => vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            ImageAccess::ShaderSample | ImageAccess::StorageRead | ImageAccess::StorageWrite => shader_stage,
+            ImageAccess::InputAttachment => vk::PipelineStageFlags::FRAGMENT_SHADER,
+            ImageAccess::Present => vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        }
+    }
+
+    /// All memory accesses, used as the destination of a barrier
+    pub fn get_access_mask(&self) -> vk::AccessFlags {
+        match self {
+            ImageAccess::ColorAttachment => vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ImageAccess::DepthStencilAttachment => vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ImageAccess::ShaderSample | ImageAccess::StorageRead => vk::AccessFlags::SHADER_READ,
+            ImageAccess::InputAttachment => vk::AccessFlags::INPUT_ATTACHMENT_READ,
+            ImageAccess::StorageWrite => vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ImageAccess::Present => vk::AccessFlags::empty(),
+        }
+    }
+
+    /// Only the writes, which have to be made available before anyone else touches the image
+    pub fn get_write_access_mask(&self) -> vk::AccessFlags {
+        match self {
+            ImageAccess::ColorAttachment => vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ImageAccess::DepthStencilAttachment => vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ImageAccess::StorageWrite => vk::AccessFlags::SHADER_WRITE,
+            ImageAccess::ShaderSample | ImageAccess::InputAttachment | ImageAccess::StorageRead | ImageAccess::Present => vk::AccessFlags::empty(),
+        }
+    }
+
+    pub fn is_write(&self) -> bool {
+        !self.get_write_access_mask().is_empty()
+    }
+
+    /// How an image has to be created to allow this access
+    pub fn get_usage(&self) -> vk::ImageUsageFlags {
+        match self {
+            ImageAccess::ColorAttachment => vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            ImageAccess::DepthStencilAttachment => vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            ImageAccess::ShaderSample => vk::ImageUsageFlags::SAMPLED,
+            ImageAccess::InputAttachment => vk::ImageUsageFlags::INPUT_ATTACHMENT,
+            ImageAccess::StorageRead | ImageAccess::StorageWrite => vk::ImageUsageFlags::STORAGE,
+            // presenting without a swapchain copies the image
+            ImageAccess::Present => vk::ImageUsageFlags::TRANSFER_SRC,
+        }
+    }
+}
@@ -9,6 +9,41 @@ pub enum ImageFormat {
     D32_SFLOAT,
 }
 
+impl ImageFormat {
+    pub fn is_depth_format(&self) -> bool {
+        match self {
+            ImageFormat::D32_SFLOAT => true,
+            _ => false,
+        }
+    }
+
+    pub fn get_bytes_per_pixel(&self) -> usize {
+        match self {
+            ImageFormat::B8G8R8A8_SRGB => 4,
+            ImageFormat::R8G8B8A8_SRGB => 4,
+            ImageFormat::R32G32B32A32_SFLOAT => 16,
+            ImageFormat::R32G32B32_SFLOAT => 12,
+            ImageFormat::R32G32_SFLOAT => 8,
+            ImageFormat::D32_SFLOAT => 4,
+        }
+    }
+}
+
+use ash::vk;
+
+impl Into<vk::Format> for ImageFormat {
+    fn into(self) -> vk::Format {
+        match self {
+            ImageFormat::B8G8R8A8_SRGB => vk::Format::B8G8R8A8_SRGB,
+            ImageFormat::R8G8B8A8_SRGB => vk::Format::R8G8B8A8_SRGB,
+            ImageFormat::R32G32B32A32_SFLOAT => vk::Format::R32G32B32A32_SFLOAT,
+            ImageFormat::R32G32B32_SFLOAT => vk::Format::R32G32B32_SFLOAT,
+            ImageFormat::R32G32_SFLOAT => vk::Format::R32G32_SFLOAT,
+            ImageFormat::D32_SFLOAT => vk::Format::D32_SFLOAT,
+        }
+    }
+}
+
 use sdl2::pixels::PixelFormatEnum;
 
 impl Into<ImageFormat> for PixelFormatEnum {
@@ -4,5 +4,21 @@ pub enum ImageLayout {
     ColorAttachment,
     DepthStencilAttachment,
     ShaderSample,
+    General,
     Present,
 }
+
+use ash::vk;
+
+impl Into<vk::ImageLayout> for ImageLayout {
+    fn into(self) -> vk::ImageLayout {
+        match self {
+            ImageLayout::Unknown => vk::ImageLayout::UNDEFINED,
+            ImageLayout::ColorAttachment => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ImageLayout::DepthStencilAttachment => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            ImageLayout::ShaderSample => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ImageLayout::General => vk::ImageLayout::GENERAL,
+            ImageLayout::Present => vk::ImageLayout::PRESENT_SRC_KHR,
+        }
+    }
+}
@@ -4,3 +4,15 @@ pub enum ImageType {
     Type2D,
     Type3D,
 }
+
+use ash::vk;
+
+impl Into<vk::ImageType> for ImageType {
+    fn into(self) -> vk::ImageType {
+        match self {
+            ImageType::Type1D => vk::ImageType::TYPE_1D,
+            ImageType::Type2D => vk::ImageType::TYPE_2D,
+            ImageType::Type3D => vk::ImageType::TYPE_3D,
+        }
+    }
+}
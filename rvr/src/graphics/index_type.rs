@@ -0,0 +1,16 @@
+use ash::vk;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum IndexType {
+    U16,
+    U32,
+}
+
+impl Into<vk::IndexType> for IndexType {
+    fn into(self) -> vk::IndexType {
+        match self {
+            IndexType::U16 => vk::IndexType::UINT16,
+            IndexType::U32 => vk::IndexType::UINT32,
+        }
+    }
+}
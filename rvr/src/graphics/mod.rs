@@ -1,24 +1,47 @@
 pub mod rendergraph;
 
+mod attachment_ops;
+mod buffer_access;
 mod configuration;
+mod image_access;
 mod image_format;
 mod image_layout;
 mod image_type;
+mod index_type;
+mod present_mode;
+mod queue_type;
 mod sample_count;
 mod renderer;
 mod vulkan;
 mod graphics_error;
 mod resources;
 mod frame_resources;
+mod frame_recorder;
+mod gpu_profiler;
+mod render_pass_cache;
+mod pipeline_cache;
 mod transfer_device;
+mod validation;
 
 pub use renderer::*;
+pub use attachment_ops::*;
+pub use buffer_access::*;
 pub use configuration::*;
+pub use image_access::*;
 pub use image_format::*;
 pub use image_layout::*;
 pub use image_type::*;
+pub use index_type::*;
+pub use present_mode::*;
+pub use queue_type::*;
 pub use sample_count::*;
 pub use graphics_error::*;
 pub use resources::*;
 pub use frame_resources::*;
+pub use frame_recorder::*;
+pub use gpu_profiler::*;
+pub use render_pass_cache::*;
+pub use pipeline_cache::*;
 pub use transfer_device::*;
+pub use validation::*;
+pub use vulkan::{PipelineBindPoint, ShaderDescription};
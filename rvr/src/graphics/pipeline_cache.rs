@@ -0,0 +1,73 @@
+use ash::vk;
+use std::collections::{HashMap, hash_map::Entry};
+use crate::{
+    Error,
+    assets::Shader,
+    graphics::*,
+    graphics::vulkan::*,
+};
+
+/// Descriptions of the loaded shaders and the pipelines that were created from them
+pub struct PipelineCache {
+    shaders: HashMap<Shader, ShaderDescription>,
+    pipelines: HashMap<(Shader, vk::RenderPass, u32), Pipeline>,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self {
+            shaders: HashMap::new(),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    pub fn add_shader(&mut self, shader: Shader, description: ShaderDescription) {
+        self.shaders.insert(shader, description);
+    }
+
+    /// Returns the pipeline of the shader for a subpass of the render pass, creating it on first use
+    pub fn get_graphics_pipeline(
+        &mut self,
+        device: &Device,
+        shader: Shader,
+        render_pass: vk::RenderPass,
+        subpass: u32,
+        color_attachment_count: u32,
+        samples: vk::SampleCountFlags,
+    ) -> Result<&Pipeline, Error> {
+
+        let description = self.shaders.get(&shader).ok_or(GraphicsError::ShaderNotResident(shader))?;
+        if description.get_bind_point() != PipelineBindPoint::Graphics {
+            return Err(GraphicsError::ShaderBindPointMismatch(shader).into());
+        }
+
+        // render passes stay in their cache, so their handles can't be reused by another one
+        let pipeline = match self.pipelines.entry((shader, render_pass, subpass)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let pipeline = device.create_graphics_pipeline(description, render_pass, subpass, color_attachment_count, samples)?;
+                entry.insert(pipeline)
+            },
+        };
+
+        Ok(pipeline)
+    }
+
+    /// Returns the compute pipeline of the shader, creating it on first use
+    pub fn get_compute_pipeline(&mut self, device: &Device, shader: Shader) -> Result<&Pipeline, Error> {
+        let description = self.shaders.get(&shader).ok_or(GraphicsError::ShaderNotResident(shader))?;
+        if description.get_bind_point() != PipelineBindPoint::Compute {
+            return Err(GraphicsError::ShaderBindPointMismatch(shader).into());
+        }
+
+        let pipeline = match self.pipelines.entry((shader, vk::RenderPass::null(), 0)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let pipeline = device.create_compute_pipeline(description)?;
+                entry.insert(pipeline)
+            },
+        };
+
+        Ok(pipeline)
+    }
+}
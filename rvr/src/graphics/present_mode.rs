@@ -0,0 +1,38 @@
+use ash::vk;
+use serde_derive::*;
+use std::str::FromStr;
+use crate::{Error, graphics::GraphicsError};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresentMode {
+    Fifo,
+    FifoRelaxed,
+    Mailbox,
+    Immediate,
+}
+
+impl Into<vk::PresentModeKHR> for PresentMode {
+    fn into(self) -> vk::PresentModeKHR {
+        match self {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
+
+impl FromStr for PresentMode {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Error> {
+        match value.trim().to_lowercase().as_str() {
+            "fifo" => Ok(PresentMode::Fifo),
+            "fifo_relaxed" => Ok(PresentMode::FifoRelaxed),
+            "mailbox" => Ok(PresentMode::Mailbox),
+            "immediate" => Ok(PresentMode::Immediate),
+            _ => Err(GraphicsError::InvalidConfiguration("present mode".to_owned(), value.to_owned()).into()),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use crate::graphics::vulkan::{Device, Queue};
+
+/// The queues the renderer submits work to
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum QueueType {
+    Graphics,
+    Compute,
+}
+
+impl QueueType {
+    pub(crate) fn get_queue<'a>(&self, device: &'a Device) -> &'a Queue {
+        match self {
+            QueueType::Graphics => &device.graphics_queue,
+            QueueType::Compute => &device.compute_queue,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use ash::vk;
+use std::collections::{HashMap, hash_map::Entry};
+use crate::{
+    Error,
+    graphics::*,
+    graphics::rendergraph::ImageSubresourceRange,
+    graphics::vulkan::*,
+};
+
+/// A framebuffer together with the views of its attachments
+struct CachedFramebuffer {
+    framebuffer: Framebuffer,
+    image_views: Vec<ImageView>,
+}
+
+/// Render passes and framebuffers that get reused by later frames
+pub struct RenderPassCache {
+    render_passes: HashMap<RenderPassDescription, RenderPass>,
+    framebuffers: HashMap<(vk::RenderPass, Vec<(vk::Image, ImageSubresourceRange)>), CachedFramebuffer>,
+    created_framebuffers: usize,
+}
+
+impl RenderPassCache {
+    pub fn new() -> Self {
+        Self {
+            render_passes: HashMap::new(),
+            framebuffers: HashMap::new(),
+            created_framebuffers: 0,
+        }
+    }
+
+    /// Returns the render pass for the description and a framebuffer with the subresources as attachments, creating them on first use
+    pub fn get(&mut self, device: &Device, description: RenderPassDescription, attachments: &[(PhysicalImage, ImageSubresourceRange)]) -> Result<(&RenderPass, &Framebuffer), Error> {
+        let render_pass = match self.render_passes.entry(description) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let render_pass = device.create_render_pass(entry.key())?;
+                entry.insert(render_pass)
+            },
+        };
+
+        let key = (render_pass.get_inner(), attachments.iter().map(|(image, range)| (image.image, *range)).collect());
+        let created_framebuffers = &mut self.created_framebuffers;
+        let cached = match self.framebuffers.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut image_views = Vec::new();
+                for (image, range) in attachments {
+                    image_views.push(device.create_image_view(image.image, image.format, get_subresource_range(image.format, range))?);
+                }
+
+                // the framebuffer has the size of the mip level it renders to
+                let framebuffer = {
+                    let (image, range) = &attachments[0];
+                    let views: Vec<&ImageView> = image_views.iter().collect();
+                    let width = (image.width >> range.base_mip_level).max(1);
+                    let height = (image.height >> range.base_mip_level).max(1);
+                    device.create_framebuffer(render_pass, &views, width, height)?
+                };
+
+                *created_framebuffers += 1;
+                entry.insert(CachedFramebuffer {
+                    framebuffer,
+                    image_views,
+                })
+            },
+        };
+
+        Ok((render_pass, &cached.framebuffer))
+    }
+
+    /// How many framebuffers were created so far, a frame that hits the cache doesn't add any
+    pub fn get_created_framebuffer_count(&self) -> usize {
+        self.created_framebuffers
+    }
+
+    /// Forgets the framebuffers that use one of the images, frames in flight might still use them
+    pub fn evict_images(&mut self, images: &[vk::Image], frame_resources: &mut FrameResourceBuilder) {
+        let keys: Vec<(vk::RenderPass, Vec<(vk::Image, ImageSubresourceRange)>)> = self.framebuffers.keys()
+            .filter(|(_, attachments)| attachments.iter().any(|(image, _)| images.contains(image)))
+            .cloned()
+            .collect();
+
+        for key in keys {
+            let cached = self.framebuffers.remove(&key).unwrap();
+            frame_resources.add_framebuffer(cached.framebuffer);
+            cached.image_views.into_iter().for_each(|image_view| frame_resources.add_image_view(image_view));
+        }
+    }
+}
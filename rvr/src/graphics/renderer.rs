@@ -1,43 +1,126 @@
 use raw_window_handle::RawWindowHandle;
-use ash::vk::{
-    Image,
-    Fence,
-    Semaphore,
-};
+use ash::vk;
 use crate::{
     Error,
+    assets::Shader,
     graphics::*,
     graphics::rendergraph::*,
     graphics::vulkan::*,
 };
+use std::collections::{HashMap, VecDeque, hash_map::Entry};
 use std::ffi::CString;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+enum Backbuffer {
+    Swapchain {
+        swapchain: Swapchain,
+        images: Vec<vk::Image>,
+        // the size of the window, only used if the surface doesn't decide the extent itself
+        width: u32,
+        height: u32,
+    },
+    Offscreen {
+        image: Image,
+        readback: Buffer,
+        format: ImageFormat,
+    },
+}
+
+/// An image that keeps its contents between frames
+struct PersistentImage {
+    image: Image,
+    description: ImageDescription,
+    usage: vk::ImageUsageFlags,
+    // the layout and queue the last submitted frame left the image in
+    state: Option<(ImageLayout, QueueType)>,
+}
 
 pub struct Renderer {
     configuration: Configuration,
-    swapchain_images: Vec<Image>,
-    swapchain: Swapchain,
+    backbuffer: Backbuffer,
     resources: Resources,
-    pending_frame_resources: Vec<FrameResources>,
+    pending_frame_resources: VecDeque<FrameResources>,
+    // every frame in flight records into its own transient resources, a slot is only reused once its last frame is finished
+    transient_resources: Vec<Option<TransientResources>>,
+    frame_index: usize,
+    persistent_images: HashMap<String, PersistentImage>,
+    schedule_cache: ScheduleCache,
+    render_pass_cache: RenderPassCache,
+    pipeline_cache: Mutex<PipelineCache>,
+    gpu_profiler: GpuProfiler,
     device: Arc<Device>,
 }
 
 impl Renderer {
-    pub fn create(configuration: Configuration, window_handle: RawWindowHandle) -> Result<Self, Error> {
+    pub fn create(configuration: Configuration, window_handle: RawWindowHandle, width: u32, height: u32) -> Result<Self, Error> {
 
-        let device = Device::create(&CString::new("").unwrap(), 0, window_handle, true)?;
-        let resources = Resources::new(device.create_semaphore_pool()?, device.create_fence_pool()?);
-        let pending_frame_resources = Vec::new();
-        let swapchain = device.create_swapchain(2, 2560, 1440)?;
+        let device = Device::create(&CString::new("").unwrap(), 0, Some(window_handle), configuration.validation, configuration.device.as_ref())?;
+        let resources = Resources::new(device.create_semaphore_pool()?);
+        let pending_frame_resources = VecDeque::new();
+        let present_modes = configuration.get_vk_present_modes();
+        let swapchain = device.create_swapchain(configuration.swapchain_image_count, &present_modes, width, height, None)?;
 
         let swapchain_images = swapchain.get_images()?;
+        let transient_resources = (0..configuration.frames_in_flight).map(|_| None).collect();
+        let gpu_profiler = GpuProfiler::new(&device, configuration.gpu_profiling);
+
+        Ok(Self {
+            configuration,
+            backbuffer: Backbuffer::Swapchain {
+                swapchain,
+                images: swapchain_images,
+                width,
+                height,
+            },
+            resources,
+            pending_frame_resources,
+            transient_resources,
+            frame_index: 0,
+            persistent_images: HashMap::new(),
+            schedule_cache: ScheduleCache::new(),
+            render_pass_cache: RenderPassCache::new(),
+            pipeline_cache: Mutex::new(PipelineCache::new()),
+            gpu_profiler,
+            device: Arc::new(device),
+        })
+    }
+
+    /// Creates a renderer without window, surface or swapchain that renders into an offscreen image
+    pub fn create_headless(configuration: Configuration, width: u32, height: u32) -> Result<Self, Error> {
+
+        let device = Device::create(&CString::new("").unwrap(), 0, None, configuration.validation, configuration.device.as_ref())?;
+        let resources = Resources::new(device.create_semaphore_pool()?);
+        let pending_frame_resources = VecDeque::new();
+
+        let format = ImageFormat::B8G8R8A8_SRGB;
+        let image = device.create_image(&ImageCreateDescription::new_2d(
+            width,
+            height,
+            format.into(),
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+        ))?;
+        let readback = device.create_readback_buffer(width as usize * height as usize * format.get_bytes_per_pixel())?;
+        device.set_object_name(image.get_inner(), "Offscreen Backbuffer")?;
+        device.set_object_name(readback.get_inner(), "Readback")?;
+        let transient_resources = (0..configuration.frames_in_flight).map(|_| None).collect();
+        let gpu_profiler = GpuProfiler::new(&device, configuration.gpu_profiling);
 
         Ok(Self {
             configuration,
-            swapchain_images,
-            swapchain,
+            backbuffer: Backbuffer::Offscreen {
+                image,
+                readback,
+                format,
+            },
             resources,
             pending_frame_resources,
+            transient_resources,
+            frame_index: 0,
+            persistent_images: HashMap::new(),
+            schedule_cache: ScheduleCache::new(),
+            render_pass_cache: RenderPassCache::new(),
+            pipeline_cache: Mutex::new(PipelineCache::new()),
+            gpu_profiler,
             device: Arc::new(device),
         })
     }
@@ -47,82 +130,537 @@ impl Renderer {
         Ok(TransferDevice::new(device))
     }
 
-    pub fn render(&mut self, graph: Graph) -> Result<(), Error> {
-        let schedule = graph.compile_schedule();
+    /// Makes a loaded shader available to the passes of later frames
+    pub fn add_shader(&mut self, shader: Shader, description: ShaderDescription) {
+        self.pipeline_cache.get_mut().unwrap().add_shader(shader, description);
+    }
+
+    pub fn is_headless(&self) -> bool {
+        match self.backbuffer {
+            Backbuffer::Swapchain { .. } => false,
+            Backbuffer::Offscreen { .. } => true,
+        }
+    }
+
+    /// Returns the description of the current backbuffer, the backbuffer of a graph has to be imported with it
+    pub fn get_backbuffer_image_description(&self) -> ImageDescription {
+        let (width, height, format) = match &self.backbuffer {
+            // swapchains are only created with this format
+            Backbuffer::Swapchain { swapchain, .. } => (swapchain.get_extent().width, swapchain.get_extent().height, ImageFormat::B8G8R8A8_SRGB),
+            Backbuffer::Offscreen { image, format, .. } => (image.get_width(), image.get_height(), *format),
+        };
+
+        ImageDescription::new(width, height, format, ImageType::Type2D, SampleCount::Type_1, ImageLayout::Unknown)
+    }
+
+    /// Recreates the swapchain after the window was resized, a headless renderer keeps its size
+    pub fn resize(&mut self, new_width: u32, new_height: u32) -> Result<(), Error> {
+        if let Backbuffer::Swapchain { width, height, .. } = &mut self.backbuffer {
+            *width = new_width;
+            *height = new_height;
+        }
+
+        self.recreate_swapchain()
+    }
+
+    fn recreate_swapchain(&mut self) -> Result<(), Error> {
+        if let Backbuffer::Swapchain { swapchain, images, width, height } = &mut self.backbuffer {
+            // some platforms report an empty extent while minimized, a swapchain can't be created for it
+            let extent = self.device.get_surface_extent()?;
+            let extent = if extent.width == std::u32::MAX {
+                vk::Extent2D { width: *width, height: *height }
+            } else {
+                extent
+            };
+
+            if extent.width == 0 || extent.height == 0 {
+                return Ok(());
+            }
+
+            // frames in flight might still render to the old images
+            self.device.wait_idle()?;
+            for frame_resources in self.pending_frame_resources.drain(..) {
+                retire_frame(&mut self.gpu_profiler, &mut self.resources, frame_resources)?;
+            }
+
+            // the device is idle, so the framebuffers of the old images can be destroyed right away
+            let mut evicted = FrameResources::builder();
+            self.render_pass_cache.evict_images(images, &mut evicted);
+            drop(evicted);
+
+            let present_modes = self.configuration.get_vk_present_modes();
+            let new_swapchain = self.device.create_swapchain(
+                self.configuration.swapchain_image_count,
+                &present_modes,
+                *width,
+                *height,
+                Some(&*swapchain),
+            )?;
+            *images = new_swapchain.get_images()?;
+            *swapchain = new_swapchain;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_gpu_profiler(&self) -> &GpuProfiler {
+        &self.gpu_profiler
+    }
+
+    pub fn get_gpu_profiler_mut(&mut self) -> &mut GpuProfiler {
+        &mut self.gpu_profiler
+    }
+
+    /// Takes the validation messages that arrived since the last call, empty when validation is off
+    pub fn take_validation_messages(&self) -> Vec<ValidationMessage> {
+        self.device.take_validation_messages()
+    }
+
+    /// Waits for all submitted frames and returns the pixels of the last rendered offscreen backbuffer
+    pub fn read_back(&mut self) -> Result<Vec<u8>, Error> {
+        match &self.backbuffer {
+            Backbuffer::Swapchain { .. } => Err(GraphicsError::NoReadbackTarget.into()),
+            Backbuffer::Offscreen { readback, .. } => {
+                self.device.wait_idle()?;
+                self.device.check_validation()?;
+                readback.read()
+            },
+        }
+    }
+
+    /// Creates the persistent images of the graph that don't exist yet or changed, returns the images they replace
+    fn prepare_persistent_images(&mut self, graph: &mut Graph) -> Result<Vec<Image>, Error> {
+        let mut replaced_images = Vec::new();
 
-        for i in (0..self.pending_frame_resources.len()).rev() {
-            let fence = self.pending_frame_resources[i].get_fence();
-            let completed = self.device.get_fence_status(fence.get_inner())?;
+        for (id, name, description) in graph.get_persistent_images() {
+            let usage = graph.get_image_usage(id);
 
-            if completed {
-                let resources = self.pending_frame_resources.remove(i);
-                resources.recycle(&mut self.resources);
+            let previous_usage = match self.persistent_images.get(&name) {
+                Some(image) if image.description == description && image.usage.contains(usage) => {
+                    if let Some((layout, queue)) = image.state {
+                        graph.set_previous_state(id, layout, queue);
+                    }
+                    continue;
+                },
+                Some(image) if image.description == description => image.usage,
+                _ => vk::ImageUsageFlags::empty(),
+            };
+
+            // keeping the usages of earlier frames avoids recreating the image for graphs that alternate
+            let usage = usage | previous_usage;
+            let image = self.device.create_image(&description.get_create_description(usage))?;
+            self.device.set_object_name(image.get_inner(), &name)?;
+
+            // a new image has no contents, so this frame can't read last frame's version
+            graph.discard_contents(id);
+
+            if let Some(previous) = self.persistent_images.insert(name, PersistentImage { image, description, usage, state: None }) {
+                replaced_images.push(previous.image);
             }
         }
 
+        Ok(replaced_images)
+    }
+
+    /// Renders a frame of the graph, the imported buffers have to stay alive until the frame is finished
+    pub fn render(&mut self, mut graph: Graph, backbuffer: ImageHandle, buffers: &[(BufferHandle, PhysicalBuffer)]) -> Result<(), Error> {
+        graph.validate_imported_buffers(buffers)?;
+
+        let replaced_images = self.prepare_persistent_images(&mut graph)?;
+        let persistent_images: Vec<(u32, String)> = graph.get_persistent_images().into_iter()
+            .map(|(id, name, _)| (id, name))
+            .collect();
+
+        // the graph rarely changes its shape, so most frames reuse the schedule of the last one
+        let schedule = self.schedule_cache.get_schedule(&graph)?;
+        let pass_names = graph.get_pass_names();
+        let resource_names = graph.get_resource_names();
+        let executors = graph.into_executors();
+
+        // frames finish in order, so retiring stops at the first one that is still running
+        while let Some(frame_resources) = self.pending_frame_resources.front() {
+            if !frame_resources.is_finished(&self.device)? {
+                break;
+            }
+
+            let frame_resources = self.pending_frame_resources.pop_front().unwrap();
+            retire_frame(&mut self.gpu_profiler, &mut self.resources, frame_resources)?;
+        }
+
+        // a full ring blocks until the oldest frame is finished, so the cpu never runs too far ahead of the gpu
+        while self.pending_frame_resources.len() >= self.configuration.frames_in_flight as usize {
+            let frame_resources = self.pending_frame_resources.pop_front().unwrap();
+            frame_resources.wait(&self.device)?;
+            retire_frame(&mut self.gpu_profiler, &mut self.resources, frame_resources)?;
+        }
+
         let mut frame_resources = FrameResources::builder();
 
-        let acquire_semaphore = self.resources.get_semaphore()?;
-        let submit_semaphore = self.resources.get_semaphore()?;
-        let finish_submit_fence = self.resources.get_fence()?;
-        let command_pool = self.device.create_command_pool(self.device.graphics_queue.get_family_index())?;
-        if let Some(index) = self.swapchain.acquire_next_image(std::u64::MAX, acquire_semaphore.get_inner(), Fence::null())? {
+        // passes in the order they get executed, with the queue they run on
+        let mut passes = Vec::new();
+        for submission in schedule.get_submissions() {
+            for instruction in submission.get_instructions() {
+                let ids = match instruction {
+                    Instruction::ExecutePass { id, .. } => vec![*id],
+                    Instruction::ExecuteSubpasses { subpasses, .. } => subpasses.iter().map(|subpass| subpass.id).collect(),
+                    _ => continue,
+                };
+
+                for id in ids {
+                    passes.push((id, pass_names.get(&id).cloned().unwrap_or_default(), submission.get_queue()));
+                }
+            }
+        }
+        let timestamp_queries = self.gpu_profiler.begin_frame(&self.device, &mut self.resources, passes)?;
+
+        // earlier frames might still use the images that got replaced
+        let replaced: Vec<vk::Image> = replaced_images.iter().map(|image| image.get_inner()).collect();
+        self.render_pass_cache.evict_images(&replaced, &mut frame_resources);
+        replaced_images.into_iter().for_each(|image| frame_resources.add_image(image));
+
+        let mut recorder = FrameRecorder::new(&self.device, executors, &mut self.render_pass_cache, &self.pipeline_cache);
+        recorder.set_names(pass_names, resource_names);
+
+        // the ring above made sure the last frame of this slot is finished
+        let slot = self.frame_index % self.transient_resources.len();
+        self.frame_index += 1;
+        if let Some(transient_resources) = self.transient_resources[slot].take() {
+            recorder.reuse_transient_resources(transient_resources);
+        }
+
+        let timestamp_queries = timestamp_queries.map(|(timestamp_queries, queries)| {
+            recorder.write_timestamps(timestamp_queries.get_query_pool(), queries);
+            timestamp_queries
+        });
+
+        for (id, name) in persistent_images.iter() {
+            let image = &self.persistent_images[name].image;
+            recorder.import_image(*id, PhysicalImage::new(image.get_inner(), image.get_format(), image.get_width(), image.get_height(), image.get_samples()));
+        }
+
+        for (buffer, physical_buffer) in buffers {
+            recorder.import_buffer(buffer.id, *physical_buffer);
+        }
+
+        let mut recreate_swapchain = false;
+        let mut timeline_values = Vec::new();
+
+        match &self.backbuffer {
+            Backbuffer::Swapchain { swapchain, images, .. } => {
+                let acquire_semaphore = self.resources.get_semaphore()?;
+                let submit_semaphore = self.resources.get_semaphore()?;
+
+                if let Some(index) = swapchain.acquire_next_image(std::u64::MAX, acquire_semaphore.get_inner(), vk::Fence::null())? {
 
-            let mut command_buffers = command_pool.allocate_command_buffers(1, true)?;
-            let mut command_buffer = command_buffers.remove(0);
+                    let extent = swapchain.get_extent();
+                    recorder.import_image(backbuffer.id, PhysicalImage::new(
+                        images[index as usize],
+                        swapchain.get_image_format(),
+                        extent.width,
+                        extent.height,
+                        vk::SampleCountFlags::TYPE_1,
+                    ));
 
-            command_buffer.begin()?;
+                    timeline_values = submit_schedule(
+                        &self.device,
+                        &mut self.resources,
+                        &mut recorder,
+                        schedule,
+                        backbuffer.id,
+                        vk::ImageLayout::PRESENT_SRC_KHR,
+                        Some(acquire_semaphore.get_inner()),
+                        Some(submit_semaphore.get_inner()),
+                        &mut frame_resources,
+                        |_| {},
+                    )?;
 
-            for instruction in schedule.get_instructions().iter() {
-                match instruction {
-                    Instruction::CreateImage => {},
-                    Instruction::ReleaseImage => {},
-                    Instruction::ExecutePass => {},
-                    Instruction::Present => {},
-                    Instruction::ImageLayoutBarrier { id, from, to } => {
-                        // TODO transition image
-                        // TODO just take swapchain image for now
+                    recreate_swapchain = swapchain.present(index, self.device.graphics_queue.get_inner(), submit_semaphore.get_inner())?;
+                } else {
+                    // the frame gets dropped, without submissions it counts as finished right away
+                    recreate_swapchain = true;
+                }
+
+                frame_resources.add_semaphore(acquire_semaphore);
+                frame_resources.add_semaphore(submit_semaphore);
+            },
+            Backbuffer::Offscreen { image, readback, .. } => {
+                recorder.import_image(backbuffer.id, PhysicalImage::new(
+                    image.get_inner(),
+                    image.get_format(),
+                    image.get_width(),
+                    image.get_height(),
+                    image.get_samples(),
+                ));
 
-                        let image = &self.swapchain_images[index as usize];
+                // without a presentation engine, presenting means copying the image to the readback buffer
+                timeline_values = submit_schedule(
+                    &self.device,
+                    &mut self.resources,
+                    &mut recorder,
+                    schedule,
+                    backbuffer.id,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    None,
+                    None,
+                    &mut frame_resources,
+                    |command_buffer| {
+                        command_buffer.copy_image_to_buffer(image.get_inner(), image.get_width(), image.get_height(), readback.get_inner());
 
-                        command_buffer.pipeline_barrier(*image);
+                        // makes the copy visible to the host once the frame's timeline value is reached
+                        command_buffer.pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::HOST, &[BufferBarrier {
+                            buffer: readback.get_inner(),
+                            src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                            dst_access_mask: vk::AccessFlags::HOST_READ,
+                            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                        }], &[]);
                     },
+                )?;
+            },
+        }
+
+        self.transient_resources[slot] = Some(recorder.finish());
+
+        // a dropped frame didn't touch the persistent images, so they stay in the state of the frame before
+        if !timeline_values.is_empty() {
+            for (id, layout, queue) in schedule.get_persistent_image_states() {
+                if let Some((_, name)) = persistent_images.iter().find(|(image, _)| image == id) {
+                    self.persistent_images.get_mut(name).unwrap().state = Some((*layout, *queue));
                 }
             }
+        }
 
-            command_buffer.end()?;
+        // a dropped frame never reset its queries, they would still hold the timestamps of an earlier frame
+        if let Some(timestamp_queries) = timestamp_queries {
+            if timeline_values.is_empty() {
+                self.resources.return_query_pool(timestamp_queries.into_query_pool());
+            } else {
+                frame_resources.set_timestamp_queries(timestamp_queries);
+            }
+        }
 
-            self.device.queue_submit(
-                self.device.graphics_queue.get_inner(),
-                &command_buffer,
-                &[ash::vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
-                &[acquire_semaphore.get_inner()],
-                &[submit_semaphore.get_inner()],
-                finish_submit_fence.get_inner(),
-            )?;
+        self.pending_frame_resources.push_back(frame_resources.build(timeline_values));
+
+        if recreate_swapchain {
+            self.recreate_swapchain()?;
+        }
+
+        // validation messages are reported while recording and submitting, so the frame that caused them fails
+        self.device.check_validation()
+    }
+}
+
+/// Reads the timings of a finished frame and recycles its resources
+fn retire_frame(gpu_profiler: &mut GpuProfiler, resources: &mut Resources, mut frame_resources: FrameResources) -> Result<(), Error> {
+    if let Some(timestamp_queries) = frame_resources.take_timestamp_queries() {
+        gpu_profiler.resolve(timestamp_queries, resources)?;
+    }
+
+    frame_resources.recycle(resources);
+
+    Ok(())
+}
+
+/// Records every submission of the schedule into its own command buffer and submits it to its queue,
+/// the first submission waits for the acquired image and the last one signals that the image can be presented.
+/// Returns the timeline value of the last submission to every queue
+fn submit_schedule<F>(
+    device: &Device,
+    resources: &mut Resources,
+    recorder: &mut FrameRecorder,
+    schedule: &Schedule,
+    backbuffer: u32,
+    present_layout: vk::ImageLayout,
+    acquire_semaphore: Option<vk::Semaphore>,
+    submit_semaphore: Option<vk::Semaphore>,
+    frame_resources: &mut FrameResourceBuilder,
+    finish: F,
+) -> Result<Vec<(QueueType, u64)>, Error>
+    where F: FnOnce(&mut CommandBuffer)
+{
+    recorder.create_transient_resources(schedule, frame_resources)?;
+    recorder.record_passes(schedule, resources, frame_resources)?;
+
+    let submissions = schedule.get_submissions();
+    let mut command_pools: HashMap<u32, CommandPool> = HashMap::new();
+    // submissions on other queues wait for the timeline value of the submission they depend on
+    let mut timeline_values: Vec<(vk::Semaphore, u64)> = Vec::new();
+    let mut last_values: HashMap<QueueType, u64> = HashMap::new();
+    let mut finish = Some(finish);
+
+    // the backbuffer may only be touched once the presentation engine released it, which is not necessarily in the first submission
+    let acquire_index = schedule.get_first_submission_using(backbuffer).unwrap_or(0);
+
+    for (index, submission) in submissions.iter().enumerate() {
+        let queue = submission.get_queue().get_queue(device);
+        let last = index + 1 == submissions.len();
+
+        // command buffers can only be submitted to queues of the family their pool was created for
+        let command_pool = match command_pools.entry(queue.get_family_index()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(resources.get_command_pool(device, queue.get_family_index())?),
+        };
+
+        let mut command_buffers = command_pool.allocate_command_buffers(1, true)?;
+        let mut command_buffer = command_buffers.remove(0);
+
+        command_buffer.begin()?;
 
-            if self.swapchain.present(index, self.device.graphics_queue.get_inner(), submit_semaphore.get_inner())? {
-                // TODO recreate swapchain
+        recorder.record(&mut command_buffer, submission, present_layout)?;
+        if last {
+            if let Some(finish) = finish.take() {
+                finish(&mut command_buffer);
             }
         }
 
-        frame_resources.add_semaphore(acquire_semaphore);
-        frame_resources.add_semaphore(submit_semaphore);
-        frame_resources.add_command_pool(command_pool);
+        command_buffer.end()?;
 
-        self.pending_frame_resources.push(frame_resources.build(finish_submit_fence));
+        let mut wait_stage_masks = Vec::new();
+        let mut wait_semaphores = Vec::new();
+        let mut wait_values = Vec::new();
+        for wait in submission.get_waits() {
+            let (semaphore, value) = timeline_values[wait.submission];
+            wait_stage_masks.push(wait.stage_mask);
+            wait_semaphores.push(semaphore);
+            wait_values.push(value);
+        }
 
-        Ok(())
+        if let (true, Some(acquire_semaphore)) = (index == acquire_index, acquire_semaphore) {
+            wait_stage_masks.push(match submission.get_queue() {
+                QueueType::Graphics => vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                QueueType::Compute => vk::PipelineStageFlags::COMPUTE_SHADER,
+            });
+            wait_semaphores.push(acquire_semaphore);
+            wait_values.push(0);
+        }
+
+        let mut signal_semaphores = Vec::new();
+        if last {
+            signal_semaphores.extend(submit_semaphore);
+        }
+
+        let value = device.queue_submit(
+            queue,
+            &command_buffer,
+            &wait_stage_masks,
+            &wait_semaphores,
+            &wait_values,
+            &signal_semaphores,
+        )?;
+
+        timeline_values.push((queue.get_timeline().get_inner(), value));
+        last_values.insert(submission.get_queue(), value);
     }
+
+    command_pools.into_iter().for_each(|(_, command_pool)| frame_resources.add_command_pool(command_pool));
+
+    Ok(last_values.into_iter().collect())
 }
 
 impl Drop for Renderer {
     fn drop(&mut self) {
         self.device.wait_idle().unwrap();
 
-        while self.pending_frame_resources.len() > 0 {
-            let frame_resources = self.pending_frame_resources.remove(0);
+        for frame_resources in self.pending_frame_resources.drain(..) {
             frame_resources.recycle(&mut self.resources);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_frame(renderer: &Renderer) -> (Graph, ImageHandle) {
+        let mut builder = GraphBuilder::new();
+        let backbuffer = builder.import_image("Backbuffer", renderer.get_backbuffer_image_description()).unwrap();
+        let color_buffer = builder.create_image("Color Buffer", ImageDescription::new(
+            64,
+            64,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let color_buffer = builder.add_pass("Render Scene", |builder| {
+            builder.color_attachment(color_buffer)
+        }, |_, _| {}).unwrap();
+
+        let backbuffer_result = builder.add_pass("Post Process", |builder| {
+            builder.sample_image(color_buffer);
+            builder.color_attachment(backbuffer)
+        }, |_, _| {}).unwrap();
+
+        (builder.build(&[backbuffer_result]).unwrap(), backbuffer)
+    }
+
+    // needs a vulkan driver and the validation layers, a software driver like lavapipe works: cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn it_renders_headless_without_validation_errors() {
+        let mut configuration = Configuration::new();
+        configuration.validation = ValidationMode::Panic;
+        let mut renderer = Renderer::create_headless(configuration, 64, 64).unwrap();
+
+        for _ in 0..3 {
+            let (graph, backbuffer) = build_frame(&renderer);
+            renderer.render(graph, backbuffer, &[]).unwrap();
+        }
+
+        assert_eq!(renderer.read_back().unwrap().len(), 64 * 64 * 4);
+        assert!(renderer.take_validation_messages().iter().all(|message| message.severity != ValidationSeverity::Error));
+    }
+
+    // needs a vulkan driver: cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn it_records_graphs_with_imported_buffers() {
+        let mut renderer = Renderer::create_headless(Configuration::new(), 64, 64).unwrap();
+        let camera_buffer = renderer.device.create_buffer(256, vk::BufferUsageFlags::UNIFORM_BUFFER).unwrap();
+        let physical_camera_buffer = PhysicalBuffer::new(camera_buffer.get_inner(), camera_buffer.get_size());
+
+        let mut builder = GraphBuilder::new();
+        let backbuffer = builder.import_image("Backbuffer", renderer.get_backbuffer_image_description()).unwrap();
+        let camera = builder.import_buffer("Camera", BufferDescription::new(256)).unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let backbuffer_result = builder.add_pass("Draw", |builder| {
+            builder.read_uniform_buffer(camera);
+            builder.color_attachment(backbuffer)
+        }, move |_, context| sender.send(context.get_buffer(camera).buffer).unwrap()).unwrap();
+
+        let graph = builder.build(&[backbuffer_result]).unwrap();
+        renderer.render(graph, backbuffer, &[(camera, physical_camera_buffer)]).unwrap();
+        renderer.read_back().unwrap();
+
+        assert_eq!(receiver.recv().unwrap(), camera_buffer.get_inner());
+    }
+
+    // needs a vulkan driver: cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn it_reuses_framebuffers_across_frames() {
+        const FRAMES: u32 = 100;
+
+        let mut renderer = Renderer::create_headless(Configuration::new(), 64, 64).unwrap();
+        let frames_in_flight = renderer.configuration.frames_in_flight;
+
+        // the first frame of every slot compiles the schedule or creates transient images, render passes and framebuffers
+        for _ in 0..frames_in_flight {
+            let (graph, backbuffer) = build_frame(&renderer);
+            renderer.render(graph, backbuffer, &[]).unwrap();
+        }
+        renderer.read_back().unwrap();
+        let created_framebuffers = renderer.render_pass_cache.get_created_framebuffer_count();
+
+        for _ in 0..FRAMES {
+            let (graph, backbuffer) = build_frame(&renderer);
+            renderer.render(graph, backbuffer, &[]).unwrap();
+        }
+        renderer.read_back().unwrap();
+
+        assert!(created_framebuffers > 0);
+        assert_eq!(renderer.render_pass_cache.get_created_framebuffer_count(), created_framebuffers);
+    }
+}
@@ -0,0 +1,12 @@
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BufferDescription {
+    pub size: u64,
+}
+
+impl BufferDescription {
+    pub fn new(size: u64) -> Self {
+        Self {
+            size,
+        }
+    }
+}
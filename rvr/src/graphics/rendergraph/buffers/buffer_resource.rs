@@ -0,0 +1,18 @@
+use crate::graphics::rendergraph::*;
+
+#[derive(Debug)]
+pub struct BufferResource {
+    pub id: u32,
+    pub name: String,
+    pub description: BufferDescription,
+}
+
+impl BufferResource {
+    pub fn new(id: u32, name: String, description: BufferDescription) -> Self {
+        Self {
+            id,
+            name,
+            description,
+        }
+    }
+}
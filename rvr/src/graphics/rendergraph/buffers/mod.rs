@@ -0,0 +1,7 @@
+mod buffer_description;
+mod buffer_handle;
+mod buffer_resource;
+
+pub use buffer_description::*;
+pub use buffer_handle::*;
+pub use buffer_resource::*;
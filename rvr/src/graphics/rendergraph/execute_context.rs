@@ -1,3 +1,175 @@
-pub struct ExecuteContext {
-    
+use ash::vk;
+use std::sync::Mutex;
+use crate::{
+    Error,
+    assets::Shader,
+    graphics::*,
+    graphics::rendergraph::*,
+    graphics::vulkan::*,
+};
+
+/// The subpass of a render pass a graphics pass gets executed in
+#[derive(Copy, Clone)]
+pub(crate) struct SubpassInfo {
+    pub render_pass: vk::RenderPass,
+    pub framebuffer: vk::Framebuffer,
+    pub index: u32,
+    pub color_attachment_count: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What later commands need of the bound pipeline, so they don't have to look it up in the shared pipeline cache
+struct BoundPipeline {
+    bind_point: vk::PipelineBindPoint,
+    layout: vk::PipelineLayout,
+    push_constants: Vec<PushConstant>,
+}
+
+/// Records the commands of a single pass, graph resources get resolved to the images and buffers of the current frame
+pub struct ExecuteContext<'a> {
+    device: &'a Device,
+    command_buffer: &'a mut CommandBuffer,
+    resources: &'a PhysicalResources,
+    pipeline_cache: &'a Mutex<PipelineCache>,
+    frame_resources: &'a mut FrameResourceBuilder,
+    subpass: Option<SubpassInfo>,
+    sample_count: vk::SampleCountFlags,
+    pipeline: Option<BoundPipeline>,
+}
+
+impl<'a> ExecuteContext<'a> {
+    pub(crate) fn new(
+        device: &'a Device,
+        command_buffer: &'a mut CommandBuffer,
+        resources: &'a PhysicalResources,
+        pipeline_cache: &'a Mutex<PipelineCache>,
+        frame_resources: &'a mut FrameResourceBuilder,
+        subpass: Option<SubpassInfo>,
+        sample_count: vk::SampleCountFlags,
+    ) -> Self {
+        Self {
+            device,
+            command_buffer,
+            resources,
+            pipeline_cache,
+            frame_resources,
+            subpass,
+            sample_count,
+            pipeline: None,
+        }
+    }
+
+    /// The sample count pipelines used in this pass have to rasterize with
+    pub fn get_sample_count(&self) -> vk::SampleCountFlags {
+        self.sample_count
+    }
+
+    pub fn get_image(&self, image: ImageHandle) -> PhysicalImage {
+        self.resources.get_image(image.id)
+    }
+
+    pub fn get_buffer(&self, buffer: BufferHandle) -> PhysicalBuffer {
+        self.resources.get_buffer(buffer.id)
+    }
+
+    /// Creates a 2D view of the subresource that lives until the frame is finished
+    pub fn get_image_view<I: Into<ImageSubresourceHandle>>(&mut self, subresource: I) -> Result<vk::ImageView, Error> {
+        let subresource = subresource.into();
+        let image = self.resources.get_image(subresource.image.id);
+        let image_view = self.device.create_image_view(image.image, image.format, get_subresource_range(image.format, &subresource.range))?;
+        let inner = image_view.get_inner();
+
+        self.frame_resources.add_image_view(image_view);
+
+        Ok(inner)
+    }
+
+    /// Binds the pipeline of a loaded shader, graphics pipelines are created for the subpass of this pass
+    pub fn bind_pipeline(&mut self, shader: Shader) -> Result<(), Error> {
+        // other threads record passes at the same time, only the first use of a pipeline has to wait for its creation
+        let mut pipeline_cache = self.pipeline_cache.lock().unwrap();
+        let pipeline = match &self.subpass {
+            Some(subpass) => pipeline_cache.get_graphics_pipeline(
+                self.device,
+                shader,
+                subpass.render_pass,
+                subpass.index,
+                subpass.color_attachment_count,
+                self.sample_count,
+            )?,
+            None => pipeline_cache.get_compute_pipeline(self.device, shader)?,
+        };
+
+        self.command_buffer.bind_pipeline(pipeline);
+        if let Some(subpass) = &self.subpass {
+            self.command_buffer.set_render_area(subpass.width, subpass.height);
+        }
+
+        // pipelines stay in the cache, so their handles remain valid while the pass is recorded
+        let mut push_constants = self.pipeline.take().map(|bound| bound.push_constants).unwrap_or_default();
+        push_constants.clear();
+        push_constants.extend_from_slice(pipeline.get_push_constants());
+
+        self.pipeline = Some(BoundPipeline {
+            bind_point: pipeline.get_bind_point(),
+            layout: pipeline.get_layout(),
+            push_constants,
+        });
+
+        Ok(())
+    }
+
+    /// Binds the buffers to consecutive vertex bindings, starting at the first binding
+    pub fn bind_vertex_buffers(&mut self, first_binding: u32, buffers: &[(BufferHandle, u64)]) {
+        let offsets: Vec<vk::DeviceSize> = buffers.iter().map(|(_, offset)| *offset).collect();
+        let buffers: Vec<vk::Buffer> = buffers.iter().map(|(buffer, _)| self.resources.get_buffer(buffer.id).buffer).collect();
+
+        self.command_buffer.bind_vertex_buffers(first_binding, &buffers, &offsets);
+    }
+
+    pub fn bind_index_buffer(&mut self, buffer: BufferHandle, offset: u64, index_type: IndexType) {
+        let buffer = self.resources.get_buffer(buffer.id).buffer;
+        self.command_buffer.bind_index_buffer(buffer, offset, index_type.into());
+    }
+
+    /// Binds descriptor sets to the layout of the bound pipeline
+    pub fn bind_descriptor_sets(&mut self, first_set: u32, descriptor_sets: &[vk::DescriptorSet], dynamic_offsets: &[u32]) -> Result<(), Error> {
+        let pipeline = self.pipeline.as_ref().ok_or(GraphicsError::NoBoundPipeline)?;
+        self.command_buffer.bind_descriptor_sets(pipeline.bind_point, pipeline.layout, first_set, descriptor_sets, dynamic_offsets);
+
+        Ok(())
+    }
+
+    /// Updates the push constants of the bound pipeline, all stages that use the bytes see the update
+    pub fn push_constants(&mut self, offset: u32, data: &[u8]) -> Result<(), Error> {
+        let pipeline = self.pipeline.as_ref().ok_or(GraphicsError::NoBoundPipeline)?;
+        let stages = get_push_constant_stages(&pipeline.push_constants, offset, data.len() as u32);
+
+        self.command_buffer.push_constants(pipeline.layout, stages, offset, data);
+
+        Ok(())
+    }
+
+    pub fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32) {
+        self.command_buffer.draw(vertex_count, instance_count, first_vertex, first_instance);
+    }
+
+    pub fn draw_indexed(&mut self, index_count: u32, instance_count: u32, first_index: u32, vertex_offset: i32, first_instance: u32) {
+        self.command_buffer.draw_indexed(index_count, instance_count, first_index, vertex_offset, first_instance);
+    }
+
+    pub fn draw_indirect(&mut self, buffer: BufferHandle, offset: u64, draw_count: u32, stride: u32) {
+        let buffer = self.resources.get_buffer(buffer.id).buffer;
+        self.command_buffer.draw_indirect(buffer, offset, draw_count, stride);
+    }
+
+    pub fn draw_indexed_indirect(&mut self, buffer: BufferHandle, offset: u64, draw_count: u32, stride: u32) {
+        let buffer = self.resources.get_buffer(buffer.id).buffer;
+        self.command_buffer.draw_indexed_indirect(buffer, offset, draw_count, stride);
+    }
+
+    pub fn dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
+        self.command_buffer.dispatch(group_count_x, group_count_y, group_count_z);
+    }
 }
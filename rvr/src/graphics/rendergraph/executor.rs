@@ -1,17 +1,18 @@
 use crate::graphics::rendergraph::*;
 use std::fmt::Debug;
 
-pub trait Executor {
+/// Executors record their pass on a worker thread
+pub trait Executor: Send {
     fn execute(self: Box<Self>, context: &mut ExecuteContext);
 }
 
 pub struct FnOnceExecutor<T> {
     value: T,
-    executor: Box<dyn FnOnce(T, &mut ExecuteContext)>,
+    executor: Box<dyn FnOnce(T, &mut ExecuteContext) + Send>,
 }
 
 impl<T> FnOnceExecutor<T> {
-    pub fn new(value: T, executor: Box<dyn FnOnce(T, &mut ExecuteContext)>) -> Self {
+    pub fn new(value: T, executor: Box<dyn FnOnce(T, &mut ExecuteContext) + Send>) -> Self {
         Self {
             value,
             executor,
@@ -24,7 +25,7 @@ impl<T> FnOnceExecutor<T> {
     }
 }
 
-impl<T> Executor for FnOnceExecutor<T> {
+impl<T: Send> Executor for FnOnceExecutor<T> {
     fn execute(self: Box<Self>, context: &mut ExecuteContext) {
         self.execute_internal(context);
     }
@@ -0,0 +1,477 @@
+use super::*;
+use crate::Error;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// A minimal JSON document, fields keep their insertion order so exports of the same frame diff cleanly
+enum Json {
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn string(value: impl ToString) -> Self {
+        Json::String(value.to_string())
+    }
+
+    fn write(&self, writer: &mut dyn Write, indent: usize) -> Result<(), Error> {
+        match self {
+            Json::Bool(value) => write!(writer, "{}", value)?,
+            Json::Number(value) => write!(writer, "{}", value)?,
+            Json::String(value) => write!(writer, "\"{}\"", escape(value))?,
+            Json::Array(values) if values.is_empty() => write!(writer, "[]")?,
+            Json::Array(values) => {
+                writeln!(writer, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    write!(writer, "{:1$}", "", (indent + 1) * 2)?;
+                    value.write(writer, indent + 1)?;
+                    writeln!(writer, "{}", if index + 1 < values.len() { "," } else { "" })?;
+                }
+                write!(writer, "{:1$}]", "", indent * 2)?;
+            },
+            Json::Object(fields) if fields.is_empty() => write!(writer, "{{}}")?,
+            Json::Object(fields) => {
+                writeln!(writer, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    write!(writer, "{:1$}\"{2}\": ", "", (indent + 1) * 2, key)?;
+                    value.write(writer, indent + 1)?;
+                    writeln!(writer, "{}", if index + 1 < fields.len() { "," } else { "" })?;
+                }
+                write!(writer, "{:1$}}}", "", indent * 2)?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes quotes, backslashes and line breaks, which is enough for both JSON strings and DOT labels
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// The name of an instruction and its fields
+fn describe_instruction(instruction: &Instruction) -> (&'static str, Vec<(&'static str, Json)>) {
+    match instruction {
+        Instruction::CreateImage { id, description, usage, allocation } => ("CreateImage", vec![
+            ("id", Json::Number(*id as u64)),
+            ("description", Json::String(describe_image(description))),
+            ("usage", debug(usage)),
+            ("allocation", Json::Number(*allocation as u64)),
+        ]),
+        Instruction::ReleaseImage { id } => ("ReleaseImage", vec![("id", Json::Number(*id as u64))]),
+        Instruction::CreateBuffer { id, description, usage } => ("CreateBuffer", vec![
+            ("id", Json::Number(*id as u64)),
+            ("size", Json::Number(description.size)),
+            ("usage", debug(usage)),
+        ]),
+        Instruction::ReleaseBuffer { id } => ("ReleaseBuffer", vec![("id", Json::Number(*id as u64))]),
+        Instruction::ExecutePass { id, bind_point, attachment_ops, .. } => ("ExecutePass", vec![
+            ("id", Json::Number(*id as u64)),
+            ("bind_point", debug(bind_point)),
+            ("attachment_ops", debug(attachment_ops)),
+        ]),
+        Instruction::ExecuteSubpasses { subpasses, dependencies } => ("ExecuteSubpasses", vec![
+            ("passes", Json::String(subpasses.iter().map(|subpass| subpass.id.to_string()).collect::<Vec<String>>().join(", "))),
+            ("dependencies", Json::String(dependencies.iter()
+                .map(|dependency| format!(
+                    "{} -> {} ({:?} -> {:?}, {:?} -> {:?})",
+                    dependency.src_subpass,
+                    dependency.dst_subpass,
+                    dependency.src_stage_mask,
+                    dependency.dst_stage_mask,
+                    dependency.src_access_mask,
+                    dependency.dst_access_mask,
+                ))
+                .collect::<Vec<String>>()
+                .join("; "))),
+        ]),
+        Instruction::Present { id } => ("Present", vec![("id", Json::Number(*id as u64))]),
+        Instruction::ImageLayoutBarrier { id, subresource, from, to, src_stage_mask, dst_stage_mask, src_access_mask, dst_access_mask } => ("ImageLayoutBarrier", vec![
+            ("id", Json::Number(*id as u64)),
+            ("subresource", Json::String(describe_subresource(subresource))),
+            ("from", debug(from)),
+            ("to", debug(to)),
+            ("src_stage_mask", debug(src_stage_mask)),
+            ("dst_stage_mask", debug(dst_stage_mask)),
+            ("src_access_mask", debug(src_access_mask)),
+            ("dst_access_mask", debug(dst_access_mask)),
+        ]),
+        Instruction::ImageOwnershipRelease { id, subresource, from, to, src_stage_mask, src_access_mask, dst_queue } => ("ImageOwnershipRelease", vec![
+            ("id", Json::Number(*id as u64)),
+            ("subresource", Json::String(describe_subresource(subresource))),
+            ("from", debug(from)),
+            ("to", debug(to)),
+            ("src_stage_mask", debug(src_stage_mask)),
+            ("src_access_mask", debug(src_access_mask)),
+            ("dst_queue", debug(dst_queue)),
+        ]),
+        Instruction::ImageOwnershipAcquire { id, subresource, from, to, dst_stage_mask, dst_access_mask, src_queue } => ("ImageOwnershipAcquire", vec![
+            ("id", Json::Number(*id as u64)),
+            ("subresource", Json::String(describe_subresource(subresource))),
+            ("from", debug(from)),
+            ("to", debug(to)),
+            ("dst_stage_mask", debug(dst_stage_mask)),
+            ("dst_access_mask", debug(dst_access_mask)),
+            ("src_queue", debug(src_queue)),
+        ]),
+        Instruction::BufferOwnershipRelease { id, src_stage_mask, src_access_mask, dst_queue } => ("BufferOwnershipRelease", vec![
+            ("id", Json::Number(*id as u64)),
+            ("src_stage_mask", debug(src_stage_mask)),
+            ("src_access_mask", debug(src_access_mask)),
+            ("dst_queue", debug(dst_queue)),
+        ]),
+        Instruction::BufferOwnershipAcquire { id, dst_stage_mask, dst_access_mask, src_queue } => ("BufferOwnershipAcquire", vec![
+            ("id", Json::Number(*id as u64)),
+            ("dst_stage_mask", debug(dst_stage_mask)),
+            ("dst_access_mask", debug(dst_access_mask)),
+            ("src_queue", debug(src_queue)),
+        ]),
+        Instruction::BufferBarrier { id, src_stage_mask, dst_stage_mask, src_access_mask, dst_access_mask } => ("BufferBarrier", vec![
+            ("id", Json::Number(*id as u64)),
+            ("src_stage_mask", debug(src_stage_mask)),
+            ("dst_stage_mask", debug(dst_stage_mask)),
+            ("src_access_mask", debug(src_access_mask)),
+            ("dst_access_mask", debug(dst_access_mask)),
+        ]),
+    }
+}
+
+/// Flags without any bit set print as an empty string
+fn debug(value: impl std::fmt::Debug) -> Json {
+    match format!("{:?}", value) {
+        text if text.is_empty() => Json::string("empty"),
+        text => Json::String(text),
+    }
+}
+
+fn describe_image(description: &ImageDescription) -> String {
+    format!(
+        "{}x{}x{} {:?} {:?}, {} mip levels, {} layers",
+        description.width,
+        description.height,
+        description.depth,
+        description.format,
+        description.sample_count,
+        description.mip_levels,
+        description.array_layers,
+    )
+}
+
+fn describe_subresource(range: &ImageSubresourceRange) -> String {
+    format!(
+        "mip levels {}..{}, layers {}..{}",
+        range.base_mip_level,
+        range.base_mip_level + range.level_count,
+        range.base_array_layer,
+        range.base_array_layer + range.layer_count,
+    )
+}
+
+fn is_barrier(instruction: &Instruction) -> bool {
+    match instruction {
+        Instruction::ImageLayoutBarrier { .. }
+        | Instruction::ImageOwnershipRelease { .. }
+        | Instruction::ImageOwnershipAcquire { .. }
+        | Instruction::BufferOwnershipRelease { .. }
+        | Instruction::BufferOwnershipAcquire { .. }
+        | Instruction::BufferBarrier { .. } => true,
+        _ => false,
+    }
+}
+
+fn instruction_to_json(instruction: &Instruction) -> Json {
+    let (name, fields) = describe_instruction(instruction);
+
+    let mut object = vec![("type", Json::string(name))];
+    object.extend(fields);
+
+    Json::Object(object)
+}
+
+fn instruction_to_label(instruction: &Instruction) -> String {
+    let (name, fields) = describe_instruction(instruction);
+    let fields: Vec<String> = fields.into_iter()
+        .map(|(key, value)| match value {
+            Json::Number(value) => format!("{}: {}", key, value),
+            Json::String(value) => format!("{}: {}", key, value),
+            _ => key.to_string(),
+        })
+        .collect();
+
+    format!("{}\n{}", name, fields.join("\n"))
+}
+
+/// The barriers recorded right before each executed pass, the ones after the last pass are keyed by `None`
+fn get_barriers_per_pass(schedule: &Schedule) -> HashMap<Option<u32>, Vec<&Instruction>> {
+    let mut barriers: HashMap<Option<u32>, Vec<&Instruction>> = HashMap::new();
+    let mut pending = Vec::new();
+
+    for instruction in schedule.get_instructions() {
+        if is_barrier(instruction) {
+            pending.push(instruction);
+        } else if let Instruction::ExecutePass { id, .. } = instruction {
+            barriers.insert(Some(*id), std::mem::replace(&mut pending, Vec::new()));
+        } else if let Instruction::ExecuteSubpasses { subpasses, .. } = instruction {
+            // barriers between subpasses are part of the render pass
+            for subpass in subpasses.iter() {
+                barriers.insert(Some(subpass.id), std::mem::replace(&mut pending, Vec::new()));
+            }
+        }
+    }
+
+    barriers.insert(None, pending);
+    barriers
+}
+
+impl Graph {
+    /// Writes passes, resource versions and the barriers of the compiled schedule as a Graphviz graph,
+    /// passes that don't contribute to a result are drawn dashed, dotted edges lead from the version a write builds on
+    pub fn write_dot(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        let schedule = self.compile_schedule()?;
+        let barriers = get_barriers_per_pass(&schedule);
+
+        writeln!(writer, "digraph \"Graph\" {{")?;
+        writeln!(writer, "    node [fontname=\"monospace\"];")?;
+
+        for pass in self.passes.iter() {
+            let mut label = format!("{} ({:?}, {:?})", pass.name, pass.bind_point, pass.queue);
+            match barriers.get(&Some(pass.id)) {
+                Some(barriers) => barriers.iter().for_each(|barrier| label.push_str(&format!("\n\n{}", instruction_to_label(barrier)))),
+                None => label.push_str("\npruned"),
+            }
+
+            let style = if barriers.contains_key(&Some(pass.id)) { "solid" } else { "dashed" };
+            writeln!(writer, "    \"pass_{}\" [shape=box, style={}, label=\"{}\"];", pass.id, style, escape(&label))?;
+        }
+
+        for (id, version) in self.get_image_versions() {
+            let image = self.get_image(id);
+            let shape = if self.result_images.contains(&ImageHandle::new(id, version)) { "doublecircle" } else { "ellipse" };
+            let label = format!("{} v{}\n{}", image.name, version, describe_image(&image.description));
+            writeln!(writer, "    \"image_{}_v{}\" [shape={}, label=\"{}\"];", id, version, shape, escape(&label))?;
+        }
+
+        for (id, version) in self.get_buffer_versions() {
+            let buffer = self.get_buffer(id);
+            let label = format!("{} v{}\n{} bytes", buffer.name, version, buffer.description.size);
+            writeln!(writer, "    \"buffer_{}_v{}\" [shape=note, label=\"{}\"];", id, version, escape(&label))?;
+        }
+
+        for pass in self.passes.iter() {
+            for image in pass.get_read_images() {
+                writeln!(writer, "    \"image_{}_v{}\" -> \"pass_{}\";", image.id, image.version, pass.id)?;
+            }
+            for image in pass.get_written_images() {
+                writeln!(writer, "    \"pass_{}\" -> \"image_{}_v{}\";", pass.id, image.id, image.version)?;
+                if let Some(previous) = image.previous_version() {
+                    writeln!(writer, "    \"image_{}_v{}\" -> \"pass_{}\" [style=dotted];", previous.id, previous.version, pass.id)?;
+                }
+            }
+            for buffer in pass.get_read_buffers() {
+                writeln!(writer, "    \"buffer_{}_v{}\" -> \"pass_{}\";", buffer.id, buffer.version, pass.id)?;
+            }
+            for buffer in pass.get_written_buffers() {
+                writeln!(writer, "    \"pass_{}\" -> \"buffer_{}_v{}\";", pass.id, buffer.id, buffer.version)?;
+                if let Some(previous) = buffer.previous_version() {
+                    writeln!(writer, "    \"buffer_{}_v{}\" -> \"pass_{}\" [style=dotted];", previous.id, previous.version, pass.id)?;
+                }
+            }
+        }
+
+        writeln!(writer, "}}")?;
+
+        Ok(())
+    }
+
+    /// Writes passes, resource versions and the barriers of the compiled schedule as JSON
+    pub fn write_json(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        let schedule = self.compile_schedule()?;
+        let barriers = get_barriers_per_pass(&schedule);
+
+        let handle_to_json = |id: u32, version: u32| Json::Object(vec![
+            ("id", Json::Number(id as u64)),
+            ("version", Json::Number(version as u64)),
+        ]);
+
+        let passes = self.passes.iter()
+            .map(|pass| Json::Object(vec![
+                ("id", Json::Number(pass.id as u64)),
+                ("name", Json::string(&pass.name)),
+                ("bind_point", debug(pass.bind_point)),
+                ("queue", debug(pass.queue)),
+                ("pruned", Json::Bool(!barriers.contains_key(&Some(pass.id)))),
+                ("reads", Json::Array(pass.get_read_images().into_iter().map(|image| handle_to_json(image.id, image.version))
+                    .chain(pass.get_read_buffers().into_iter().map(|buffer| handle_to_json(buffer.id, buffer.version)))
+                    .collect())),
+                ("writes", Json::Array(pass.get_written_images().into_iter().map(|image| handle_to_json(image.id, image.version))
+                    .chain(pass.get_written_buffers().into_iter().map(|buffer| handle_to_json(buffer.id, buffer.version)))
+                    .collect())),
+                ("barriers", Json::Array(barriers.get(&Some(pass.id)).into_iter().flatten().map(|barrier| instruction_to_json(barrier)).collect())),
+            ]))
+            .collect();
+
+        let images = self.get_image_versions().into_iter()
+            .map(|(id, version)| {
+                let image = self.get_image(id);
+                Json::Object(vec![
+                    ("id", Json::Number(id as u64)),
+                    ("version", Json::Number(version as u64)),
+                    ("name", Json::string(&image.name)),
+                    ("imported", Json::Bool(self.import_images.iter().any(|imported| imported.id == id))),
+                    ("persistent", Json::Bool(self.persistent_images.iter().any(|persistent| persistent.id == id))),
+                    ("width", Json::Number(image.description.width as u64)),
+                    ("height", Json::Number(image.description.height as u64)),
+                    ("depth", Json::Number(image.description.depth as u64)),
+                    ("format", debug(image.description.format)),
+                    ("sample_count", debug(image.description.sample_count)),
+                    ("mip_levels", Json::Number(image.description.mip_levels as u64)),
+                    ("array_layers", Json::Number(image.description.array_layers as u64)),
+                    ("cube_compatible", Json::Bool(image.description.cube_compatible)),
+                ])
+            })
+            .collect();
+
+        let buffers = self.get_buffer_versions().into_iter()
+            .map(|(id, version)| {
+                let buffer = self.get_buffer(id);
+                Json::Object(vec![
+                    ("id", Json::Number(id as u64)),
+                    ("version", Json::Number(version as u64)),
+                    ("name", Json::string(&buffer.name)),
+                    ("imported", Json::Bool(self.import_buffers.iter().any(|imported| imported.id == id))),
+                    ("size", Json::Number(buffer.description.size)),
+                ])
+            })
+            .collect();
+
+        let json = Json::Object(vec![
+            ("passes", Json::Array(passes)),
+            ("images", Json::Array(images)),
+            ("buffers", Json::Array(buffers)),
+            ("results", Json::Array(self.result_images.iter().map(|image| handle_to_json(image.id, image.version)).collect())),
+            ("present_barriers", Json::Array(barriers.get(&None).into_iter().flatten().map(|barrier| instruction_to_json(barrier)).collect())),
+        ]);
+
+        json.write(writer, 0)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+
+    fn get_image(&self, id: u32) -> &ImageResource {
+        self.create_images.iter()
+            .chain(self.import_images.iter())
+            .chain(self.persistent_images.iter())
+            .find(|image| image.id == id)
+            .unwrap()
+    }
+
+    fn get_buffer(&self, id: u32) -> &BufferResource {
+        self.create_buffers.iter()
+            .chain(self.import_buffers.iter())
+            .find(|buffer| buffer.id == id)
+            .unwrap()
+    }
+
+    /// Every image version that exists in the graph, sorted by id and version
+    fn get_image_versions(&self) -> Vec<(u32, u32)> {
+        let mut versions: Vec<(u32, u32)> = self.passes.iter()
+            .flat_map(|pass| pass.get_images())
+            .chain(self.result_images.iter().cloned())
+            .flat_map(|image| image.previous_version().into_iter().chain(Some(image)))
+            .map(|image| (image.id, image.version))
+            .collect();
+
+        versions.sort();
+        versions.dedup();
+        versions
+    }
+
+    /// Every buffer version that exists in the graph, sorted by id and version
+    fn get_buffer_versions(&self) -> Vec<(u32, u32)> {
+        let mut versions: Vec<(u32, u32)> = self.passes.iter()
+            .flat_map(|pass| pass.get_buffers())
+            .flat_map(|buffer| buffer.previous_version().into_iter().chain(Some(buffer)))
+            .map(|buffer| (buffer.id, buffer.version))
+            .collect();
+
+        versions.sort();
+        versions.dedup();
+        versions
+    }
+}
+
+impl Schedule {
+    /// Writes the instructions of every submission as a chain of nodes, semaphore waits connect the submissions
+    pub fn write_dot(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        writeln!(writer, "digraph \"Schedule\" {{")?;
+        writeln!(writer, "    node [fontname=\"monospace\", shape=box];")?;
+
+        for (index, submission) in self.get_submissions().iter().enumerate() {
+            writeln!(writer, "    subgraph \"cluster_{}\" {{", index)?;
+            writeln!(writer, "        label=\"Submission {} ({:?})\";", index, submission.get_queue())?;
+            writeln!(writer, "        \"submission_{}\" [shape=plaintext, label=\"Submission {}\"];", index, index)?;
+
+            let mut previous = format!("submission_{}", index);
+            for (instruction_index, instruction) in submission.get_instructions().iter().enumerate() {
+                let node = format!("submission_{}_{}", index, instruction_index);
+                let style = if is_barrier(instruction) { "dashed" } else { "solid" };
+
+                writeln!(writer, "        \"{}\" [style={}, label=\"{}\"];", node, style, escape(&instruction_to_label(instruction)))?;
+                writeln!(writer, "        \"{}\" -> \"{}\";", previous, node)?;
+                previous = node;
+            }
+
+            writeln!(writer, "    }}")?;
+        }
+
+        for (index, submission) in self.get_submissions().iter().enumerate() {
+            for wait in submission.get_waits() {
+                writeln!(writer, "    \"submission_{}\" -> \"submission_{}\" [style=bold, label=\"{}\"];", wait.submission, index, escape(&format!("{:?}", wait.stage_mask)))?;
+            }
+        }
+
+        writeln!(writer, "}}")?;
+
+        Ok(())
+    }
+
+    /// Writes submissions, their semaphore waits, instructions and the transient allocations as JSON
+    pub fn write_json(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        let submissions = self.get_submissions().iter()
+            .map(|submission| Json::Object(vec![
+                ("queue", debug(submission.get_queue())),
+                ("signals", Json::Bool(submission.signals())),
+                ("waits", Json::Array(submission.get_waits().iter()
+                    .map(|wait| Json::Object(vec![
+                        ("submission", Json::Number(wait.submission as u64)),
+                        ("stage_mask", debug(wait.stage_mask)),
+                    ]))
+                    .collect())),
+                ("instructions", Json::Array(submission.get_instructions().iter().map(instruction_to_json).collect())),
+            ]))
+            .collect();
+
+        let allocations = self.get_allocations().iter()
+            .map(|allocation| Json::Object(vec![
+                ("id", Json::Number(allocation.id as u64)),
+                ("size", Json::Number(allocation.size)),
+                ("images", Json::Array(allocation.images.iter().map(|image| Json::Number(*image as u64)).collect())),
+            ]))
+            .collect();
+
+        let json = Json::Object(vec![
+            ("submissions", Json::Array(submissions)),
+            ("allocations", Json::Array(allocations)),
+        ]);
+
+        json.write(writer, 0)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+}
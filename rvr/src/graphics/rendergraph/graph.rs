@@ -1,43 +1,167 @@
 use super::*;
 use crate::{
+    Error,
     graphics::*,
     util::DirectedGraph,
 };
+use ash::vk;
+use std::collections::HashMap;
 
 pub struct Graph {
-    create_images: Vec<ImageResource>,
-    import_images: Vec<ImageResource>,
-    result_images: Vec<ImageHandle>,
-    passes: Vec<Pass>,
+    pub(crate) create_images: Vec<ImageResource>,
+    pub(crate) import_images: Vec<ImageResource>,
+    pub(crate) persistent_images: Vec<ImageResource>,
+    pub(crate) create_buffers: Vec<BufferResource>,
+    pub(crate) import_buffers: Vec<BufferResource>,
+    pub(crate) result_images: Vec<ImageHandle>,
+    pub(crate) passes: Vec<Pass>,
 }
 
 impl Graph {
     pub(crate) fn new(
         create_images: Vec<ImageResource>,
         import_images: Vec<ImageResource>,
+        persistent_images: Vec<ImageResource>,
+        create_buffers: Vec<BufferResource>,
+        import_buffers: Vec<BufferResource>,
         result_images: Vec<ImageHandle>,
         passes: Vec<Pass>,
     ) -> Self {
         Self {
             create_images,
             import_images,
+            persistent_images,
+            create_buffers,
+            import_buffers,
             result_images,
             passes,
         }
     }
 
-    pub fn compile_schedule(&self) -> Schedule {
-        use std::collections::{HashMap, HashSet};
+    /// Takes the executors of all passes, keyed by pass id
+    pub(crate) fn into_executors(self) -> HashMap<u32, Box<dyn Executor>> {
+        self.passes.into_iter()
+            .map(|pass| (pass.id, pass.executor))
+            .collect()
+    }
+
+    pub(crate) fn get_pass_names(&self) -> HashMap<u32, String> {
+        self.passes.iter()
+            .map(|pass| (pass.id, pass.name.clone()))
+            .collect()
+    }
+
+    /// The names of all images and buffers, keyed by resource id
+    pub(crate) fn get_resource_names(&self) -> HashMap<u32, String> {
+        self.create_images.iter()
+            .chain(self.import_images.iter())
+            .chain(self.persistent_images.iter())
+            .map(|image| (image.id, image.name.clone()))
+            .chain(self.create_buffers.iter()
+                .chain(self.import_buffers.iter())
+                .map(|buffer| (buffer.id, buffer.name.clone())))
+            .collect()
+    }
+
+    /// Every imported buffer needs a physical buffer that holds at least the size of its description
+    pub(crate) fn validate_imported_buffers(&self, buffers: &[(BufferHandle, PhysicalBuffer)]) -> Result<(), Error> {
+        for resource in self.import_buffers.iter() {
+            let (_, buffer) = buffers.iter()
+                .find(|(handle, _)| handle.id == resource.id)
+                .ok_or_else(|| GraphicsError::MissingImportedBuffer(resource.name.clone()))?;
+
+            if (buffer.size as u64) < resource.description.size {
+                return Err(GraphicsError::ImportedBufferTooSmall(resource.name.clone()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ids, names and descriptions of the images that keep their contents between frames
+    pub(crate) fn get_persistent_images(&self) -> Vec<(u32, String, ImageDescription)> {
+        self.persistent_images.iter()
+            .map(|image| (image.id, image.name.clone(), image.description.clone()))
+            .collect()
+    }
+
+    /// Every usage any pass needs an image for, persistent images also get sampled at the end of the frame
+    pub(crate) fn get_image_usage(&self, id: u32) -> vk::ImageUsageFlags {
+        let mut usage = self.passes.iter()
+            .flat_map(|pass| pass.get_image_accesses())
+            .filter(|(image, _)| image.image.id == id)
+            .fold(vk::ImageUsageFlags::empty(), |usage, (_, access)| usage | access.get_usage());
+
+        if self.persistent_images.iter().any(|image| image.id == id) {
+            usage |= ImageAccess::ShaderSample.get_usage();
+        }
+
+        usage
+    }
+
+    /// The persistent image lost what the last frame left in it, its first version has no contents
+    pub(crate) fn discard_contents(&mut self, id: u32) {
+        if let Some(image) = self.persistent_images.iter_mut().find(|image| image.id == id) {
+            image.description.initial_layout = ImageLayout::Unknown;
+            image.queue = None;
+        }
+    }
+
+    /// The persistent image starts in the layout and on the queue the last frame left it in
+    pub(crate) fn set_previous_state(&mut self, id: u32, layout: ImageLayout, queue: QueueType) {
+        if let Some(image) = self.persistent_images.iter_mut().find(|image| image.id == id) {
+            image.description.initial_layout = layout;
+            image.queue = Some(queue);
+        }
+    }
+
+    /// Hashes the structure of the graph, graphs with the same hash compile to the same schedule
+    pub fn get_structure_hash(&self) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+
+        for images in [&self.create_images, &self.import_images, &self.persistent_images].iter() {
+            images.len().hash(&mut hasher);
+            for image in images.iter() {
+                image.id.hash(&mut hasher);
+                image.description.hash(&mut hasher);
+                image.queue.hash(&mut hasher);
+            }
+        }
+
+        for buffers in [&self.create_buffers, &self.import_buffers].iter() {
+            buffers.len().hash(&mut hasher);
+            for buffer in buffers.iter() {
+                buffer.id.hash(&mut hasher);
+                buffer.description.hash(&mut hasher);
+            }
+        }
 
-        // first, create a hashmap that maps ImageHandle to the pass id that created it, if any
+        self.result_images.hash(&mut hasher);
+
+        self.passes.len().hash(&mut hasher);
+        for pass in self.passes.iter() {
+            pass.hash_structure(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    pub fn compile_schedule(&self) -> Result<Schedule, Error> {
+        use std::collections::HashSet;
+
+        // first, create hashmaps that map image and buffer versions to the pass id that created them, if any
         let mut image_sources = HashMap::new();
+        let mut buffer_sources = HashMap::new();
         for pass in self.passes.iter() {
-            for color_attachment in pass.render_target.color_attachments.iter() {
-                image_sources.insert(color_attachment, pass.id);
+            for image in pass.get_written_images() {
+                image_sources.insert(image, pass.id);
             }
 
-            if let Some(depth_stencil_attachment) = &pass.render_target.depth_stencil_attachment {
-                image_sources.insert(depth_stencil_attachment, pass.id);
+            for buffer in pass.get_written_buffers() {
+                buffer_sources.insert(buffer, pass.id);
             }
         }
 
@@ -52,107 +176,360 @@ impl Graph {
 
         // and fill the graph with edges based on when image versions are needed
         for pass in self.passes.iter() {
-            for sample_image in pass.sample_images.iter() {
-                if let Some(previous_pass) = image_sources.get(sample_image) {
-                    dependency_graph.add_edge(
-                        *passes.get(&pass.id).unwrap(),
-                        *passes.get(previous_pass).unwrap(),
-                    );
-                }
-            }
+            // reads need the version itself, writes need the version before it
+            let needed_images = pass.get_read_images().into_iter()
+                .chain(pass.get_written_images().into_iter().filter_map(|image| image.previous_version()));
 
-            for color_attachment in pass.render_target.color_attachments.iter() {
-                if let Some(previous_image) = color_attachment.previous_version() {
-                    if let Some(previous_pass) = image_sources.get(&previous_image) {
-                        dependency_graph.add_edge(
-                            *passes.get(&pass.id).unwrap(),
-                            *passes.get(previous_pass).unwrap(),
-                        );
-                    }
-                }
-            }
+            let needed_buffers = pass.get_read_buffers().into_iter()
+                .chain(pass.get_written_buffers().into_iter().filter_map(|buffer| buffer.previous_version()));
 
-            if let Some(depth_stencil_attachment) = &pass.render_target.depth_stencil_attachment {
-                if let Some(previous_image) = depth_stencil_attachment.previous_version() {
-                    if let Some(previous_pass) = image_sources.get(&previous_image) {
-                        dependency_graph.add_edge(
-                            *passes.get(&pass.id).unwrap(),
-                            *passes.get(previous_pass).unwrap(),
-                        );
-                    }
-                }
+            let previous_passes = needed_images.filter_map(|image| image_sources.get(&image))
+                .chain(needed_buffers.filter_map(|buffer| buffer_sources.get(&buffer)));
+
+            for previous_pass in previous_passes {
+                dependency_graph.add_edge(
+                    *passes.get(&pass.id).unwrap(),
+                    *passes.get(previous_pass).unwrap(),
+                );
             }
         }
 
         // then find the passes we have to execute and prune passes we don't need this frame
-        // also find all images that were never part of a pass but need to transition layout
-        // to be presented
-        let mut transition_images = Vec::new();
         let mut root_passes = Vec::new();
 
         for result_image in self.result_images.iter() {
             if let Some(pass) = image_sources.get(result_image) {
                 // image gets presented and was part of a render pass
                 root_passes.push(*passes.get(pass).unwrap());
-            } else {
-                // image gets presented, but was not part of any render pass
-                transition_images.push(result_image);
             }
         }
 
-        // the order in which passes get executed
-        // we ignore potential optimizations like collapsing passes for now
-        let order = dependency_graph.topological_sort(&root_passes);
-        
+        // the latest version of a persistent image is needed by the next frame
+        for image in self.persistent_images.iter() {
+            let latest = image_sources.iter()
+                .filter(|(handle, _)| handle.id == image.id)
+                .max_by_key(|(handle, _)| handle.version);
+
+            if let Some((_, pass)) = latest {
+                root_passes.push(*passes.get(pass).unwrap());
+            }
+        }
+
+        // the order in which passes get executed, compatible passes get collapsed into subpasses at the end
+        let order = dependency_graph.topological_sort(&root_passes)
+            .map_err(|cycle| {
+                let names = cycle.iter().map(|index| dependency_graph.get_node(*index).1.clone()).collect();
+                GraphicsError::DependencyCycle(names)
+            })?;
+        let order: Vec<&Pass> = order.iter()
+            .map(|index| {
+                let (id, _) = dependency_graph.get_node(*index);
+                self.passes.iter().find(|p| p.id == *id).unwrap()
+            })
+            .collect();
+
+        // find the first and last pass that uses each image, transient images get released right after the last one
+        let mut first_uses = HashMap::new();
+        let mut last_uses = HashMap::new();
+        for (index, pass) in order.iter().enumerate() {
+            for image in pass.get_images() {
+                first_uses.entry(image.id).or_insert(index);
+                last_uses.insert(image.id, pass.id);
+            }
+        }
+
+        let result_ids: HashSet<u32> = self.result_images.iter().map(|image| image.id).collect();
+
+        // images only alias on a single queue, result images are also used by the graphics queue for presenting
+        let mut image_queues: HashMap<u32, HashSet<QueueType>> = HashMap::new();
+        for pass in order.iter() {
+            for image in pass.get_images() {
+                image_queues.entry(image.id).or_insert_with(HashSet::new).insert(pass.queue);
+            }
+        }
+        for result_image in self.result_images.iter() {
+            image_queues.entry(result_image.id).or_insert_with(HashSet::new).insert(QueueType::Graphics);
+        }
+
+        // transient images that are never alive at the same time can share memory, result images live until they got presented
+        let lifetimes: Vec<ImageLifetime> = self.create_images.iter()
+            .filter_map(|image| {
+                let first_use = *first_uses.get(&image.id)?;
+                let last_pass = *last_uses.get(&image.id)?;
+                let last_use = if result_ids.contains(&image.id) {
+                    order.len()
+                } else {
+                    order.iter().position(|pass| pass.id == last_pass).unwrap()
+                };
+
+                Some(ImageLifetime {
+                    id: image.id,
+                    first_use,
+                    last_use,
+                    size: image.description.get_size(),
+                    is_depth: image.description.format.is_depth_format(),
+                    queue: image_queues.get(&image.id)
+                        .filter(|queues| queues.len() == 1)
+                        .and_then(|queues| queues.iter().next().copied()),
+                })
+            })
+            .collect();
+
+        let mut last_buffer_uses = HashMap::new();
+        for pass in order.iter() {
+            for buffer in pass.get_buffers() {
+                last_buffer_uses.insert(buffer.id, pass.id);
+            }
+        }
+
+        // transient images get created with exactly the usages the graph needs
+        let mut image_usages: HashMap<u32, vk::ImageUsageFlags> = HashMap::new();
+        for pass in order.iter() {
+            for (image, access) in pass.get_image_accesses() {
+                *image_usages.entry(image.image.id).or_insert_with(vk::ImageUsageFlags::empty) |= access.get_usage();
+            }
+        }
+        for result_image in self.result_images.iter() {
+            *image_usages.entry(result_image.id).or_insert_with(vk::ImageUsageFlags::empty) |= ImageAccess::Present.get_usage();
+        }
+
+        let mut buffer_usages: HashMap<u32, vk::BufferUsageFlags> = HashMap::new();
+        for pass in order.iter() {
+            for (buffer, access) in pass.buffers.iter() {
+                *buffer_usages.entry(buffer.id).or_insert_with(vk::BufferUsageFlags::empty) |= access.get_usage();
+            }
+        }
+
+        let allocations = assign_allocations(&lifetimes);
+        let image_allocations: HashMap<u32, u32> = allocations.iter()
+            .flat_map(|allocation| allocation.images.iter().map(move |image| (*image, allocation.id)))
+            .collect();
+
         // now we start building the linear schedule to be executed by the renderer
         let mut created_images = HashSet::new();
-        let mut image_layouts = HashMap::new();
-        let mut schedule = ScheduleBuilder::new();
+        let mut image_states = HashMap::new();
+        let mut allocation_states: HashMap<u32, ImageState> = HashMap::new();
+        let mut created_buffers = HashSet::new();
+        let mut buffer_states = HashMap::new();
+        let mut schedule = ScheduleBuilder::new(allocations);
 
-        // first set all image layouts to their initial states
+        // first set all images to their initial states, we don't know what happened to imported images before this frame
         for image in self.create_images.iter() {
-            image_layouts.insert(image.id, image.description.initial_layout);
+            image_states.insert(image.id, ImageState::new(&image.description, vk::PipelineStageFlags::TOP_OF_PIPE));
         }
         for image in self.import_images.iter() {
-            image_layouts.insert(image.id, image.description.initial_layout);
+            image_states.insert(image.id, ImageState::new(&image.description, vk::PipelineStageFlags::ALL_COMMANDS));
+        }
+
+        // persistent images stay with the queue of the last frame, which has to release them to a queue that uses them first
+        let mut first_queues = HashMap::new();
+        for pass in order.iter() {
+            for image in pass.get_images() {
+                first_queues.entry(image.id).or_insert(pass.queue);
+            }
+        }
+        for image in self.persistent_images.iter() {
+            // unused images only get sampled on the graphics queue at the end of the frame
+            let first_queue = first_queues.get(&image.id).copied().unwrap_or(QueueType::Graphics);
+
+            let owner = match image.queue {
+                Some(queue) if queue != first_queue && image.description.initial_layout != ImageLayout::Unknown => {
+                    schedule.begin_submission(queue);
+                    Some(schedule.get_current_submission())
+                },
+                _ => None,
+            };
+
+            image_states.insert(image.id, ImageState::persistent(&image.description, owner));
+        }
+
+        // barriers cover the actual mip levels and layers instead of remaining ones
+        let descriptions: HashMap<u32, &ImageDescription> = self.create_images.iter()
+            .chain(self.import_images.iter())
+            .chain(self.persistent_images.iter())
+            .map(|image| (image.id, &image.description))
+            .collect();
+        let whole_image = |id: u32| ImageSubresourceRange::all().resolve(descriptions[&id]);
+        for buffer in self.create_buffers.iter() {
+            buffer_states.insert(buffer.id, BufferState::new(vk::PipelineStageFlags::empty()));
+        }
+        for buffer in self.import_buffers.iter() {
+            buffer_states.insert(buffer.id, BufferState::new(vk::PipelineStageFlags::ALL_COMMANDS));
         }
 
         // iterate over all passes in execution order
-        order.iter().for_each(|index| {
-            let (id, _) = dependency_graph.get_node(*index);
-            let pass = self.passes.iter().find(|p| p.id == *id).unwrap();
-
-            for color_attachment in pass.render_target.color_attachments.iter() {
-                if !created_images.contains(color_attachment) {
-                    // TODO create image
-                    created_images.insert(color_attachment.clone());
+        for (index, pass) in order.iter().enumerate() {
+
+            // consecutive passes on the same queue share a submission
+            schedule.begin_submission(pass.queue);
+
+            // create transient images on their first use
+            for image in pass.get_images() {
+                if created_images.contains(&image.id) {
+                    continue;
+                }
+
+                if let Some(resource) = self.create_images.iter().find(|resource| resource.id == image.id) {
+                    let allocation = *image_allocations.get(&image.id).unwrap();
+
+                    // an aliased image has to wait for the previous image in the same memory
+                    if let Some(previous) = allocation_states.get(&allocation) {
+                        image_states.get_mut(&image.id).unwrap().alias(previous);
+                    }
+
+                    schedule.add_create_image(image.id, resource.description.clone(), *image_usages.get(&image.id).unwrap(), allocation);
+                    created_images.insert(image.id);
                 }
             }
 
-            if let Some(depth_stencil_attachment) = &pass.render_target.depth_stencil_attachment {
-                if !created_images.contains(depth_stencil_attachment) {
-                    // TODO create image
-                    created_images.insert(depth_stencil_attachment.clone());
+            // buffers don't alias, so they are created without further bookkeeping
+            for buffer in pass.get_buffers() {
+                if created_buffers.contains(&buffer.id) {
+                    continue;
                 }
+
+                if let Some(resource) = self.create_buffers.iter().find(|resource| resource.id == buffer.id) {
+                    schedule.add_create_buffer(buffer.id, resource.description.clone(), *buffer_usages.get(&buffer.id).unwrap());
+                    created_buffers.insert(buffer.id);
+                }
+            }
+
+            // bring every image into the state this pass needs it in
+            let mut accesses: Vec<(u32, ImageSubresourceRange, ImageAccess)> = pass.get_image_accesses().into_iter()
+                .map(|(image, access)| (image.image.id, image.range.resolve(descriptions[&image.image.id]), access))
+                .collect();
+
+            accesses.dedup();
+            for (id, range, access) in accesses {
+                image_states.get_mut(&id).unwrap().transition(id, range, access, pass.bind_point, &mut schedule);
             }
 
-            // TODO define render pass
+            // a pass can use a buffer in several ways at once, it gets a single barrier covering all of them
+            let mut buffer_accesses: Vec<(u32, vk::PipelineStageFlags, vk::AccessFlags, vk::AccessFlags)> = Vec::new();
+            for (buffer, access) in pass.buffers.iter() {
+                let stage_mask = access.get_stage_mask(pass.bind_point);
 
-            // TODO execute pass
-        });
+                match buffer_accesses.iter_mut().find(|(id, ..)| *id == buffer.id) {
+                    Some((_, stages, accesses, writes)) => {
+                        *stages |= stage_mask;
+                        *accesses |= access.get_access_mask();
+                        *writes |= access.get_write_access_mask();
+                    },
+                    None => buffer_accesses.push((buffer.id, stage_mask, access.get_access_mask(), access.get_write_access_mask())),
+                }
+            }
 
-        // insert barriers for images that still need to be transitioned to another layout
-        for transition_image in transition_images {
-            let from_layout = *image_layouts.get(&transition_image.id).unwrap();
-            let to_layout = ImageLayout::Present;
+            for (id, stage_mask, access_mask, write_access_mask) in buffer_accesses {
+                buffer_states.get_mut(&id).unwrap().access(id, stage_mask, access_mask, write_access_mask, &mut schedule);
+            }
 
-            schedule.add_image_layout_barrier(
-                transition_image.id,
-                from_layout,
-                to_layout,
+            schedule.add_execute_pass(
+                pass.id,
+                pass.bind_point,
+                pass.render_target.color_attachments.clone(),
+                pass.render_target.depth_stencil_attachment.clone(),
+                pass.render_target.input_attachments.clone(),
+                pass.render_target.resolve_attachments.clone(),
+                self.get_attachment_ops(pass, &order[index + 1..]),
             );
+
+            // release transient images after their last use, unless they still need to be presented
+            let mut released = HashSet::new();
+            for image in pass.get_images() {
+                if created_images.contains(&image.id) && !result_ids.contains(&image.id) && last_uses.get(&image.id) == Some(&pass.id) && released.insert(image.id) {
+                    allocation_states.insert(*image_allocations.get(&image.id).unwrap(), image_states.get(&image.id).unwrap().clone());
+                    schedule.add_release_image(image.id);
+                }
+            }
+
+            let mut released = HashSet::new();
+            for buffer in pass.get_buffers() {
+                if created_buffers.contains(&buffer.id) && last_buffer_uses.get(&buffer.id) == Some(&pass.id) && released.insert(buffer.id) {
+                    schedule.add_release_buffer(buffer.id);
+                }
+            }
+        }
+
+        // transition all result images to be presented, which happens on the graphics queue
+        schedule.begin_submission(QueueType::Graphics);
+        for result_image in self.result_images.iter() {
+            image_states.get_mut(&result_image.id).unwrap().transition(result_image.id, whole_image(result_image.id), ImageAccess::Present, PipelineBindPoint::Graphics, &mut schedule);
+            schedule.add_present(result_image.id);
+        }
+
+        // persistent images get sampled as last frame's version by the next frame
+        for image in self.persistent_images.iter() {
+            let state = image_states.get_mut(&image.id).unwrap();
+            state.transition(image.id, whole_image(image.id), ImageAccess::ShaderSample, PipelineBindPoint::Graphics, &mut schedule);
+
+            let (layout, owner) = state.get_last_use();
+            schedule.add_persistent_image_state(image.id, layout, schedule.get_queue(owner));
         }
 
-        schedule.build()
+        // transient result images live until they got presented
+        for result_image in self.result_images.iter() {
+            if created_images.contains(&result_image.id) {
+                schedule.add_release_image(result_image.id);
+            }
+        }
+
+        // passes that can share a render pass become its subpasses
+        let merge_passes: HashMap<u32, &Pass> = order.iter().map(|pass| (pass.id, *pass)).collect();
+        let merge_images: HashMap<u32, MergeImage> = self.create_images.iter()
+            .chain(self.import_images.iter())
+            .chain(self.persistent_images.iter())
+            .map(|image| (image.id, MergeImage {
+                description: &image.description,
+                allocation: image_allocations.get(&image.id).copied(),
+            }))
+            .collect();
+
+        schedule.rewrite_instructions(|instructions| merge_subpasses(instructions, &merge_passes, &merge_images));
+
+        Ok(schedule.build())
+    }
+
+    /// How each attachment of a pass gets loaded and stored, in the order of its subpass attachments.
+    /// The first version of a transient image has nothing worth loading, a version no later pass needs isn't worth storing,
+    /// unless the image outlives the graph.
+    fn get_attachment_ops(&self, pass: &Pass, later_passes: &[&Pass]) -> Vec<AttachmentOps> {
+        let is_imported = |id: u32| self.import_images.iter().chain(self.persistent_images.iter()).any(|image| image.id == id);
+
+        // later passes need a version when they read it or write the next one, unless they clear the same subresources
+        let get_store_op = |subresource: &ImageSubresourceHandle| {
+            let image = subresource.image;
+            let is_needed = is_imported(image.id) || self.result_images.contains(&image) || later_passes.iter().any(|later| {
+                let clears = later.render_target.clear_values.contains_key(&image.id) && later.render_target.color_attachments.iter()
+                    .chain(later.render_target.depth_stencil_attachment.iter())
+                    .any(|attachment| attachment.image.previous_version() == Some(image) && attachment.range == subresource.range);
+
+                later.get_read_images().contains(&image) || (!clears && later.get_written_images().iter()
+                    .any(|written| written.previous_version() == Some(image)))
+            });
+
+            if is_needed { StoreOp::Store } else { StoreOp::DontCare }
+        };
+
+        let written = pass.render_target.color_attachments.iter()
+            .chain(pass.render_target.depth_stencil_attachment.iter())
+            .map(|subresource| {
+                let image = subresource.image;
+                let load_op = match pass.render_target.clear_values.get(&image.id) {
+                    Some(clear_value) => LoadOp::Clear(*clear_value),
+                    None if image.version == 1 && !is_imported(image.id) => LoadOp::DontCare,
+                    None => LoadOp::Load,
+                };
+
+                AttachmentOps::new(load_op, get_store_op(subresource))
+            });
+
+        let read = pass.render_target.input_attachments.iter()
+            .map(|image| AttachmentOps::new(LoadOp::Load, get_store_op(image)));
+
+        // resolving overwrites every pixel, so the previous contents never have to be loaded
+        let resolved = pass.render_target.resolve_attachments.iter()
+            .map(|(_, target)| AttachmentOps::new(LoadOp::DontCare, get_store_op(target)));
+
+        written.chain(read).chain(resolved).collect()
     }
 }
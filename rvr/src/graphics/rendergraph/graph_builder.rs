@@ -1,12 +1,17 @@
 use super::*;
 use crate::{
     Error,
+    graphics::{GraphicsError, ImageLayout, PipelineBindPoint, QueueType, SampleCount},
     util::IdGenerator,
 };
+use std::collections::{HashMap, HashSet};
 
 pub struct GraphBuilder {
     create_images: Vec<ImageResource>,
     import_images: Vec<ImageResource>,
+    persistent_images: Vec<ImageResource>,
+    create_buffers: Vec<BufferResource>,
+    import_buffers: Vec<BufferResource>,
     passes: Vec<Pass>,
     id_generator: IdGenerator,
 }
@@ -16,12 +21,17 @@ impl GraphBuilder {
         Self {
             create_images: Vec::new(),
             import_images: Vec::new(),
+            persistent_images: Vec::new(),
+            create_buffers: Vec::new(),
+            import_buffers: Vec::new(),
             passes: Vec::new(),
             id_generator: IdGenerator::new(),
         }
     }
 
     pub(crate) fn import_image(&mut self, name: &str, description: ImageDescription) -> Result<ImageHandle, Error> {
+        validate_description(name, &description)?;
+
         let id = self.id_generator.next();
         self.import_images.push(ImageResource::new(id, name.to_string(), description));
 
@@ -29,18 +39,79 @@ impl GraphBuilder {
     }
 
     pub fn create_image(&mut self, name: &str, description: ImageDescription) -> Result<ImageHandle, Error> {
+        validate_description(name, &description)?;
+
         let id = self.id_generator.next();
         self.create_images.push(ImageResource::new(id, name.to_string(), description));
 
         Ok(ImageHandle::new(id, 0))
     }
 
-    pub fn add_pass<I, E: 'static, PassData: 'static + Clone>(&mut self, name: &str, initialize: I, execute: E) -> Result<PassData, Error>
+    /// Adds an image that keeps its contents between frames, version 0 holds whatever the last frame left in it.
+    /// The contents are undefined in the first frame and after the description changed.
+    pub fn persistent_image(&mut self, name: &str, description: ImageDescription) -> Result<ImageHandle, Error> {
+        validate_description(name, &description)?;
+
+        if self.persistent_images.iter().any(|image| image.name == name) {
+            return Err(GraphicsError::DuplicatePersistentImage(name.to_string()).into());
+        }
+
+        // every frame leaves its persistent images ready to be sampled by the next one
+        let description = ImageDescription {
+            initial_layout: ImageLayout::ShaderSample,
+            ..description
+        };
+
+        let id = self.id_generator.next();
+        self.persistent_images.push(ImageResource::new(id, name.to_string(), description));
+
+        Ok(ImageHandle::new(id, 0))
+    }
+
+    /// Adds a buffer that lives outside of the graph, `Renderer::render` has to be given a physical buffer for it
+    pub fn import_buffer(&mut self, name: &str, description: BufferDescription) -> Result<BufferHandle, Error> {
+        let id = self.id_generator.next();
+        self.import_buffers.push(BufferResource::new(id, name.to_string(), description));
+
+        Ok(BufferHandle::new(id, 0))
+    }
+
+    pub fn create_buffer(&mut self, name: &str, description: BufferDescription) -> Result<BufferHandle, Error> {
+        let id = self.id_generator.next();
+        self.create_buffers.push(BufferResource::new(id, name.to_string(), description));
+
+        Ok(BufferHandle::new(id, 0))
+    }
+
+    pub fn add_pass<I, E: 'static + Send, PassData: 'static + Clone + Send>(&mut self, name: &str, initialize: I, execute: E) -> Result<PassData, Error>
+        where I: FnOnce(&mut PassBuilder) -> Result<PassData, Error>,
+        E: FnOnce(PassData, &mut ExecuteContext)
+    {
+        self.add_pass_with_bind_point(name, PipelineBindPoint::Graphics, QueueType::Graphics, initialize, execute)
+    }
+
+    /// Adds a pass that dispatches compute work, it can sample and use storage images but has no attachments
+    pub fn add_compute_pass<I, E: 'static + Send, PassData: 'static + Clone + Send>(&mut self, name: &str, initialize: I, execute: E) -> Result<PassData, Error>
+        where I: FnOnce(&mut PassBuilder) -> Result<PassData, Error>,
+        E: FnOnce(PassData, &mut ExecuteContext)
+    {
+        self.add_pass_with_bind_point(name, PipelineBindPoint::Compute, QueueType::Graphics, initialize, execute)
+    }
+
+    /// Adds a compute pass that runs on the compute queue, so it can overlap with graphics work
+    pub fn add_async_compute_pass<I, E: 'static + Send, PassData: 'static + Clone + Send>(&mut self, name: &str, initialize: I, execute: E) -> Result<PassData, Error>
+        where I: FnOnce(&mut PassBuilder) -> Result<PassData, Error>,
+        E: FnOnce(PassData, &mut ExecuteContext)
+    {
+        self.add_pass_with_bind_point(name, PipelineBindPoint::Compute, QueueType::Compute, initialize, execute)
+    }
+
+    fn add_pass_with_bind_point<I, E: 'static + Send, PassData: 'static + Clone + Send>(&mut self, name: &str, bind_point: PipelineBindPoint, queue: QueueType, initialize: I, execute: E) -> Result<PassData, Error>
         where I: FnOnce(&mut PassBuilder) -> Result<PassData, Error>,
         E: FnOnce(PassData, &mut ExecuteContext)
     {
         let id = self.id_generator.next();
-        let mut pass_builder = PassBuilder::new(id, name.to_string(), &mut self.id_generator);
+        let mut pass_builder = PassBuilder::new(id, name.to_string(), bind_point, queue, &mut self.id_generator);
         let pass_data = initialize(&mut pass_builder)?;
         
         let executor = FnOnceExecutor::new(pass_data.clone(), Box::new(execute));
@@ -50,12 +121,192 @@ impl GraphBuilder {
         Ok(pass_data)
     }
 
-    pub(crate) fn build(self, result_images: &[ImageHandle]) -> Graph {
-        Graph::new(
+    pub(crate) fn build(self, result_images: &[ImageHandle]) -> Result<Graph, Error> {
+        self.validate_versions()?;
+        self.validate_subresources()?;
+        self.validate_attachments()?;
+        self.validate_results(result_images)?;
+
+        Ok(Graph::new(
             self.create_images,
             self.import_images,
+            self.persistent_images,
+            self.create_buffers,
+            self.import_buffers,
             Vec::from(result_images),
             self.passes,
-        )
+        ))
+    }
+
+    fn get_image_resource(&self, id: u32) -> Option<&ImageResource> {
+        self.create_images.iter()
+            .chain(self.import_images.iter())
+            .chain(self.persistent_images.iter())
+            .find(|image| image.id == id)
+    }
+
+    fn get_resource_name(&self, id: u32) -> String {
+        let buffer = self.create_buffers.iter()
+            .chain(self.import_buffers.iter())
+            .find(|buffer| buffer.id == id)
+            .map(|buffer| buffer.name.clone());
+
+        self.get_image_resource(id)
+            .map(|image| image.name.clone())
+            .or(buffer)
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Passes are added in order, so every write has to build on the latest version of a resource.
+    /// A single pass may write disjoint mip levels or layers of the same version, like all cascades of a shadow map.
+    fn validate_versions(&self) -> Result<(), Error> {
+        let mut latest_versions: HashMap<u32, (u32, &str)> = HashMap::new();
+
+        for pass in self.passes.iter() {
+            let mut written_ranges: HashMap<ImageHandle, Vec<ImageSubresourceRange>> = HashMap::new();
+            let mut writes = Vec::new();
+
+            for subresource in pass.get_written_subresources() {
+                let range = match self.get_image_resource(subresource.image.id) {
+                    Some(resource) => subresource.range.resolve(&resource.description),
+                    None => subresource.range,
+                };
+
+                // the version was already checked when the pass wrote another part of the image
+                let ranges = written_ranges.entry(subresource.image).or_insert_with(Vec::new);
+                if ranges.is_empty() || ranges.iter().any(|written| written.overlaps(&range)) {
+                    writes.push((subresource.image.id, subresource.image.version));
+                }
+                ranges.push(range);
+            }
+            writes.extend(pass.get_written_buffers().into_iter().map(|buffer| (buffer.id, buffer.version)));
+
+            for (id, version) in writes {
+                let (latest, producer) = latest_versions.get(&id).cloned().unwrap_or((0, ""));
+
+                if version == latest {
+                    return Err(GraphicsError::ConflictingWrites {
+                        first_pass: producer.to_string(),
+                        second_pass: pass.name.clone(),
+                        resource: self.get_resource_name(id),
+                        version,
+                    }.into());
+                }
+
+                if version < latest {
+                    return Err(GraphicsError::OutdatedVersionWrite {
+                        pass: pass.name.clone(),
+                        resource: self.get_resource_name(id),
+                        version: version - 1,
+                        latest,
+                    }.into());
+                }
+
+                latest_versions.insert(id, (version, &pass.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every mip level and layer a pass uses has to exist
+    fn validate_subresources(&self) -> Result<(), Error> {
+        for pass in self.passes.iter() {
+            for (image, _) in pass.get_image_accesses() {
+                if let Some(resource) = self.get_image_resource(image.image.id) {
+                    if !image.range.is_valid(&resource.description) {
+                        return Err(GraphicsError::InvalidSubresource {
+                            pass: pass.name.clone(),
+                            resource: resource.name.clone(),
+                        }.into());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// All attachments of a pass are rendered with the same framebuffer, which holds a single mip level and layer of each
+    fn validate_attachments(&self) -> Result<(), Error> {
+        for pass in self.passes.iter() {
+            let render_target = &pass.render_target;
+
+            // draws have to happen inside a render pass, which needs at least one attachment to render to
+            if pass.bind_point == PipelineBindPoint::Graphics && render_target.color_attachments.is_empty() && render_target.depth_stencil_attachment.is_none() {
+                return Err(GraphicsError::GraphicsPassWithoutAttachments(pass.name.clone()).into());
+            }
+            let attachments: Vec<(&ImageSubresourceHandle, &ImageDescription)> = render_target.color_attachments.iter()
+                .chain(render_target.depth_stencil_attachment.iter())
+                .chain(render_target.input_attachments.iter())
+                .filter_map(|attachment| Some((attachment, &self.get_image_resource(attachment.image.id)?.description)))
+                .collect();
+
+            let resolve_targets = render_target.resolve_attachments.iter()
+                .filter_map(|(_, target)| Some((target, &self.get_image_resource(target.image.id)?.description)));
+
+            for (attachment, description) in attachments.iter().cloned().chain(resolve_targets) {
+                let range = attachment.range.resolve(description);
+                if range.level_count != 1 || range.layer_count != 1 {
+                    return Err(GraphicsError::MultipleSubresourceAttachment(pass.name.clone()).into());
+                }
+            }
+
+            // resolve targets are single sampled, so they only have to match the attachments in size
+            let framebuffer: Vec<(u32, u32, SampleCount)> = attachments.iter()
+                .map(|(attachment, description)| {
+                    let (width, height, _) = description.get_mip_extent(attachment.range.base_mip_level);
+                    (width, height, description.sample_count)
+                })
+                .collect();
+
+            if let Some((first_width, first_height, first_sample_count)) = framebuffer.first().cloned() {
+                if framebuffer.iter().any(|(width, height, _)| *width != first_width || *height != first_height) {
+                    return Err(GraphicsError::AttachmentSizeMismatch(pass.name.clone()).into());
+                }
+
+                if framebuffer.iter().any(|(_, _, sample_count)| *sample_count != first_sample_count) {
+                    return Err(GraphicsError::AttachmentSampleCountMismatch(pass.name.clone()).into());
+                }
+            }
+
+            for (msaa, target) in render_target.resolve_attachments.iter() {
+                let msaa = self.get_image_resource(msaa.image.id).map(|image| (msaa, &image.description));
+                let target = self.get_image_resource(target.image.id).map(|image| (target, &image.description));
+
+                if let (Some((msaa, msaa_description)), Some((target, target_description))) = (msaa, target) {
+                    let msaa_extent = msaa_description.get_mip_extent(msaa.range.base_mip_level);
+                    let target_extent = target_description.get_mip_extent(target.range.base_mip_level);
+
+                    if msaa_extent != target_extent || msaa_description.format != target_description.format
+                        || msaa_description.sample_count == SampleCount::Type_1 || target_description.sample_count != SampleCount::Type_1 {
+                        return Err(GraphicsError::InvalidResolveTarget(pass.name.clone()).into());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Results have to be written by a pass, unless an imported or persistent image is presented as it is
+    fn validate_results(&self, result_images: &[ImageHandle]) -> Result<(), Error> {
+        let produced: HashSet<ImageHandle> = self.passes.iter()
+            .flat_map(|pass| pass.get_written_images())
+            .chain(self.import_images.iter().chain(self.persistent_images.iter()).map(|image| ImageHandle::new(image.id, 0)))
+            .collect();
+
+        match result_images.iter().find(|image| !produced.contains(image)) {
+            Some(image) => Err(GraphicsError::UnproducedResult(self.get_resource_name(image.id)).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_description(name: &str, description: &ImageDescription) -> Result<(), Error> {
+    if description.is_valid() {
+        Ok(())
+    } else {
+        Err(GraphicsError::InvalidImageDescription(name.to_string()).into())
     }
 }
@@ -1,12 +1,19 @@
-use crate::graphics::*;
+use ash::vk;
+use crate::graphics::{*, vulkan::ImageCreateDescription};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct ImageDescription {
     pub width: u32,
     pub height: u32,
+    /// Only 3D images have a depth other than 1
+    pub depth: u32,
     pub format: ImageFormat,
     pub image_type: ImageType,
     pub sample_count: SampleCount,
+    pub mip_levels: u32,
+    pub array_layers: u32,
+    /// The array layers can be viewed as cube maps, six faces each
+    pub cube_compatible: bool,
     pub initial_layout: ImageLayout,
 }
 
@@ -15,10 +22,85 @@ impl ImageDescription {
         Self {
             width,
             height,
+            depth: 1,
             format,
             image_type,
             sample_count,
+            mip_levels: 1,
+            array_layers: 1,
+            cube_compatible: false,
             initial_layout,
         }
     }
+
+    pub fn with_depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_mip_levels(mut self, mip_levels: u32) -> Self {
+        self.mip_levels = mip_levels;
+        self
+    }
+
+    pub fn with_array_layers(mut self, array_layers: u32) -> Self {
+        self.array_layers = array_layers;
+        self
+    }
+
+    /// Makes a 2D image with six layers for each cube
+    pub fn with_cubes(mut self, cubes: u32) -> Self {
+        self.array_layers = cubes * 6;
+        self.cube_compatible = true;
+        self
+    }
+
+    /// The number of mip levels a full mip chain of the image has
+    pub fn get_max_mip_levels(&self) -> u32 {
+        32 - self.width.max(self.height).max(self.depth).leading_zeros()
+    }
+
+    /// Width, height and depth of a mip level
+    pub fn get_mip_extent(&self, mip_level: u32) -> (u32, u32, u32) {
+        ((self.width >> mip_level).max(1), (self.height >> mip_level).max(1), (self.depth >> mip_level).max(1))
+    }
+
+    /// Whether the combination of type, extent, layers and mip levels can be created
+    pub fn is_valid(&self) -> bool {
+        let extent_valid = match self.image_type {
+            ImageType::Type1D => self.height == 1 && self.depth == 1,
+            ImageType::Type2D => self.depth == 1,
+            ImageType::Type3D => self.array_layers == 1,
+        };
+
+        let cube_valid = !self.cube_compatible
+            || (self.image_type == ImageType::Type2D && self.width == self.height && self.array_layers % 6 == 0);
+
+        extent_valid && cube_valid && self.array_layers > 0 && self.mip_levels > 0 && self.mip_levels <= self.get_max_mip_levels()
+    }
+
+    pub(crate) fn get_create_description(&self, usage: vk::ImageUsageFlags) -> ImageCreateDescription {
+        ImageCreateDescription {
+            image_type: self.image_type.into(),
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            format: self.format.into(),
+            samples: self.sample_count.into(),
+            mip_levels: self.mip_levels,
+            array_layers: self.array_layers,
+            flags: if self.cube_compatible { vk::ImageCreateFlags::CUBE_COMPATIBLE } else { vk::ImageCreateFlags::empty() },
+            usage,
+        }
+    }
+
+    /// Estimated size in bytes, the actual requirements are only known to the device
+    pub fn get_size(&self) -> u64 {
+        let texels: u64 = (0..self.mip_levels)
+            .map(|mip_level| self.get_mip_extent(mip_level))
+            .map(|(width, height, depth)| width as u64 * height as u64 * depth as u64)
+            .sum();
+
+        texels * self.array_layers as u64 * self.format.get_bytes_per_pixel() as u64 * self.sample_count.get_count() as u64
+    }
 }
@@ -1,3 +1,5 @@
+use crate::graphics::rendergraph::*;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct ImageHandle {
     pub(crate) id: u32,
@@ -12,6 +14,20 @@ impl ImageHandle {
         }
     }
 
+    /// A single mip level of this version
+    pub fn mip(self, mip_level: u32) -> ImageSubresourceHandle {
+        ImageSubresourceHandle::new(self, ImageSubresourceRange::all()).mip(mip_level)
+    }
+
+    /// A single array layer of this version
+    pub fn layer(self, array_layer: u32) -> ImageSubresourceHandle {
+        ImageSubresourceHandle::new(self, ImageSubresourceRange::all()).layer(array_layer)
+    }
+
+    pub fn subresource(self, range: ImageSubresourceRange) -> ImageSubresourceHandle {
+        ImageSubresourceHandle::new(self, range)
+    }
+
     pub(crate) fn previous_version(&self) -> Option<Self> {
         if self.version > 0 {
             Some(Self::new(self.id, self.version - 1))
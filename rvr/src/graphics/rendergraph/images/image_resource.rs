@@ -1,10 +1,12 @@
-use crate::graphics::rendergraph::*;
+use crate::graphics::{QueueType, rendergraph::*};
 
 #[derive(Debug)]
 pub struct ImageResource {
     pub id: u32,
     pub name: String,
     pub description: ImageDescription,
+    /// The queue that used a persistent image last frame, a first access on another queue has to take it over
+    pub queue: Option<QueueType>,
 }
 
 impl ImageResource {
@@ -13,6 +15,7 @@ impl ImageResource {
             id,
             name,
             description,
+            queue: None,
         }
     }
 }
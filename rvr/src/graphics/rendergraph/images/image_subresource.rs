@@ -0,0 +1,96 @@
+use crate::graphics::rendergraph::*;
+
+/// Mip levels and array layers of an image, counts of `REMAINING` reach to the last level or layer
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ImageSubresourceRange {
+    pub base_mip_level: u32,
+    pub level_count: u32,
+    pub base_array_layer: u32,
+    pub layer_count: u32,
+}
+
+impl ImageSubresourceRange {
+    pub const REMAINING: u32 = !0;
+
+    pub fn new(base_mip_level: u32, level_count: u32, base_array_layer: u32, layer_count: u32) -> Self {
+        Self {
+            base_mip_level,
+            level_count,
+            base_array_layer,
+            layer_count,
+        }
+    }
+
+    /// Every mip level and array layer
+    pub fn all() -> Self {
+        Self::new(0, Self::REMAINING, 0, Self::REMAINING)
+    }
+
+    /// Replaces remaining counts with the actual ones of the image
+    pub fn resolve(&self, description: &ImageDescription) -> Self {
+        let level_count = if self.level_count == Self::REMAINING {
+            description.mip_levels.saturating_sub(self.base_mip_level)
+        } else {
+            self.level_count
+        };
+
+        let layer_count = if self.layer_count == Self::REMAINING {
+            description.array_layers.saturating_sub(self.base_array_layer)
+        } else {
+            self.layer_count
+        };
+
+        Self::new(self.base_mip_level, level_count, self.base_array_layer, layer_count)
+    }
+
+    /// Whether the resolved ranges share a mip level and array layer
+    pub fn overlaps(&self, other: &ImageSubresourceRange) -> bool {
+        self.base_mip_level < other.base_mip_level + other.level_count && other.base_mip_level < self.base_mip_level + self.level_count
+            && self.base_array_layer < other.base_array_layer + other.layer_count && other.base_array_layer < self.base_array_layer + self.layer_count
+    }
+
+    /// Whether the resolved range lies within the image and isn't empty
+    pub fn is_valid(&self, description: &ImageDescription) -> bool {
+        let range = self.resolve(description);
+
+        range.level_count > 0 && range.layer_count > 0
+            && range.base_mip_level + range.level_count <= description.mip_levels
+            && range.base_array_layer + range.layer_count <= description.array_layers
+    }
+}
+
+/// A version of an image restricted to some of its mip levels and array layers
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ImageSubresourceHandle {
+    pub(crate) image: ImageHandle,
+    pub(crate) range: ImageSubresourceRange,
+}
+
+impl ImageSubresourceHandle {
+    pub fn new(image: ImageHandle, range: ImageSubresourceRange) -> Self {
+        Self {
+            image,
+            range,
+        }
+    }
+
+    /// Restricts the handle to a single mip level
+    pub fn mip(mut self, mip_level: u32) -> Self {
+        self.range.base_mip_level = mip_level;
+        self.range.level_count = 1;
+        self
+    }
+
+    /// Restricts the handle to a single array layer
+    pub fn layer(mut self, array_layer: u32) -> Self {
+        self.range.base_array_layer = array_layer;
+        self.range.layer_count = 1;
+        self
+    }
+}
+
+impl Into<ImageSubresourceHandle> for ImageHandle {
+    fn into(self) -> ImageSubresourceHandle {
+        ImageSubresourceHandle::new(self, ImageSubresourceRange::all())
+    }
+}
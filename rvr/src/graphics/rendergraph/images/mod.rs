@@ -1,7 +1,9 @@
 mod image_description;
 mod image_handle;
 mod image_resource;
+mod image_subresource;
 
 pub use image_description::*;
 pub use image_handle::*;
 pub use image_resource::*;
+pub use image_subresource::*;
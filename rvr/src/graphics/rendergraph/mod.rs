@@ -1,27 +1,41 @@
+mod buffers;
 mod images;
 mod render_targets;
 mod graph;
 mod graph_builder;
 mod pass;
 mod pass_builder;
+mod resource_state;
 mod execute_context;
 mod executor;
+mod export;
 mod schedule;
 mod schedule_builder;
+mod schedule_cache;
+mod submission;
+mod subpass_merging;
+mod transient_allocation;
 
+pub use buffers::*;
 pub use images::*;
 pub use render_targets::*;
 pub use graph::*;
 pub use graph_builder::*;
 pub use pass::*;
 pub use pass_builder::*;
+pub(crate) use resource_state::*;
 pub use execute_context::*;
 pub use executor::*;
 pub use schedule::*;
 pub use schedule_builder::*;
+pub use schedule_cache::*;
+pub use submission::*;
+pub(crate) use subpass_merging::*;
+pub use transient_allocation::*;
 
 #[cfg(test)]
 mod tests {
+    use ash::vk;
     use crate::graphics::{
         *,
         rendergraph::*,
@@ -40,16 +54,1299 @@ mod tests {
             ImageLayout::Unknown,
         )).unwrap();
 
-        let graph = builder.build(&[back_buffer]);
+        let graph = builder.build(&[back_buffer]).unwrap();
 
-        let schedule = graph.compile_schedule();
+        let schedule = graph.compile_schedule().unwrap();
 
         dbg!(schedule);
     }
 
     #[test]
-    fn it_works() {
-        let start = std::time::Instant::now();
+    fn it_creates_executes_and_releases() {
+        let mut builder = GraphBuilder::new();
+
+        let back_buffer = builder.import_image("Back Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::B8G8R8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let color_buffer = builder.create_image("Color Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let unused_buffer = builder.create_image("Unused Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let color_buffer = builder.add_pass("Render Scene", |builder| {
+            builder.color_attachment(color_buffer)
+        }, |_, _| {}).unwrap();
+
+        builder.add_pass("Unused", |builder| {
+            builder.color_attachment(unused_buffer)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Post Process", |builder| {
+            builder.sample_image(color_buffer);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let graph = builder.build(&[back_buffer]).unwrap();
+        let schedule = graph.compile_schedule().unwrap();
+
+        assert_eq!(schedule.get_submissions().len(), 1);
+        assert_eq!(schedule.get_submissions()[0].get_instructions(), &[
+            Instruction::CreateImage {
+                id: color_buffer.id,
+                description: ImageDescription::new(
+                    1920,
+                    1080,
+                    ImageFormat::R8G8B8A8_SRGB,
+                    ImageType::Type2D,
+                    SampleCount::Type_1,
+                    ImageLayout::Unknown,
+                ),
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+                allocation: 0,
+            },
+            Instruction::ImageLayoutBarrier {
+                id: color_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::Unknown,
+                to: ImageLayout::ColorAttachment,
+                src_stage_mask: vk::PipelineStageFlags::TOP_OF_PIPE,
+                dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                src_access_mask: vk::AccessFlags::empty(),
+                dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            },
+            Instruction::ExecutePass {
+                id: 3,
+                bind_point: PipelineBindPoint::Graphics,
+                color_attachments: vec![color_buffer.into()],
+                depth_stencil_attachment: None,
+                input_attachments: vec![],
+                resolve_attachments: vec![],
+                attachment_ops: vec![AttachmentOps::new(LoadOp::DontCare, StoreOp::Store)],
+            },
+            Instruction::ImageLayoutBarrier {
+                id: color_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::ColorAttachment,
+                to: ImageLayout::ShaderSample,
+                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+            },
+            Instruction::ImageLayoutBarrier {
+                id: back_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::Unknown,
+                to: ImageLayout::ColorAttachment,
+                src_stage_mask: vk::PipelineStageFlags::ALL_COMMANDS,
+                dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                src_access_mask: vk::AccessFlags::empty(),
+                dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            },
+            Instruction::ExecutePass {
+                id: 5,
+                bind_point: PipelineBindPoint::Graphics,
+                color_attachments: vec![back_buffer.into()],
+                depth_stencil_attachment: None,
+                input_attachments: vec![],
+                resolve_attachments: vec![],
+                attachment_ops: vec![AttachmentOps::new(LoadOp::Load, StoreOp::Store)],
+            },
+            Instruction::ReleaseImage {
+                id: color_buffer.id,
+            },
+            Instruction::ImageLayoutBarrier {
+                id: back_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::ColorAttachment,
+                to: ImageLayout::Present,
+                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access_mask: vk::AccessFlags::empty(),
+            },
+            Instruction::Present {
+                id: back_buffer.id,
+            },
+        ][..]);
+    }
+
+    #[test]
+    fn it_aliases_transient_images() {
+        let mut builder = GraphBuilder::new();
+
+        let back_buffer = builder.import_image("Back Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::B8G8R8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let scene_buffer = builder.create_image("Scene Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let bloom_buffer = builder.create_image("Bloom Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R32G32B32A32_SFLOAT,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let tonemapped_buffer = builder.create_image("Tonemapped Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let scene_buffer = builder.add_pass("Render Scene", |builder| {
+            builder.color_attachment(scene_buffer)
+        }, |_, _| {}).unwrap();
+
+        let bloom_buffer = builder.add_pass("Bloom", |builder| {
+            builder.sample_image(scene_buffer);
+            builder.color_attachment(bloom_buffer)
+        }, |_, _| {}).unwrap();
+
+        let tonemapped_buffer = builder.add_pass("Tonemap", |builder| {
+            builder.sample_image(bloom_buffer);
+            builder.color_attachment(tonemapped_buffer)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Post Process", |builder| {
+            builder.sample_image(tonemapped_buffer);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[back_buffer]).unwrap().compile_schedule().unwrap();
+
+        // the scene is no longer needed once the tonemapped image gets written
+        assert_eq!(schedule.get_allocations(), &[
+            TransientAllocation {
+                id: 0,
+                size: 1920 * 1080 * 16,
+                images: vec![bloom_buffer.id],
+            },
+            TransientAllocation {
+                id: 1,
+                size: 1920 * 1080 * 4,
+                images: vec![scene_buffer.id, tonemapped_buffer.id],
+            },
+        ][..]);
+        assert_eq!(schedule.get_memory_usage(), 1920 * 1080 * 20);
+        assert_eq!(schedule.get_unaliased_memory_usage(), 1920 * 1080 * 24);
+
+        // the tonemapped image has to wait until the scene was sampled by the bloom pass
+        let first_barrier = schedule.get_instructions().into_iter()
+            .find(|instruction| match instruction {
+                Instruction::ImageLayoutBarrier { id, .. } => *id == tonemapped_buffer.id,
+                _ => false,
+            })
+            .unwrap();
+
+        assert_eq!(first_barrier, &Instruction::ImageLayoutBarrier {
+            id: tonemapped_buffer.id,
+            subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+            from: ImageLayout::Unknown,
+            to: ImageLayout::ColorAttachment,
+            src_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            src_access_mask: vk::AccessFlags::empty(),
+            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        });
+    }
+
+    #[test]
+    fn it_transitions_storage_images_of_compute_passes() {
+        let mut builder = GraphBuilder::new();
+
+        let back_buffer = builder.import_image("Back Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::B8G8R8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let scene_buffer = builder.create_image("Scene Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let blur_buffer = builder.create_image("Blur Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R32G32B32A32_SFLOAT,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let scene_buffer = builder.add_pass("Render Scene", |builder| {
+            builder.color_attachment(scene_buffer)
+        }, |_, _| {}).unwrap();
+
+        let blur_buffer = builder.add_compute_pass("Blur", |builder| {
+            builder.sample_image(scene_buffer);
+            builder.write_storage_image(blur_buffer)
+        }, |_, _| {}).unwrap();
+
+        let blur_buffer = builder.add_compute_pass("Sharpen", |builder| {
+            builder.read_storage_image(blur_buffer);
+            builder.write_storage_image(blur_buffer)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Post Process", |builder| {
+            builder.sample_image(blur_buffer);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[back_buffer]).unwrap().compile_schedule().unwrap();
+
+        let bind_points: Vec<_> = schedule.get_instructions().iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::ExecutePass { bind_point, .. } => Some(*bind_point),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(bind_points, vec![
+            PipelineBindPoint::Graphics,
+            PipelineBindPoint::Compute,
+            PipelineBindPoint::Compute,
+            PipelineBindPoint::Graphics,
+        ]);
+
+        let usage = schedule.get_instructions().iter()
+            .find_map(|instruction| match instruction {
+                Instruction::CreateImage { id, usage, .. } if *id == blur_buffer.id => Some(*usage),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(usage, vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::SAMPLED);
+
+        let barriers: Vec<_> = schedule.get_instructions().iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::ImageLayoutBarrier { id, from, to, src_stage_mask, dst_stage_mask, src_access_mask, dst_access_mask, .. } if *id != back_buffer.id => {
+                    Some((*id, *from, *to, *src_stage_mask, *dst_stage_mask, *src_access_mask, *dst_access_mask))
+                },
+                _ => None,
+            })
+            .collect();
+
+        let compute_shader = vk::PipelineStageFlags::COMPUTE_SHADER;
+        let shader_read_write = vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE;
+
+        assert_eq!(barriers, vec![
+            (scene_buffer.id, ImageLayout::Unknown, ImageLayout::ColorAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::AccessFlags::empty(), vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE),
+            // Blur
+            (scene_buffer.id, ImageLayout::ColorAttachment, ImageLayout::ShaderSample, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, compute_shader, vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::SHADER_READ),
+            (blur_buffer.id, ImageLayout::Unknown, ImageLayout::General, vk::PipelineStageFlags::TOP_OF_PIPE, compute_shader, vk::AccessFlags::empty(), shader_read_write),
+            // Sharpen reads and writes in place with a single barrier
+            (blur_buffer.id, ImageLayout::General, ImageLayout::General, compute_shader, compute_shader, vk::AccessFlags::SHADER_WRITE, shader_read_write),
+            // Post Process
+            (blur_buffer.id, ImageLayout::General, ImageLayout::ShaderSample, compute_shader, vk::PipelineStageFlags::FRAGMENT_SHADER, vk::AccessFlags::SHADER_WRITE, vk::AccessFlags::SHADER_READ),
+        ]);
+    }
+
+    #[test]
+    fn it_waits_for_the_backbuffer_in_the_first_submission_using_it() {
+        let mut builder = GraphBuilder::new();
+
+        let back_buffer = builder.import_image("Back Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::B8G8R8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let particles = builder.create_buffer("Particles", BufferDescription::new(1024)).unwrap();
+
+        let particles = builder.add_async_compute_pass("Simulate Particles", |builder| {
+            builder.write_storage_buffer(particles)
+        }, |_, _| {}).unwrap();
+
+        let result = builder.add_pass("Render Particles", |builder| {
+            builder.read_storage_buffer(particles);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[result]).unwrap().compile_schedule().unwrap();
+
+        let queues: Vec<QueueType> = schedule.get_submissions().iter().map(|submission| submission.get_queue()).collect();
+        assert_eq!(queues, vec![QueueType::Compute, QueueType::Graphics]);
+
+        assert!(!schedule.get_submissions()[0].uses_image(back_buffer.id));
+        assert_eq!(schedule.get_first_submission_using(back_buffer.id), Some(1));
+    }
+
+    #[test]
+    fn it_splits_async_compute_into_submissions() {
+        let mut builder = GraphBuilder::new();
+
+        let back_buffer = builder.import_image("Back Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::B8G8R8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let scene_buffer = builder.create_image("Scene Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let histogram = builder.create_buffer("Histogram", BufferDescription::new(1024)).unwrap();
+
+        let scene_buffer = builder.add_pass("Render Scene", |builder| {
+            builder.color_attachment(scene_buffer)
+        }, |_, _| {}).unwrap();
+
+        let histogram = builder.add_async_compute_pass("Histogram", |builder| {
+            builder.sample_image(scene_buffer);
+            builder.write_storage_buffer(histogram)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Tonemap", |builder| {
+            builder.sample_image(scene_buffer);
+            builder.read_storage_buffer(histogram);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[back_buffer]).unwrap().compile_schedule().unwrap();
+        let submissions = schedule.get_submissions();
+
+        let queues: Vec<QueueType> = submissions.iter().map(|submission| submission.get_queue()).collect();
+        assert_eq!(queues, vec![QueueType::Graphics, QueueType::Compute, QueueType::Graphics]);
+
+        let signals: Vec<bool> = submissions.iter().map(|submission| submission.signals()).collect();
+        assert_eq!(signals, vec![true, true, false]);
+
+        assert_eq!(submissions[0].get_waits(), &[][..]);
+        assert_eq!(submissions[1].get_waits(), &[
+            SemaphoreWait {
+                submission: 0,
+                stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER,
+            },
+        ][..]);
+        assert_eq!(submissions[2].get_waits(), &[
+            SemaphoreWait {
+                submission: 1,
+                stage_mask: vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            },
+        ][..]);
+
+        // the graphics queue releases the scene after rendering it, the compute queue acquires it before sampling
+        assert_eq!(submissions[0].get_instructions().last(), Some(&Instruction::ImageOwnershipRelease {
+            id: scene_buffer.id,
+            subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+            from: ImageLayout::ColorAttachment,
+            to: ImageLayout::ShaderSample,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            dst_queue: QueueType::Compute,
+        }));
+
+        let ownership: Vec<&Instruction> = submissions[1].get_instructions().iter()
+            .filter(|instruction| match instruction {
+                Instruction::ImageOwnershipAcquire { .. } | Instruction::ImageOwnershipRelease { .. } | Instruction::BufferOwnershipRelease { .. } => true,
+                _ => false,
+            })
+            .collect();
+
+        assert_eq!(ownership, vec![
+            &Instruction::ImageOwnershipAcquire {
+                id: scene_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::ColorAttachment,
+                to: ImageLayout::ShaderSample,
+                dst_stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+                src_queue: QueueType::Graphics,
+            },
+            // the scene goes back to the graphics queue without another transition
+            &Instruction::ImageOwnershipRelease {
+                id: scene_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::ShaderSample,
+                to: ImageLayout::ShaderSample,
+                src_stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER,
+                src_access_mask: vk::AccessFlags::empty(),
+                dst_queue: QueueType::Graphics,
+            },
+            &Instruction::BufferOwnershipRelease {
+                id: histogram.id,
+                src_stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER,
+                src_access_mask: vk::AccessFlags::SHADER_WRITE,
+                dst_queue: QueueType::Graphics,
+            },
+        ]);
+
+        let acquires: Vec<&Instruction> = submissions[2].get_instructions().iter()
+            .filter(|instruction| match instruction {
+                Instruction::ImageOwnershipAcquire { .. } | Instruction::BufferOwnershipAcquire { .. } => true,
+                _ => false,
+            })
+            .collect();
+
+        assert_eq!(acquires, vec![
+            &Instruction::ImageOwnershipAcquire {
+                id: scene_buffer.id,
+                subresource: ImageSubresourceRange::new(0, 1, 0, 1),
+                from: ImageLayout::ShaderSample,
+                to: ImageLayout::ShaderSample,
+                dst_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+                src_queue: QueueType::Compute,
+            },
+            &Instruction::BufferOwnershipAcquire {
+                id: histogram.id,
+                dst_stage_mask: vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+                src_queue: QueueType::Compute,
+            },
+        ]);
+    }
+
+    #[test]
+    fn it_rejects_attachments_in_compute_passes() {
+        let mut builder = GraphBuilder::new();
+
+        let image = builder.create_image("Image", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let result = builder.add_compute_pass("Compute", |builder| {
+            builder.color_attachment(image)
+        }, |_, _| {});
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_orders_and_synchronizes_buffers() {
+        let mut builder = GraphBuilder::new();
+
+        let back_buffer = builder.import_image("Back Buffer", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::B8G8R8A8_SRGB,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let camera = builder.import_buffer("Camera", BufferDescription::new(256)).unwrap();
+        let instances = builder.create_buffer("Instances", BufferDescription::new(4096)).unwrap();
+
+        // culling writes the instances and indirect arguments the draw consumes
+        let culled_instances = builder.add_compute_pass("Cull", |builder| {
+            builder.read_uniform_buffer(camera);
+            builder.write_storage_buffer(instances)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Draw", |builder| {
+            builder.read_vertex_buffer(culled_instances);
+            builder.read_indirect_buffer(culled_instances);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Debug Overlay", |builder| {
+            builder.read_storage_buffer(culled_instances);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[back_buffer]).unwrap().compile_schedule().unwrap();
+
+        let instructions: Vec<&Instruction> = schedule.get_instructions().into_iter()
+            .filter(|instruction| match instruction {
+                Instruction::ImageLayoutBarrier { .. } | Instruction::Present { .. } => false,
+                _ => true,
+            })
+            .collect();
+
+        assert_eq!(instructions, vec![
+            &Instruction::CreateBuffer {
+                id: instances.id,
+                description: BufferDescription::new(4096),
+                usage: vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::INDIRECT_BUFFER,
+            },
+            // neither reading the camera nor writing the new buffer has to wait for anything
+            &Instruction::ExecutePass {
+                id: 3,
+                bind_point: PipelineBindPoint::Compute,
+                color_attachments: vec![],
+                depth_stencil_attachment: None,
+                input_attachments: vec![],
+                resolve_attachments: vec![],
+                attachment_ops: vec![],
+            },
+            // both reads of the same pass share one barrier
+            &Instruction::BufferBarrier {
+                id: instances.id,
+                src_stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER,
+                dst_stage_mask: vk::PipelineStageFlags::VERTEX_INPUT | vk::PipelineStageFlags::DRAW_INDIRECT,
+                src_access_mask: vk::AccessFlags::SHADER_WRITE,
+                dst_access_mask: vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDIRECT_COMMAND_READ,
+            },
+            // the write is not yet visible to shader reads, the barrier moves in front of the shared render pass
+            &Instruction::BufferBarrier {
+                id: instances.id,
+                src_stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER,
+                dst_stage_mask: vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                src_access_mask: vk::AccessFlags::SHADER_WRITE,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+            },
+            &Instruction::ExecuteSubpasses {
+                subpasses: vec![
+                    Subpass {
+                        id: 4,
+                        color_attachments: vec![ImageHandle::new(back_buffer.id, 1).into()],
+                        depth_stencil_attachment: None,
+                        input_attachments: vec![],
+                        resolve_attachments: vec![],
+                        attachment_ops: vec![AttachmentOps::new(LoadOp::Load, StoreOp::Store)],
+                    },
+                    Subpass {
+                        id: 5,
+                        color_attachments: vec![back_buffer.into()],
+                        depth_stencil_attachment: None,
+                        input_attachments: vec![],
+                        resolve_attachments: vec![],
+                        attachment_ops: vec![AttachmentOps::new(LoadOp::Load, StoreOp::Store)],
+                    },
+                ],
+                dependencies: vec![SubpassDependency {
+                    src_subpass: 0,
+                    dst_subpass: 1,
+                    src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                }],
+            },
+            &Instruction::ReleaseBuffer {
+                id: instances.id,
+            },
+        ]);
+    }
+
+    #[test]
+    fn it_merges_passes_into_subpasses() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let albedo = builder.create_image("Albedo", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let normals = builder.create_image("Normals", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let scene = builder.create_image("Scene", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        let (albedo, normals) = builder.add_pass("G-Buffer", |builder| {
+            Ok((builder.clear_color_attachment(albedo, [0.0, 0.0, 0.0, 1.0])?, builder.color_attachment(normals)?))
+        }, |_, _| {}).unwrap();
+
+        let scene = builder.add_pass("Lighting", |builder| {
+            builder.input_attachment(albedo)?;
+            builder.input_attachment(normals)?;
+            builder.color_attachment(scene)
+        }, |_, _| {}).unwrap();
+
+        // sampling an attachment of the render pass needs the render pass to end first
+        let back_buffer = builder.add_pass("Tonemap", |builder| {
+            builder.sample_image(scene);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[back_buffer]).unwrap().compile_schedule().unwrap();
+
+        let passes: Vec<&Instruction> = schedule.get_instructions().into_iter()
+            .filter(|instruction| match instruction {
+                Instruction::ExecutePass { .. } | Instruction::ExecuteSubpasses { .. } => true,
+                _ => false,
+            })
+            .collect();
+
+        assert_eq!(passes, vec![
+            &Instruction::ExecuteSubpasses {
+                subpasses: vec![
+                    Subpass {
+                        id: 4,
+                        color_attachments: vec![albedo.into(), normals.into()],
+                        depth_stencil_attachment: None,
+                        input_attachments: vec![],
+                        resolve_attachments: vec![],
+                        attachment_ops: vec![
+                            AttachmentOps::new(LoadOp::Clear(ClearValue::Color([0.0, 0.0, 0.0, 1.0])), StoreOp::Store),
+                            AttachmentOps::new(LoadOp::DontCare, StoreOp::Store),
+                        ],
+                    },
+                    Subpass {
+                        id: 5,
+                        color_attachments: vec![scene.into()],
+                        depth_stencil_attachment: None,
+                        input_attachments: vec![albedo.into(), normals.into()],
+                        resolve_attachments: vec![],
+                        attachment_ops: vec![
+                            AttachmentOps::new(LoadOp::DontCare, StoreOp::Store),
+                            // nothing after the lighting needs the g-buffer
+                            AttachmentOps::new(LoadOp::Load, StoreOp::DontCare),
+                            AttachmentOps::new(LoadOp::Load, StoreOp::DontCare),
+                        ],
+                    },
+                ],
+                // the barriers of both attachments end up in the same dependency
+                dependencies: vec![SubpassDependency {
+                    src_subpass: 0,
+                    dst_subpass: 1,
+                    src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    dst_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                    src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    dst_access_mask: vk::AccessFlags::INPUT_ATTACHMENT_READ,
+                }],
+            },
+            &Instruction::ExecutePass {
+                id: 6,
+                bind_point: PipelineBindPoint::Graphics,
+                color_attachments: vec![back_buffer.into()],
+                depth_stencil_attachment: None,
+                input_attachments: vec![],
+                resolve_attachments: vec![],
+                attachment_ops: vec![AttachmentOps::new(LoadOp::Load, StoreOp::Store)],
+            },
+        ]);
+
+        // the first write of the scene is transitioned before the render pass begins
+        let instructions = schedule.get_instructions();
+        let render_pass = instructions.iter().position(|instruction| match instruction {
+            Instruction::ExecuteSubpasses { .. } => true,
+            _ => false,
+        }).unwrap();
+
+        assert!(instructions[..render_pass].iter().any(|instruction| match instruction {
+            Instruction::ImageLayoutBarrier { id, .. } => *id == scene.id,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn it_infers_load_and_store_ops() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let scene = builder.create_image("Scene", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let depth = builder.create_image("Depth", ImageDescription::new(
+            1920,
+            1080,
+            ImageFormat::D32_SFLOAT,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        )).unwrap();
+
+        let scene = builder.add_pass("Scene", |builder| {
+            builder.clear_depth_stencil_attachment(depth, 1.0, 0)?;
+            builder.color_attachment(scene)
+        }, |_, _| {}).unwrap();
+
+        // the back buffer gets cleared, so whatever was in it before is not needed
+        let cleared = builder.add_pass("Clear", |builder| builder.color_attachment(back_buffer), |_, _| {}).unwrap();
+        let back_buffer = builder.add_pass("Compose", |builder| {
+            builder.sample_image(scene);
+            builder.clear_color_attachment(cleared, [0.0; 4])
+        }, |_, _| {}).unwrap();
+
+        let schedule = builder.build(&[back_buffer]).unwrap().compile_schedule().unwrap();
+
+        let attachment_ops: Vec<(u32, Vec<AttachmentOps>)> = schedule.get_instructions().into_iter()
+            .flat_map(|instruction| match instruction {
+                Instruction::ExecutePass { id, attachment_ops, .. } => vec![(*id, attachment_ops.clone())],
+                Instruction::ExecuteSubpasses { subpasses, .. } => subpasses.iter()
+                    .map(|subpass| (subpass.id, subpass.attachment_ops.clone()))
+                    .collect(),
+                _ => vec![],
+            })
+            .collect();
+
+        assert_eq!(attachment_ops, vec![
+            // the depth buffer is only needed while the scene gets rendered
+            (3, vec![
+                AttachmentOps::new(LoadOp::DontCare, StoreOp::Store),
+                AttachmentOps::new(LoadOp::Clear(ClearValue::DepthStencil { depth: 1.0, stencil: 0 }), StoreOp::DontCare),
+            ]),
+            // imported images keep their contents
+            (4, vec![AttachmentOps::new(LoadOp::Load, StoreOp::Store)]),
+            (5, vec![AttachmentOps::new(LoadOp::Clear(ClearValue::Color([0.0; 4])), StoreOp::Store)]),
+        ]);
+    }
+
+    #[test]
+    fn it_resolves_multisampled_attachments() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let lines = builder.create_image("Lines", image_description(1920, 1080, SampleCount::Type_4)).unwrap();
+
+        let back_buffer = builder.add_pass("Lines", |builder| {
+            let lines = builder.clear_color_attachment(lines, [0.0; 4])?;
+            builder.resolve_attachment(lines, back_buffer)
+        }, |_, _| {}).unwrap();
+
+        assert!(builder.add_pass("Resolve Without Attachment", |builder| {
+            builder.resolve_attachment(lines, back_buffer)
+        }, |_, _| {}).is_err());
+
+        let graph = builder.build(&[back_buffer]).unwrap();
+        let schedule = graph.compile_schedule().unwrap();
+
+        let pass = schedule.get_instructions().into_iter()
+            .find(|instruction| match instruction {
+                Instruction::ExecutePass { .. } => true,
+                _ => false,
+            })
+            .unwrap();
+
+        // the multisampled image only lives in the pass, the resolved one gets presented
+        assert_eq!(pass, &Instruction::ExecutePass {
+            id: 2,
+            bind_point: PipelineBindPoint::Graphics,
+            color_attachments: vec![ImageHandle::new(lines.id, 1).into()],
+            depth_stencil_attachment: None,
+            input_attachments: vec![],
+            resolve_attachments: vec![(ImageHandle::new(lines.id, 1).into(), back_buffer.into())],
+            attachment_ops: vec![
+                AttachmentOps::new(LoadOp::Clear(ClearValue::Color([0.0; 4])), StoreOp::DontCare),
+                AttachmentOps::new(LoadOp::DontCare, StoreOp::Store),
+            ],
+        });
+
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let lines = builder.create_image("Lines", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        let back_buffer = builder.add_pass("Lines", |builder| {
+            let lines = builder.color_attachment(lines)?;
+            builder.resolve_attachment(lines, back_buffer)
+        }, |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[back_buffer]).err().unwrap()) {
+            GraphicsError::InvalidResolveTarget(pass) => assert_eq!(pass, "Lines"),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    /// Temporal anti aliasing blends the current frame with the history of the last one
+    fn build_temporal_graph() -> (Graph, ImageHandle) {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let scene = builder.create_image("Scene", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let history = builder.persistent_image("History", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        assert!(builder.persistent_image("History", image_description(1920, 1080, SampleCount::Type_1)).is_err());
+
+        let scene = builder.add_pass("Resolve", |builder| {
+            builder.sample_image(history);
+            builder.color_attachment(scene)
+        }, |_, _| {}).unwrap();
+
+        builder.add_pass("Update History", |builder| {
+            builder.sample_image(scene);
+            builder.color_attachment(history)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Present", |builder| {
+            builder.sample_image(scene);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        (builder.build(&[back_buffer]).unwrap(), history)
+    }
+
+    fn get_image_barriers(schedule: &Schedule, image: ImageHandle) -> Vec<(ImageLayout, ImageLayout, vk::PipelineStageFlags)> {
+        schedule.get_instructions().into_iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::ImageLayoutBarrier { id, from, to, src_stage_mask, .. } if *id == image.id => Some((*from, *to, *src_stage_mask)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_keeps_persistent_images_between_frames() {
+        let (graph, history) = build_temporal_graph();
+        let schedule = graph.compile_schedule().unwrap();
+
+        // the first read waits for last frame's writes, the frame ends with the image ready to be sampled again
+        assert_eq!(get_image_barriers(&schedule, history), vec![
+            (ImageLayout::ShaderSample, ImageLayout::ShaderSample, vk::PipelineStageFlags::ALL_COMMANDS),
+            (ImageLayout::ShaderSample, ImageLayout::ColorAttachment, vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::ALL_COMMANDS),
+            (ImageLayout::ColorAttachment, ImageLayout::ShaderSample, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT),
+        ]);
+
+        // persistent images are never created or released by the schedule
+        assert!(schedule.get_instructions().into_iter().all(|instruction| match instruction {
+            Instruction::CreateImage { id, .. } | Instruction::ReleaseImage { id } => *id != history.id,
+            _ => true,
+        }));
+
+        let (mut graph, history) = build_temporal_graph();
+        graph.discard_contents(history.id);
+        let schedule = graph.compile_schedule().unwrap();
+
+        assert_eq!(get_image_barriers(&schedule, history)[0], (ImageLayout::Unknown, ImageLayout::ShaderSample, vk::PipelineStageFlags::ALL_COMMANDS));
+    }
+
+    #[test]
+    fn it_takes_over_persistent_images_from_the_queue_of_the_last_frame() {
+        let build_graph = || {
+            let mut builder = GraphBuilder::new();
+            let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+            let history = builder.persistent_image("History", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+            // async compute is the first to use last frame's version
+            let history = builder.add_async_compute_pass("Reproject", |builder| builder.write_storage_image(history), |_, _| {}).unwrap();
+
+            let back_buffer = builder.add_pass("Present", |builder| {
+                builder.sample_image(history);
+                builder.color_attachment(back_buffer)
+            }, |_, _| {}).unwrap();
+
+            (builder.build(&[back_buffer]).unwrap(), history)
+        };
+
+        // the first frame has nothing to take over
+        let (mut graph, history) = build_graph();
+        graph.discard_contents(history.id);
+        let schedule = graph.compile_schedule().unwrap();
+        let queues: Vec<QueueType> = schedule.get_submissions().iter().map(|submission| submission.get_queue()).collect();
+
+        assert_eq!(queues, vec![QueueType::Compute, QueueType::Graphics]);
+        assert_eq!(schedule.get_persistent_image_states(), &[(history.id, ImageLayout::ShaderSample, QueueType::Graphics)][..]);
+
+        // the next frame releases the image on the graphics queue, where the last frame left it
+        let (mut graph, history) = build_graph();
+        graph.set_previous_state(history.id, ImageLayout::ShaderSample, QueueType::Graphics);
+        let schedule = graph.compile_schedule().unwrap();
+        let submissions = schedule.get_submissions();
+        let queues: Vec<QueueType> = submissions.iter().map(|submission| submission.get_queue()).collect();
+
+        assert_eq!(queues, vec![QueueType::Graphics, QueueType::Compute, QueueType::Graphics]);
+        assert!(matches!(submissions[0].get_instructions(), [Instruction::ImageOwnershipRelease {
+            from: ImageLayout::ShaderSample,
+            to: ImageLayout::General,
+            dst_queue: QueueType::Compute,
+            ..
+        }]));
+        assert!(submissions[0].signals());
+        assert_eq!(submissions[1].get_waits(), &[SemaphoreWait { submission: 0, stage_mask: vk::PipelineStageFlags::COMPUTE_SHADER }][..]);
+        assert!(matches!(submissions[1].get_instructions()[0], Instruction::ImageOwnershipAcquire { src_queue: QueueType::Graphics, .. }));
+
+        // a different previous queue compiles to a different schedule
+        let (graph, _) = build_graph();
+        let (mut previous_graph, history) = build_graph();
+        previous_graph.set_previous_state(history.id, ImageLayout::ShaderSample, QueueType::Graphics);
+        assert_ne!(graph.get_structure_hash(), previous_graph.get_structure_hash());
+    }
+
+    fn get_subresource_barriers(schedule: &Schedule, image: ImageHandle) -> Vec<(ImageLayout, ImageLayout, ImageSubresourceRange)> {
+        schedule.get_instructions().into_iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::ImageLayoutBarrier { id, subresource, from, to, .. } if *id == image.id => Some((*from, *to, *subresource)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_transitions_mip_levels_and_layers_separately() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1024, 1024, SampleCount::Type_1)).unwrap();
+        let bloom = builder.create_image("Bloom", image_description(1024, 1024, SampleCount::Type_1).with_mip_levels(4)).unwrap();
+        let shadow_map = builder.create_image("Shadow Map", ImageDescription::new(
+            1024,
+            1024,
+            ImageFormat::D32_SFLOAT,
+            ImageType::Type2D,
+            SampleCount::Type_1,
+            ImageLayout::Unknown,
+        ).with_array_layers(4)).unwrap();
+
+        // every cascade renders to its own layer
+        let mut shadow_map = shadow_map;
+        for cascade in 0..4 {
+            shadow_map = builder.add_pass(&format!("Cascade {}", cascade), |builder| {
+                builder.clear_depth_stencil_attachment(shadow_map.layer(cascade), 1.0, 0)
+            }, |_, _| {}).unwrap();
+        }
+
+        // the bloom mip chain samples each level to render the next smaller one
+        let mut bloom = builder.add_pass("Scene", |builder| {
+            builder.sample_image(shadow_map);
+            builder.color_attachment(bloom.mip(0))
+        }, |_, _| {}).unwrap();
+
+        for mip_level in 1..4 {
+            bloom = builder.add_pass(&format!("Downsample {}", mip_level), |builder| {
+                builder.sample_image(bloom.mip(mip_level - 1));
+                builder.color_attachment(bloom.mip(mip_level))
+            }, |_, _| {}).unwrap();
+        }
+
+        let back_buffer = builder.add_pass("Present", |builder| {
+            builder.sample_image(bloom);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let graph = builder.build(&[back_buffer]).unwrap();
+        let schedule = graph.compile_schedule().unwrap();
+
+        let mip = |mip_level| ImageSubresourceRange::new(mip_level, 1, 0, 1);
+        assert_eq!(get_subresource_barriers(&schedule, bloom), vec![
+            (ImageLayout::Unknown, ImageLayout::ColorAttachment, mip(0)),
+            (ImageLayout::ColorAttachment, ImageLayout::ShaderSample, mip(0)),
+            (ImageLayout::Unknown, ImageLayout::ColorAttachment, mip(1)),
+            (ImageLayout::ColorAttachment, ImageLayout::ShaderSample, mip(1)),
+            (ImageLayout::Unknown, ImageLayout::ColorAttachment, mip(2)),
+            (ImageLayout::ColorAttachment, ImageLayout::ShaderSample, mip(2)),
+            (ImageLayout::Unknown, ImageLayout::ColorAttachment, mip(3)),
+            // the other levels already got sampled in the same stages
+            (ImageLayout::ColorAttachment, ImageLayout::ShaderSample, mip(3)),
+        ]);
+
+        // the layers end up in the same state, so sampling all of them takes a single barrier
+        let layer = |array_layer| ImageSubresourceRange::new(0, 1, array_layer, 1);
+        assert_eq!(get_subresource_barriers(&schedule, shadow_map), vec![
+            (ImageLayout::Unknown, ImageLayout::DepthStencilAttachment, layer(0)),
+            (ImageLayout::Unknown, ImageLayout::DepthStencilAttachment, layer(1)),
+            (ImageLayout::Unknown, ImageLayout::DepthStencilAttachment, layer(2)),
+            (ImageLayout::Unknown, ImageLayout::DepthStencilAttachment, layer(3)),
+            (ImageLayout::DepthStencilAttachment, ImageLayout::ShaderSample, ImageSubresourceRange::new(0, 1, 0, 4)),
+        ]);
+    }
+
+    #[test]
+    fn it_writes_several_layers_in_a_single_pass() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1024, 1024, SampleCount::Type_1)).unwrap();
+        let shadow_map = builder.create_image("Shadow Map", image_description(1024, 1024, SampleCount::Type_1).with_array_layers(4)).unwrap();
+
+        // all cascades get rendered by the same pass, every write produces the same version
+        let shadow_map = builder.add_compute_pass("Cascades", |builder| {
+            let mut written = shadow_map;
+            for cascade in 0..4 {
+                written = builder.write_storage_image(shadow_map.layer(cascade))?;
+            }
+            Ok(written)
+        }, |_, _| {}).unwrap();
+
+        let back_buffer = builder.add_pass("Present", |builder| {
+            builder.sample_image(shadow_map);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let graph = builder.build(&[back_buffer]).unwrap();
+        let schedule = graph.compile_schedule().unwrap();
+
+        let layer = |array_layer| ImageSubresourceRange::new(0, 1, array_layer, 1);
+        assert_eq!(get_subresource_barriers(&schedule, shadow_map), vec![
+            (ImageLayout::Unknown, ImageLayout::General, layer(0)),
+            (ImageLayout::Unknown, ImageLayout::General, layer(1)),
+            (ImageLayout::Unknown, ImageLayout::General, layer(2)),
+            (ImageLayout::Unknown, ImageLayout::General, layer(3)),
+            (ImageLayout::General, ImageLayout::ShaderSample, ImageSubresourceRange::new(0, 1, 0, 4)),
+        ]);
+
+        // writing the same layer twice is still a conflict
+        let mut builder = GraphBuilder::new();
+        let shadow_map = builder.create_image("Shadow Map", image_description(1024, 1024, SampleCount::Type_1).with_array_layers(4)).unwrap();
+
+        builder.add_compute_pass("Cascades", |builder| {
+            builder.write_storage_image(shadow_map)?;
+            builder.write_storage_image(shadow_map.layer(2))
+        }, |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[]).err().unwrap()) {
+            GraphicsError::ConflictingWrites { first_pass, second_pass, .. } => assert_eq!((first_pass.as_str(), second_pass.as_str()), ("Cascades", "Cascades")),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_subresources() {
+        let mut builder = GraphBuilder::new();
+        let cube = ImageDescription::new(1024, 512, ImageFormat::R8G8B8A8_SRGB, ImageType::Type2D, SampleCount::Type_1, ImageLayout::Unknown).with_cubes(1);
+
+        match get_graphics_error(builder.create_image("Environment", cube).err().unwrap()) {
+            GraphicsError::InvalidImageDescription(name) => assert_eq!(name, "Environment"),
+            error => panic!("unexpected error {}", error),
+        }
+
+        let mut builder = GraphBuilder::new();
+        let image = builder.create_image("Image", image_description(1024, 1024, SampleCount::Type_1).with_mip_levels(4)).unwrap();
+        builder.add_pass("Whole Chain", |builder| builder.color_attachment(image), |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[]).err().unwrap()) {
+            GraphicsError::MultipleSubresourceAttachment(pass) => assert_eq!(pass, "Whole Chain"),
+            error => panic!("unexpected error {}", error),
+        }
+
+        let mut builder = GraphBuilder::new();
+        let image = builder.create_image("Image", image_description(1024, 1024, SampleCount::Type_1).with_mip_levels(4)).unwrap();
+        builder.add_pass("Missing Level", |builder| builder.color_attachment(image.mip(4)), |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[]).err().unwrap()) {
+            GraphicsError::InvalidSubresource { pass, resource } => assert_eq!((pass.as_str(), resource.as_str()), ("Missing Level", "Image")),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn it_exports_graphs_and_schedules() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let scene = builder.create_image("Scene", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let unused = builder.create_image("Unused", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        let scene = builder.add_pass("Render Scene", |builder| builder.color_attachment(scene), |_, _| {}).unwrap();
+        builder.add_pass("Debug View", |builder| builder.color_attachment(unused), |_, _| {}).unwrap();
+        let back_buffer = builder.add_pass("Post Process", |builder| {
+            builder.sample_image(scene);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let graph = builder.build(&[back_buffer]).unwrap();
+
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.starts_with("digraph \"Graph\" {"));
+        assert!(dot.contains("style=dashed, label=\"Debug View (Graphics, Graphics)\\npruned\""));
+        assert!(dot.contains(&format!("\"image_{}_v1\" -> \"pass_", scene.id)));
+        assert!(dot.contains("ImageLayoutBarrier"));
+
+        let mut json = Vec::new();
+        graph.write_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+
+        assert!(json.contains("\"name\": \"Debug View\""));
+        assert!(json.contains("\"pruned\": true"));
+        assert!(json.contains("\"format\": \"R8G8B8A8_SRGB\""));
+
+        let schedule = graph.compile_schedule().unwrap();
+
+        let mut dot = Vec::new();
+        schedule.write_dot(&mut dot).unwrap();
+        assert!(String::from_utf8(dot).unwrap().contains("label=\"Submission 0 (Graphics)\";"));
+
+        let mut json = Vec::new();
+        schedule.write_json(&mut json).unwrap();
+        assert!(String::from_utf8(json).unwrap().contains("\"type\": \"ExecutePass\""));
+    }
+
+    fn image_description(width: u32, height: u32, sample_count: SampleCount) -> ImageDescription {
+        ImageDescription::new(
+            width,
+            height,
+            ImageFormat::R8G8B8A8_SRGB,
+            ImageType::Type2D,
+            sample_count,
+            ImageLayout::Unknown,
+        )
+    }
+
+    fn get_graphics_error(error: crate::Error) -> GraphicsError {
+        error.downcast::<GraphicsError>().unwrap()
+    }
+
+    #[test]
+    fn it_rejects_writes_to_written_versions() {
+        let mut builder = GraphBuilder::new();
+        let image = builder.create_image("Image", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        builder.add_pass("First", |builder| builder.color_attachment(image), |_, _| {}).unwrap();
+        builder.add_pass("Second", |builder| builder.color_attachment(image), |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[]).err().unwrap()) {
+            GraphicsError::ConflictingWrites { first_pass, second_pass, version, .. } => {
+                assert_eq!((first_pass.as_str(), second_pass.as_str(), version), ("First", "Second", 1));
+            },
+            error => panic!("unexpected error {}", error),
+        }
+
+        let mut builder = GraphBuilder::new();
+        let image = builder.create_image("Image", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        let first = builder.add_pass("First", |builder| builder.color_attachment(image), |_, _| {}).unwrap();
+        builder.add_pass("Second", |builder| builder.color_attachment(first), |_, _| {}).unwrap();
+        builder.add_pass("Third", |builder| builder.color_attachment(image), |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[]).err().unwrap()) {
+            GraphicsError::OutdatedVersionWrite { pass, version, latest, .. } => {
+                assert_eq!((pass.as_str(), version, latest), ("Third", 0, 2));
+            },
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn it_rejects_unproduced_results() {
+        let mut builder = GraphBuilder::new();
+        let image = builder.create_image("Image", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        match get_graphics_error(builder.build(&[image]).err().unwrap()) {
+            GraphicsError::UnproducedResult(name) => assert_eq!(name, "Image"),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn it_rejects_mismatching_attachments() {
+        let mut builder = GraphBuilder::new();
+        let color = builder.create_image("Color", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let normals = builder.create_image("Normals", image_description(1280, 720, SampleCount::Type_1)).unwrap();
+
+        builder.add_pass("Scene", |builder| {
+            builder.color_attachment(color)?;
+            builder.color_attachment(normals)
+        }, |_, _| {}).unwrap();
+
+        assert!(matches!(get_graphics_error(builder.build(&[]).err().unwrap()), GraphicsError::AttachmentSizeMismatch(_)));
+
+        let mut builder = GraphBuilder::new();
+        let color = builder.create_image("Color", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let normals = builder.create_image("Normals", image_description(1920, 1080, SampleCount::Type_4)).unwrap();
+
+        builder.add_pass("Scene", |builder| {
+            builder.color_attachment(color)?;
+            builder.color_attachment(normals)
+        }, |_, _| {}).unwrap();
+
+        assert!(matches!(get_graphics_error(builder.build(&[]).err().unwrap()), GraphicsError::AttachmentSampleCountMismatch(_)));
+    }
+
+    #[test]
+    fn it_requires_physical_buffers_for_imported_buffers() {
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let camera = builder.import_buffer("Camera", BufferDescription::new(256)).unwrap();
+
+        let back_buffer = builder.add_pass("Draw", |builder| {
+            builder.read_uniform_buffer(camera);
+            builder.color_attachment(back_buffer)
+        }, |_, _| {}).unwrap();
+
+        let graph = builder.build(&[back_buffer]).unwrap();
+
+        match get_graphics_error(graph.validate_imported_buffers(&[]).err().unwrap()) {
+            GraphicsError::MissingImportedBuffer(name) => assert_eq!(name, "Camera"),
+            error => panic!("unexpected error {}", error),
+        }
+
+        let small = PhysicalBuffer::new(vk::Buffer::null(), 128);
+        assert!(matches!(get_graphics_error(graph.validate_imported_buffers(&[(camera, small)]).err().unwrap()), GraphicsError::ImportedBufferTooSmall(_)));
+        assert!(graph.validate_imported_buffers(&[(camera, PhysicalBuffer::new(vk::Buffer::null(), 256))]).is_ok());
+    }
+
+    #[test]
+    fn it_rejects_graphics_passes_without_attachments() {
+        let mut builder = GraphBuilder::new();
+        let texture = builder.create_image("Texture", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let texture = builder.add_pass("Clear", |builder| builder.clear_color_attachment(texture, [0.0; 4]), |_, _| {}).unwrap();
+
+        builder.add_pass("Draw", |builder| Ok(builder.sample_image(texture)), |_, _| {}).unwrap();
+
+        match get_graphics_error(builder.build(&[]).err().unwrap()) {
+            GraphicsError::GraphicsPassWithoutAttachments(pass) => assert_eq!(pass, "Draw"),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn it_rejects_a_second_depth_stencil_attachment() {
+        let mut builder = GraphBuilder::new();
+        let depth = builder.create_image("Depth", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        let result = builder.add_pass("Scene", |builder| {
+            builder.depth_stencil_attachment(depth)?;
+            builder.depth_stencil_attachment(depth)
+        }, |_, _| {});
+
+        assert!(matches!(get_graphics_error(result.err().unwrap()), GraphicsError::MultipleDepthStencilAttachments(_)));
+    }
+
+    #[test]
+    fn it_rejects_dependency_cycles() {
+        let mut builder = GraphBuilder::new();
+        let first = builder.create_image("First", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+        let second = builder.create_image("Second", image_description(1920, 1080, SampleCount::Type_1)).unwrap();
+
+        // handles can't be forged outside the crate, so this is the only way to close a cycle
+        builder.add_pass("A", |builder| {
+            builder.sample_image(ImageHandle::new(second.id, 1));
+            builder.color_attachment(first)
+        }, |_, _| {}).unwrap();
+
+        let second = builder.add_pass("B", |builder| {
+            builder.sample_image(ImageHandle::new(first.id, 1));
+            builder.color_attachment(second)
+        }, |_, _| {}).unwrap();
+
+        let graph = builder.build(&[second]).unwrap();
+
+        match get_graphics_error(graph.compile_schedule().err().unwrap()) {
+            GraphicsError::DependencyCycle(passes) => assert_eq!(passes, vec!["B".to_string(), "A".to_string()]),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    /// A deferred renderer with shadows, reflections and post processing
+    fn build_deferred_graph() -> Graph {
         let mut builder = GraphBuilder::new();
 
         // define all images used to render the frame
@@ -175,13 +1472,118 @@ mod tests {
         }).unwrap();
 
         // construct the graph
-        let graph = builder.build(&[back_buffer]);
+        builder.build(&[back_buffer]).unwrap()
+    }
+
+    #[test]
+    fn it_works() {
+        let start = std::time::Instant::now();
+        let graph = build_deferred_graph();
 
         // compile the schedule, this linearizes the graph into a list of instructions for the renderer
-        let schedule = graph.compile_schedule();
+        let schedule = graph.compile_schedule().unwrap();
         dbg!(schedule);
 
         let elapsed = start.elapsed();
         println!("{:?}", elapsed);
     }
+
+    #[test]
+    fn it_reuses_schedules_of_graphs_with_the_same_structure() {
+        assert_eq!(build_deferred_graph().get_structure_hash(), build_deferred_graph().get_structure_hash());
+
+        let mut cache = ScheduleCache::new();
+        let first = cache.get_schedule(&build_deferred_graph()).unwrap() as *const Schedule;
+        let second = cache.get_schedule(&build_deferred_graph()).unwrap() as *const Schedule;
+        assert_eq!(first, second);
+        assert_eq!(cache.get_compile_count(), 1);
+
+        // a different size changes the transient allocations, so the schedule has to be compiled again
+        let mut builder = GraphBuilder::new();
+        let back_buffer = builder.import_image("Back Buffer", image_description(1280, 720, SampleCount::Type_1)).unwrap();
+        let back_buffer = builder.add_pass("Clear", |builder| builder.clear_color_attachment(back_buffer, [0.0; 4]), |_, _| {}).unwrap();
+        let graph = builder.build(&[back_buffer]).unwrap();
+
+        assert_ne!(graph.get_structure_hash(), build_deferred_graph().get_structure_hash());
+        assert_eq!(cache.get_schedule(&graph).unwrap().get_instructions(), graph.compile_schedule().unwrap().get_instructions());
+        assert_eq!(cache.get_compile_count(), 2);
+    }
+
+    /// Compares compiling the deferred graph every frame with looking up its cached schedule,
+    /// run with `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore]
+    fn bench_cold_and_cached_compiles() {
+        const FRAMES: u32 = 100;
+
+        let graphs: Vec<Graph> = (0..FRAMES).map(|_| build_deferred_graph()).collect();
+
+        let start = std::time::Instant::now();
+        for graph in graphs.iter() {
+            graph.compile_schedule().unwrap();
+        }
+        let cold = start.elapsed();
+
+        let mut cache = ScheduleCache::new();
+        let start = std::time::Instant::now();
+        for graph in graphs.iter() {
+            cache.get_schedule(graph).unwrap();
+        }
+        let cached = start.elapsed();
+
+        // every frame after the first one hit the cache
+        assert_eq!(cache.get_compile_count(), 1);
+
+        println!("cold: {:?} per frame, cached: {:?} per frame", cold / FRAMES, cached / FRAMES);
+    }
+
+    #[test]
+    fn it_tracks_image_layouts_and_accesses() {
+        let schedule = build_deferred_graph().compile_schedule().unwrap();
+
+        let barriers: Vec<_> = schedule.get_instructions().iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::ImageLayoutBarrier { id, from, to, src_stage_mask, dst_stage_mask, src_access_mask, dst_access_mask, .. } => {
+                    Some((*id, *from, *to, *src_stage_mask, *dst_stage_mask, *src_access_mask, *dst_access_mask))
+                },
+                _ => None,
+            })
+            .collect();
+
+        let (back_buffer, hdr_buffer, normals_buffer, specular_buffer, depth_buffer, velocity_buffer, shadow_map) = (0, 1, 2, 3, 4, 5, 6);
+
+        let color_output = vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT;
+        let fragment_tests = vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS;
+        let fragment_shader = vk::PipelineStageFlags::FRAGMENT_SHADER;
+        let color_write = vk::AccessFlags::COLOR_ATTACHMENT_WRITE;
+        let color_read_write = vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE;
+        let depth_write = vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE;
+        let depth_read_write = vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE;
+        let none = vk::AccessFlags::empty();
+
+        assert_eq!(barriers, vec![
+            // Z Prepass
+            (velocity_buffer, ImageLayout::Unknown, ImageLayout::ColorAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, color_output, none, color_read_write),
+            (depth_buffer, ImageLayout::Unknown, ImageLayout::DepthStencilAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, fragment_tests, none, depth_read_write),
+            // Render Shadow Map
+            (shadow_map, ImageLayout::Unknown, ImageLayout::DepthStencilAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, fragment_tests, none, depth_read_write),
+            // Render Scene
+            (shadow_map, ImageLayout::DepthStencilAttachment, ImageLayout::ShaderSample, fragment_tests, fragment_shader, depth_write, vk::AccessFlags::SHADER_READ),
+            (hdr_buffer, ImageLayout::Unknown, ImageLayout::ColorAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, color_output, none, color_read_write),
+            (normals_buffer, ImageLayout::Unknown, ImageLayout::ColorAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, color_output, none, color_read_write),
+            (specular_buffer, ImageLayout::Unknown, ImageLayout::ColorAttachment, vk::PipelineStageFlags::TOP_OF_PIPE, color_output, none, color_read_write),
+            (depth_buffer, ImageLayout::DepthStencilAttachment, ImageLayout::DepthStencilAttachment, fragment_tests, fragment_tests, depth_write, depth_read_write),
+            // Reflections
+            (depth_buffer, ImageLayout::DepthStencilAttachment, ImageLayout::ShaderSample, fragment_tests, fragment_shader, depth_write, vk::AccessFlags::SHADER_READ),
+            (normals_buffer, ImageLayout::ColorAttachment, ImageLayout::ShaderSample, color_output, fragment_shader, color_write, vk::AccessFlags::SHADER_READ),
+            (specular_buffer, ImageLayout::ColorAttachment, ImageLayout::ShaderSample, color_output, fragment_shader, color_write, vk::AccessFlags::SHADER_READ),
+            (hdr_buffer, ImageLayout::ColorAttachment, ImageLayout::ColorAttachment, color_output, color_output, color_write, color_read_write),
+            // Post Process, depth and normals are already readable
+            (hdr_buffer, ImageLayout::ColorAttachment, ImageLayout::ShaderSample, color_output, fragment_shader, color_write, vk::AccessFlags::SHADER_READ),
+            (velocity_buffer, ImageLayout::ColorAttachment, ImageLayout::ShaderSample, color_output, fragment_shader, color_write, vk::AccessFlags::SHADER_READ),
+            (back_buffer, ImageLayout::Unknown, ImageLayout::ColorAttachment, vk::PipelineStageFlags::ALL_COMMANDS, color_output, none, color_read_write),
+            // Present
+            (back_buffer, ImageLayout::ColorAttachment, ImageLayout::Present, color_output, vk::PipelineStageFlags::BOTTOM_OF_PIPE, color_write, none),
+        ]);
+    }
 }
@@ -1,21 +1,147 @@
 use super::*;
+use crate::graphics::{BufferAccess, ClearValue, ImageAccess, PipelineBindPoint, QueueType};
+use std::hash::{Hash, Hasher};
 
 pub struct Pass {
     pub(crate) id: u32,
     pub(crate) name: String,
+    pub(crate) bind_point: PipelineBindPoint,
+    pub(crate) queue: QueueType,
     pub(crate) render_target: RenderTargetDescription,
-    pub(crate) sample_images: Vec<ImageHandle>,
+    pub(crate) sample_images: Vec<ImageSubresourceHandle>,
+    pub(crate) storage_images_read: Vec<ImageSubresourceHandle>,
+    pub(crate) storage_images_written: Vec<ImageSubresourceHandle>,
+    pub(crate) buffers: Vec<(BufferHandle, BufferAccess)>,
     pub(crate) executor: Box<dyn Executor>,
 }
 
 impl Pass {
-    pub fn new(id: u32, name: String, render_target: RenderTargetDescription, sample_images: Vec<ImageHandle>, executor: Box<dyn Executor>) -> Self {
+    pub fn new(
+        id: u32,
+        name: String,
+        bind_point: PipelineBindPoint,
+        queue: QueueType,
+        render_target: RenderTargetDescription,
+        sample_images: Vec<ImageSubresourceHandle>,
+        storage_images_read: Vec<ImageSubresourceHandle>,
+        storage_images_written: Vec<ImageSubresourceHandle>,
+        buffers: Vec<(BufferHandle, BufferAccess)>,
+        executor: Box<dyn Executor>,
+    ) -> Self {
         Self {
             id,
             name,
+            bind_point,
+            queue,
             render_target,
             sample_images,
+            storage_images_read,
+            storage_images_written,
+            buffers,
             executor,
         }
     }
+
+    /// Hashes everything about the pass that ends up in a schedule, which is everything but its executor
+    pub(crate) fn hash_structure<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.bind_point.hash(state);
+        self.queue.hash(state);
+        self.render_target.color_attachments.hash(state);
+        self.render_target.depth_stencil_attachment.hash(state);
+        self.render_target.input_attachments.hash(state);
+        self.render_target.resolve_attachments.hash(state);
+        self.sample_images.hash(state);
+        self.storage_images_read.hash(state);
+        self.storage_images_written.hash(state);
+        self.buffers.hash(state);
+
+        let mut clear_values: Vec<(&u32, &ClearValue)> = self.render_target.clear_values.iter().collect();
+        clear_values.sort_by_key(|(id, _)| **id);
+        for (id, clear_value) in clear_values {
+            id.hash(state);
+            match clear_value {
+                ClearValue::Color(color) => color.iter().for_each(|channel| channel.to_bits().hash(state)),
+                ClearValue::DepthStencil { depth, stencil } => {
+                    depth.to_bits().hash(state);
+                    stencil.hash(state);
+                },
+            }
+        }
+    }
+
+    /// Image versions this pass reads
+    pub(crate) fn get_read_images(&self) -> Vec<ImageHandle> {
+        self.sample_images.iter()
+            .chain(self.render_target.input_attachments.iter())
+            .chain(self.storage_images_read.iter())
+            .map(|subresource| subresource.image)
+            .collect()
+    }
+
+    /// Image versions this pass produces, with the mip levels and layers it writes of them
+    pub(crate) fn get_written_subresources(&self) -> Vec<ImageSubresourceHandle> {
+        self.render_target.color_attachments.iter()
+            .chain(self.render_target.depth_stencil_attachment.iter())
+            .chain(self.render_target.resolve_attachments.iter().map(|(_, target)| target))
+            .chain(self.storage_images_written.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Image versions this pass produces, a pass that writes several subresources of an image produces a single version of it
+    pub(crate) fn get_written_images(&self) -> Vec<ImageHandle> {
+        let mut images: Vec<ImageHandle> = Vec::new();
+        for subresource in self.get_written_subresources() {
+            if !images.contains(&subresource.image) {
+                images.push(subresource.image);
+            }
+        }
+
+        images
+    }
+
+    /// All image versions this pass reads or writes
+    pub(crate) fn get_images(&self) -> Vec<ImageHandle> {
+        let mut images = self.get_read_images();
+        images.extend(self.get_written_images());
+        images
+    }
+
+    /// How this pass accesses each of its images, in the order they get transitioned
+    pub(crate) fn get_image_accesses(&self) -> Vec<(ImageSubresourceHandle, ImageAccess)> {
+        let mut accesses = Vec::new();
+        accesses.extend(self.sample_images.iter().map(|image| (*image, ImageAccess::ShaderSample)));
+        accesses.extend(self.render_target.input_attachments.iter().map(|image| (*image, ImageAccess::InputAttachment)));
+        // writing a storage image already makes previous writes visible to reads of the same pass
+        accesses.extend(self.storage_images_read.iter()
+            .filter(|image| !self.storage_images_written.iter().any(|written| written.image.id == image.image.id && written.range == image.range))
+            .map(|image| (*image, ImageAccess::StorageRead)));
+        accesses.extend(self.render_target.color_attachments.iter().map(|image| (*image, ImageAccess::ColorAttachment)));
+        accesses.extend(self.render_target.depth_stencil_attachment.iter().map(|image| (*image, ImageAccess::DepthStencilAttachment)));
+        accesses.extend(self.render_target.resolve_attachments.iter().map(|(_, target)| (*target, ImageAccess::ColorAttachment)));
+        accesses.extend(self.storage_images_written.iter().map(|image| (*image, ImageAccess::StorageWrite)));
+        accesses
+    }
+
+    /// Buffer versions this pass reads
+    pub(crate) fn get_read_buffers(&self) -> Vec<BufferHandle> {
+        self.buffers.iter()
+            .filter(|(_, access)| !access.is_write())
+            .map(|(buffer, _)| *buffer)
+            .collect()
+    }
+
+    /// Buffer versions this pass produces
+    pub(crate) fn get_written_buffers(&self) -> Vec<BufferHandle> {
+        self.buffers.iter()
+            .filter(|(_, access)| access.is_write())
+            .map(|(buffer, _)| *buffer)
+            .collect()
+    }
+
+    /// All buffer versions this pass reads or writes
+    pub(crate) fn get_buffers(&self) -> Vec<BufferHandle> {
+        self.buffers.iter().map(|(buffer, _)| *buffer).collect()
+    }
 }
@@ -1,68 +1,198 @@
 use crate::{
     Error,
+    graphics::{BufferAccess, ClearValue, GraphicsError, PipelineBindPoint, QueueType},
     graphics::rendergraph::*,
     util::IdGenerator,
 };
+use std::collections::HashMap;
 
 pub struct PassBuilder<'a> {
     id: u32,
     name: String,
-    sample_images: Vec<ImageHandle>,
-    color_attachments: Vec<ImageHandle>,
-    depth_stencil_attachment: Option<ImageHandle>,
+    bind_point: PipelineBindPoint,
+    queue: QueueType,
+    sample_images: Vec<ImageSubresourceHandle>,
+    storage_images_read: Vec<ImageSubresourceHandle>,
+    storage_images_written: Vec<ImageSubresourceHandle>,
+    color_attachments: Vec<ImageSubresourceHandle>,
+    depth_stencil_attachment: Option<ImageSubresourceHandle>,
+    input_attachments: Vec<ImageSubresourceHandle>,
+    resolve_attachments: Vec<(ImageSubresourceHandle, ImageSubresourceHandle)>,
+    clear_values: HashMap<u32, ClearValue>,
+    buffers: Vec<(BufferHandle, BufferAccess)>,
     id_generator: &'a mut IdGenerator,
 }
 
 impl<'a> PassBuilder<'a> {
-    pub fn new(id: u32, name: String, id_generator: &'a mut IdGenerator) -> Self {
+    pub fn new(id: u32, name: String, bind_point: PipelineBindPoint, queue: QueueType, id_generator: &'a mut IdGenerator) -> Self {
         Self {
             id,
             name,
+            bind_point,
+            queue,
             sample_images: Vec::new(),
+            storage_images_read: Vec::new(),
+            storage_images_written: Vec::new(),
             color_attachments: Vec::new(),
             depth_stencil_attachment: None,
+            input_attachments: Vec::new(),
+            resolve_attachments: Vec::new(),
+            clear_values: HashMap::new(),
+            buffers: Vec::new(),
             id_generator,
         }
     }
 
-    pub fn sample_image(&mut self, image: ImageHandle) {
-        self.sample_images.push(image);
+    pub fn sample_image<I: Into<ImageSubresourceHandle>>(&mut self, image: I) {
+        self.sample_images.push(image.into());
     }
 
-    pub fn color_attachment(&mut self, image: ImageHandle) -> Result<ImageHandle, Error> {
-        let next_image = ImageHandle::new(image.id, image.version + 1);
+    /// Reads an image with image load operations
+    pub fn read_storage_image<I: Into<ImageSubresourceHandle>>(&mut self, image: I) {
+        self.storage_images_read.push(image.into());
+    }
+
+    /// Writes an image with image store operations, returns the written version
+    pub fn write_storage_image<I: Into<ImageSubresourceHandle>>(&mut self, image: I) -> Result<ImageHandle, Error> {
+        let (next_image, subresource) = next_version(image.into());
 
-        self.color_attachments.push(next_image.clone());
+        self.storage_images_written.push(subresource);
 
         Ok(next_image)
     }
 
-    pub fn depth_stencil_attachment(&mut self, image: ImageHandle) -> Result<ImageHandle, Error> {
+    pub fn read_vertex_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.push((buffer, BufferAccess::VertexRead));
+    }
+
+    pub fn read_index_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.push((buffer, BufferAccess::IndexRead));
+    }
+
+    /// Reads the arguments of indirect draws or dispatches from a buffer
+    pub fn read_indirect_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.push((buffer, BufferAccess::IndirectRead));
+    }
+
+    pub fn read_uniform_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.push((buffer, BufferAccess::UniformRead));
+    }
+
+    pub fn read_storage_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.push((buffer, BufferAccess::StorageRead));
+    }
+
+    /// Writes a storage buffer from shaders, returns the written version
+    pub fn write_storage_buffer(&mut self, buffer: BufferHandle) -> Result<BufferHandle, Error> {
+        let next_buffer = BufferHandle::new(buffer.id, buffer.version + 1);
+
+        self.buffers.push((next_buffer, BufferAccess::StorageWrite));
+
+        Ok(next_buffer)
+    }
+
+    /// Renders to a single mip level and array layer of an image, returns the written version
+    pub fn color_attachment<I: Into<ImageSubresourceHandle>>(&mut self, image: I) -> Result<ImageHandle, Error> {
+        self.ensure_graphics()?;
+
+        let (next_image, subresource) = next_version(image.into());
+
+        self.color_attachments.push(subresource);
+
+        Ok(next_image)
+    }
+
+    pub fn depth_stencil_attachment<I: Into<ImageSubresourceHandle>>(&mut self, image: I) -> Result<ImageHandle, Error> {
+        self.ensure_graphics()?;
+
         if self.depth_stencil_attachment.is_some() {
-            // TODO make proper error
-            panic!("{}", "TODO depth stencil attachment already defined");
+            return Err(GraphicsError::MultipleDepthStencilAttachments(self.name.clone()).into());
         }
 
-        let next_image = ImageHandle::new(image.id, image.version + 1);
+        let (next_image, subresource) = next_version(image.into());
 
-        self.depth_stencil_attachment = Some(next_image.clone());
+        self.depth_stencil_attachment = Some(subresource);
 
         Ok(next_image)
     }
 
+    /// Writes a color attachment that gets cleared to the color when the pass begins
+    pub fn clear_color_attachment<I: Into<ImageSubresourceHandle>>(&mut self, image: I, color: [f32; 4]) -> Result<ImageHandle, Error> {
+        let next_image = self.color_attachment(image)?;
+
+        self.clear_values.insert(next_image.id, ClearValue::Color(color));
+
+        Ok(next_image)
+    }
+
+    /// Writes the depth stencil attachment, which gets cleared to the depth and stencil values when the pass begins
+    pub fn clear_depth_stencil_attachment<I: Into<ImageSubresourceHandle>>(&mut self, image: I, depth: f32, stencil: u32) -> Result<ImageHandle, Error> {
+        let next_image = self.depth_stencil_attachment(image)?;
+
+        self.clear_values.insert(next_image.id, ClearValue::DepthStencil { depth, stencil });
+
+        Ok(next_image)
+    }
+
+    /// Reads the pixel of an image that is being shaded, which lets the pass share a render pass with the one that wrote it
+    pub fn input_attachment<I: Into<ImageSubresourceHandle>>(&mut self, image: I) -> Result<(), Error> {
+        self.ensure_graphics()?;
+
+        self.input_attachments.push(image.into());
+
+        Ok(())
+    }
+
+    /// Resolves a multisampled color attachment of this pass into an image when the pass ends, returns the written version of the image
+    pub fn resolve_attachment<I: Into<ImageSubresourceHandle>>(&mut self, msaa: ImageHandle, target: I) -> Result<ImageHandle, Error> {
+        self.ensure_graphics()?;
+
+        let msaa = match self.color_attachments.iter().find(|attachment| attachment.image == msaa) {
+            Some(attachment) => *attachment,
+            None => return Err(GraphicsError::ResolveOfNonColorAttachment(self.name.clone()).into()),
+        };
+
+        let (next_image, subresource) = next_version(target.into());
+
+        self.resolve_attachments.push((msaa, subresource));
+
+        Ok(next_image)
+    }
+
+    fn ensure_graphics(&self) -> Result<(), Error> {
+        match self.bind_point {
+            PipelineBindPoint::Graphics => Ok(()),
+            PipelineBindPoint::Compute => Err(GraphicsError::AttachmentInComputePass(self.name.clone()).into()),
+        }
+    }
+
     pub(crate) fn build(self, executor: Box<dyn Executor>) -> Pass {
 
         let render_target_description = RenderTargetDescription::new(
             self.color_attachments,
             self.depth_stencil_attachment,
+            self.input_attachments,
+            self.resolve_attachments,
+            self.clear_values,
         );
 
         Pass::new(
             self.id,
             self.name,
+            self.bind_point,
+            self.queue,
             render_target_description,
             self.sample_images,
+            self.storage_images_read,
+            self.storage_images_written,
+            self.buffers,
             executor,
         )
     }
 }
+
+/// The next version of an image, writing part of an image still produces a new version of all of it
+fn next_version(subresource: ImageSubresourceHandle) -> (ImageHandle, ImageSubresourceHandle) {
+    let next_image = ImageHandle::new(subresource.image.id, subresource.image.version + 1);
+    (next_image, ImageSubresourceHandle::new(next_image, subresource.range))
+}
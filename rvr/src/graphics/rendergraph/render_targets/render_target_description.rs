@@ -1,16 +1,31 @@
-use crate::graphics::rendergraph::*;
+use crate::graphics::{ClearValue, rendergraph::*};
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct RenderTargetDescription {
-    pub(crate) color_attachments: Vec<ImageHandle>,
-    pub(crate) depth_stencil_attachment: Option<ImageHandle>,
+    pub(crate) color_attachments: Vec<ImageSubresourceHandle>,
+    pub(crate) depth_stencil_attachment: Option<ImageSubresourceHandle>,
+    pub(crate) input_attachments: Vec<ImageSubresourceHandle>,
+    /// Multisampled color attachments and the single sampled images they get resolved to
+    pub(crate) resolve_attachments: Vec<(ImageSubresourceHandle, ImageSubresourceHandle)>,
+    /// Attachments that get cleared instead of loaded, keyed by image id
+    pub(crate) clear_values: HashMap<u32, ClearValue>,
 }
 
 impl RenderTargetDescription {
-    pub fn new(color_attachments: Vec<ImageHandle>, depth_stencil_attachment: Option<ImageHandle>) -> Self {
+    pub fn new(
+        color_attachments: Vec<ImageSubresourceHandle>,
+        depth_stencil_attachment: Option<ImageSubresourceHandle>,
+        input_attachments: Vec<ImageSubresourceHandle>,
+        resolve_attachments: Vec<(ImageSubresourceHandle, ImageSubresourceHandle)>,
+        clear_values: HashMap<u32, ClearValue>,
+    ) -> Self {
         Self {
             color_attachments,
             depth_stencil_attachment,
+            input_attachments,
+            resolve_attachments,
+            clear_values,
         }
     }
 }
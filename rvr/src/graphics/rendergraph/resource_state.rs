@@ -0,0 +1,315 @@
+use super::*;
+use crate::graphics::*;
+use ash::vk;
+
+/// A dependency that has to be satisfied before a resource can be accessed
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Dependency {
+    src_stage_mask: vk::PipelineStageFlags,
+    dst_stage_mask: vk::PipelineStageFlags,
+    src_access_mask: vk::AccessFlags,
+    dst_access_mask: vk::AccessFlags,
+}
+
+/// The accesses to a resource that later accesses have to wait for
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ResourceState {
+    // the last write or layout transition
+    write_stage_mask: vk::PipelineStageFlags,
+    write_access_mask: vk::AccessFlags,
+    // everything that read the resource since then
+    read_stage_mask: vk::PipelineStageFlags,
+    // where the last write already is visible
+    visible_stage_mask: vk::PipelineStageFlags,
+    visible_access_mask: vk::AccessFlags,
+}
+
+impl ResourceState {
+    /// Accesses that happened before the graph are treated as reads in the given stages
+    fn new(read_stage_mask: vk::PipelineStageFlags) -> Self {
+        Self {
+            write_stage_mask: vk::PipelineStageFlags::empty(),
+            write_access_mask: vk::AccessFlags::empty(),
+            read_stage_mask,
+            visible_stage_mask: vk::PipelineStageFlags::empty(),
+            visible_access_mask: vk::AccessFlags::empty(),
+        }
+    }
+
+    fn access(&mut self, stage_mask: vk::PipelineStageFlags, access_mask: vk::AccessFlags, write_access_mask: vk::AccessFlags, transition: bool) -> Option<Dependency> {
+
+        // reads only have to wait for the last write, and only once per stage and access
+        if write_access_mask.is_empty() && !transition {
+            self.read_stage_mask |= stage_mask;
+
+            let visible = self.visible_stage_mask.contains(stage_mask) && self.visible_access_mask.contains(access_mask);
+            if self.write_stage_mask.is_empty() || visible {
+                return None;
+            }
+
+            self.visible_stage_mask |= stage_mask;
+            self.visible_access_mask |= access_mask;
+
+            return Some(Dependency {
+                src_stage_mask: self.write_stage_mask,
+                dst_stage_mask: stage_mask,
+                src_access_mask: self.write_access_mask,
+                dst_access_mask: access_mask,
+            });
+        }
+
+        // writes and layout transitions have to wait for everything before them
+        let src_stage_mask = self.write_stage_mask | self.read_stage_mask;
+        let dependency = if src_stage_mask.is_empty() && !transition {
+            None
+        } else {
+            Some(Dependency {
+                src_stage_mask: if src_stage_mask.is_empty() { vk::PipelineStageFlags::TOP_OF_PIPE } else { src_stage_mask },
+                dst_stage_mask: stage_mask,
+                src_access_mask: self.write_access_mask,
+                dst_access_mask: access_mask,
+            })
+        };
+
+        self.write_stage_mask = stage_mask;
+        self.write_access_mask = write_access_mask;
+
+        if write_access_mask.is_empty() {
+            // a layout transition for a read, the barrier already made it visible to this access
+            self.read_stage_mask = stage_mask;
+            self.visible_stage_mask = stage_mask;
+            self.visible_access_mask = access_mask;
+        } else {
+            self.read_stage_mask = vk::PipelineStageFlags::empty();
+            self.visible_stage_mask = vk::PipelineStageFlags::empty();
+            self.visible_access_mask = vk::AccessFlags::empty();
+        }
+
+        dependency
+    }
+}
+
+/// The layout of a single mip level of an array layer, the accesses to it and the submission that used it last
+#[derive(Debug, Copy, Clone)]
+struct SubresourceState {
+    layout: ImageLayout,
+    state: ResourceState,
+    owner: Option<usize>,
+}
+
+/// A barrier some subresources of an image need, subresources in the same state share it
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ImageBarrier {
+    Layout {
+        from: ImageLayout,
+        dependency: Dependency,
+    },
+    Ownership {
+        owner: usize,
+        from: ImageLayout,
+        dependency: Dependency,
+    },
+}
+
+/// The states of all subresources of an image, passes can use different mip levels and layers of it at the same time
+#[derive(Debug, Clone)]
+pub(crate) struct ImageState {
+    mip_levels: u32,
+    // layer after layer, with all mip levels of each layer
+    subresources: Vec<SubresourceState>,
+}
+
+impl ImageState {
+    fn with_state(description: &ImageDescription, layout: ImageLayout, state: ResourceState) -> Self {
+        let subresource = SubresourceState {
+            layout,
+            state,
+            owner: None,
+        };
+
+        Self {
+            mip_levels: description.mip_levels,
+            subresources: vec![subresource; (description.mip_levels * description.array_layers) as usize],
+        }
+    }
+
+    pub(crate) fn new(description: &ImageDescription, stage_mask: vk::PipelineStageFlags) -> Self {
+        Self::with_state(description, description.initial_layout, ResourceState::new(stage_mask))
+    }
+
+    /// An image written by an earlier frame, the first access of this frame waits for all of its writes.
+    /// The owner is the submission that releases the image from the queue of the earlier frame.
+    pub(crate) fn persistent(description: &ImageDescription, owner: Option<usize>) -> Self {
+        let mut state = ResourceState::new(vk::PipelineStageFlags::empty());
+        state.write_stage_mask = vk::PipelineStageFlags::ALL_COMMANDS;
+        state.write_access_mask = vk::AccessFlags::MEMORY_WRITE;
+
+        let mut image_state = Self::with_state(description, description.initial_layout, state);
+        image_state.subresources.iter_mut().for_each(|subresource| subresource.owner = owner);
+        image_state
+    }
+
+    /// The layout and the submission of the last access, for an image that was transitioned as a whole
+    pub(crate) fn get_last_use(&self) -> (ImageLayout, usize) {
+        let subresource = &self.subresources[0];
+        (subresource.layout, subresource.owner.expect("image was never used"))
+    }
+
+    /// Takes over the pending accesses of the image that previously used the same memory on the same queue
+    pub(crate) fn alias(&mut self, previous: &ImageState) {
+        let mut state = ResourceState::new(vk::PipelineStageFlags::empty());
+        for subresource in previous.subresources.iter() {
+            state.write_stage_mask |= subresource.state.write_stage_mask;
+            state.write_access_mask |= subresource.state.write_access_mask;
+            state.read_stage_mask |= subresource.state.read_stage_mask;
+        }
+
+        self.subresources.iter_mut().for_each(|subresource| subresource.state = state);
+    }
+
+    /// Emits barriers unless the subresources are only read again in the layout they already are in,
+    /// subresources used by another queue before get transferred to the queue of the current submission
+    pub(crate) fn transition(&mut self, id: u32, range: ImageSubresourceRange, access: ImageAccess, bind_point: PipelineBindPoint, schedule: &mut ScheduleBuilder) {
+        let submission = schedule.get_current_submission();
+        let queue = schedule.get_queue(submission);
+        let stage_mask = access.get_stage_mask(bind_point);
+        let layout = access.get_layout();
+
+        let mut barriers: Vec<(ImageBarrier, Vec<(u32, u32)>)> = Vec::new();
+
+        for array_layer in range.base_array_layer..range.base_array_layer + range.layer_count {
+            for mip_level in range.base_mip_level..range.base_mip_level + range.level_count {
+                let subresource = &mut self.subresources[(array_layer * self.mip_levels + mip_level) as usize];
+
+                let barrier = match subresource.owner {
+                    Some(owner) if schedule.get_queue(owner) != queue => Some(ImageBarrier::Ownership {
+                        owner,
+                        from: subresource.layout,
+                        dependency: subresource.state.access(stage_mask, access.get_access_mask(), access.get_write_access_mask(), true).unwrap(),
+                    }),
+                    _ => subresource.state.access(
+                        stage_mask,
+                        access.get_access_mask(),
+                        access.get_write_access_mask(),
+                        layout != subresource.layout,
+                    ).map(|dependency| ImageBarrier::Layout {
+                        from: subresource.layout,
+                        dependency,
+                    }),
+                };
+
+                subresource.layout = layout;
+                subresource.owner = Some(submission);
+
+                if let Some(barrier) = barrier {
+                    match barriers.iter_mut().find(|(other, _)| *other == barrier) {
+                        Some((_, subresources)) => subresources.push((mip_level, array_layer)),
+                        None => barriers.push((barrier, vec![(mip_level, array_layer)])),
+                    }
+                }
+            }
+        }
+
+        for (barrier, subresources) in barriers {
+            for range in get_ranges(&subresources) {
+                match barrier {
+                    ImageBarrier::Layout { from, dependency } => {
+                        schedule.add_image_layout_barrier(
+                            id,
+                            range,
+                            from,
+                            layout,
+                            dependency.src_stage_mask,
+                            dependency.dst_stage_mask,
+                            dependency.src_access_mask,
+                            dependency.dst_access_mask,
+                        );
+                    },
+                    ImageBarrier::Ownership { owner, from, dependency } => {
+                        schedule.add_image_ownership_release(owner, id, range, from, layout, dependency.src_stage_mask, dependency.src_access_mask, queue);
+                        schedule.add_semaphore_wait(owner, stage_mask);
+                        schedule.add_image_ownership_acquire(id, range, from, layout, stage_mask, dependency.dst_access_mask, schedule.get_queue(owner));
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Covers the subresources with as few ranges as possible, a single one if they form a block of mip levels and layers
+fn get_ranges(subresources: &[(u32, u32)]) -> Vec<ImageSubresourceRange> {
+    let min_mip_level = subresources.iter().map(|(mip_level, _)| *mip_level).min().unwrap();
+    let max_mip_level = subresources.iter().map(|(mip_level, _)| *mip_level).max().unwrap();
+    let min_array_layer = subresources.iter().map(|(_, array_layer)| *array_layer).min().unwrap();
+    let max_array_layer = subresources.iter().map(|(_, array_layer)| *array_layer).max().unwrap();
+
+    let level_count = max_mip_level - min_mip_level + 1;
+    let layer_count = max_array_layer - min_array_layer + 1;
+    if subresources.len() as u32 == level_count * layer_count {
+        return vec![ImageSubresourceRange::new(min_mip_level, level_count, min_array_layer, layer_count)];
+    }
+
+    // otherwise consecutive mip levels of each layer share a range, subresources are ordered layer by layer
+    let mut ranges: Vec<ImageSubresourceRange> = Vec::new();
+    for (mip_level, array_layer) in subresources.iter() {
+        match ranges.last_mut() {
+            Some(range) if range.base_array_layer == *array_layer && range.base_mip_level + range.level_count == *mip_level => range.level_count += 1,
+            _ => ranges.push(ImageSubresourceRange::new(*mip_level, 1, *array_layer, 1)),
+        }
+    }
+
+    ranges
+}
+
+/// The accesses to a buffer and the submission that used it last
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct BufferState {
+    state: ResourceState,
+    owner: Option<usize>,
+}
+
+impl BufferState {
+    pub(crate) fn new(stage_mask: vk::PipelineStageFlags) -> Self {
+        Self {
+            state: ResourceState::new(stage_mask),
+            owner: None,
+        }
+    }
+
+    /// Emits a barrier if the buffer was written before or gets written now while others still read it,
+    /// buffers used by another queue before get transferred to the queue of the current submission
+    pub(crate) fn access(
+        &mut self,
+        id: u32,
+        stage_mask: vk::PipelineStageFlags,
+        access_mask: vk::AccessFlags,
+        write_access_mask: vk::AccessFlags,
+        schedule: &mut ScheduleBuilder,
+    ) {
+        let submission = schedule.get_current_submission();
+        let queue = schedule.get_queue(submission);
+
+        match self.owner {
+            Some(owner) if schedule.get_queue(owner) != queue => {
+                let dependency = self.state.access(stage_mask, access_mask, write_access_mask, true).unwrap();
+
+                schedule.add_buffer_ownership_release(owner, id, dependency.src_stage_mask, dependency.src_access_mask, queue);
+                schedule.add_semaphore_wait(owner, stage_mask);
+                schedule.add_buffer_ownership_acquire(id, stage_mask, access_mask, schedule.get_queue(owner));
+            },
+            _ => {
+                if let Some(dependency) = self.state.access(stage_mask, access_mask, write_access_mask, false) {
+                    schedule.add_buffer_barrier(
+                        id,
+                        dependency.src_stage_mask,
+                        dependency.dst_stage_mask,
+                        dependency.src_access_mask,
+                        dependency.dst_access_mask,
+                    );
+                }
+            },
+        }
+
+        self.owner = Some(submission);
+    }
+}
@@ -1,32 +1,186 @@
-use crate::graphics::*;
+use ash::vk;
+use crate::graphics::{
+    *,
+    rendergraph::*,
+};
 
-#[derive(Debug)]
+/// A pass executed as part of a merged render pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subpass {
+    pub id: u32,
+    pub color_attachments: Vec<ImageSubresourceHandle>,
+    pub depth_stencil_attachment: Option<ImageSubresourceHandle>,
+    pub input_attachments: Vec<ImageSubresourceHandle>,
+    /// Multisampled color attachments and the images they get resolved to
+    pub resolve_attachments: Vec<(ImageSubresourceHandle, ImageSubresourceHandle)>,
+    /// How each attachment gets loaded and stored, in the order of `get_attachments`
+    pub attachment_ops: Vec<AttachmentOps>,
+}
+
+impl Subpass {
+    /// All images the subpass uses through the framebuffer
+    pub fn get_attachments(&self) -> Vec<ImageSubresourceHandle> {
+        let mut attachments = self.color_attachments.clone();
+        attachments.extend(self.depth_stencil_attachment.iter().cloned());
+        attachments.extend(self.input_attachments.iter().cloned());
+        attachments.extend(self.resolve_attachments.iter().map(|(_, target)| *target));
+        attachments
+    }
+}
+
+/// A barrier between two subpasses of the same render pass, indices refer to the subpasses
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SubpassDependency {
+    pub src_subpass: u32,
+    pub dst_subpass: u32,
+    pub src_stage_mask: vk::PipelineStageFlags,
+    pub dst_stage_mask: vk::PipelineStageFlags,
+    pub src_access_mask: vk::AccessFlags,
+    pub dst_access_mask: vk::AccessFlags,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
-    CreateImage,
-    ReleaseImage,
-    ExecutePass,
-    Present,
+    CreateImage {
+        id: u32,
+        description: ImageDescription,
+        usage: vk::ImageUsageFlags,
+        allocation: u32,
+    },
+    ReleaseImage {
+        id: u32,
+    },
+    CreateBuffer {
+        id: u32,
+        description: BufferDescription,
+        usage: vk::BufferUsageFlags,
+    },
+    ReleaseBuffer {
+        id: u32,
+    },
+    ExecutePass {
+        id: u32,
+        bind_point: PipelineBindPoint,
+        color_attachments: Vec<ImageSubresourceHandle>,
+        depth_stencil_attachment: Option<ImageSubresourceHandle>,
+        input_attachments: Vec<ImageSubresourceHandle>,
+        resolve_attachments: Vec<(ImageSubresourceHandle, ImageSubresourceHandle)>,
+        attachment_ops: Vec<AttachmentOps>,
+    },
+    /// Consecutive passes that share one render pass, each of them is a subpass
+    ExecuteSubpasses {
+        subpasses: Vec<Subpass>,
+        dependencies: Vec<SubpassDependency>,
+    },
+    Present {
+        id: u32,
+    },
 
     ImageLayoutBarrier {
         id: u32,
+        subresource: ImageSubresourceRange,
         from: ImageLayout,
         to: ImageLayout,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_access_mask: vk::AccessFlags,
+    },
+    /// Hands an image over to another queue, recorded on the queue that used it so far
+    ImageOwnershipRelease {
+        id: u32,
+        subresource: ImageSubresourceRange,
+        from: ImageLayout,
+        to: ImageLayout,
+        src_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_queue: QueueType,
+    },
+    /// Takes over an image from another queue after waiting on its semaphore
+    ImageOwnershipAcquire {
+        id: u32,
+        subresource: ImageSubresourceRange,
+        from: ImageLayout,
+        to: ImageLayout,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+        src_queue: QueueType,
+    },
+    BufferOwnershipRelease {
+        id: u32,
+        src_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_queue: QueueType,
+    },
+    BufferOwnershipAcquire {
+        id: u32,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+        src_queue: QueueType,
+    },
+    BufferBarrier {
+        id: u32,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_access_mask: vk::AccessFlags,
     },
 }
 
 #[derive(Debug)]
 pub struct Schedule {
-    instructions: Vec<Instruction>,
+    submissions: Vec<Submission>,
+    allocations: Vec<TransientAllocation>,
+    persistent_image_states: Vec<(u32, ImageLayout, QueueType)>,
 }
 
 impl Schedule {
-    pub fn new(instructions: Vec<Instruction>) -> Self {
+    pub fn new(submissions: Vec<Submission>, allocations: Vec<TransientAllocation>, persistent_image_states: Vec<(u32, ImageLayout, QueueType)>) -> Self {
         Self {
-            instructions,
+            submissions,
+            allocations,
+            persistent_image_states,
         }
     }
 
-    pub fn get_instructions(&self) -> &[Instruction] {
-        &self.instructions
+    /// The schedule split into submissions per queue, in the order they get submitted
+    pub fn get_submissions(&self) -> &[Submission] {
+        &self.submissions
+    }
+
+    /// All instructions of all submissions, in submission order
+    /// The first submission that uses the image, an acquired swapchain image has to be waited for there
+    pub fn get_first_submission_using(&self, image: u32) -> Option<usize> {
+        self.submissions.iter().position(|submission| submission.uses_image(image))
+    }
+
+    pub fn get_instructions(&self) -> Vec<&Instruction> {
+        self.submissions.iter()
+            .flat_map(|submission| submission.get_instructions().iter())
+            .collect()
+    }
+
+    /// The layout and queue every persistent image is left in at the end of the frame
+    pub fn get_persistent_image_states(&self) -> &[(u32, ImageLayout, QueueType)] {
+        &self.persistent_image_states
+    }
+
+    pub fn get_allocations(&self) -> &[TransientAllocation] {
+        &self.allocations
+    }
+
+    /// Estimated bytes of transient image memory needed to render the frame
+    pub fn get_memory_usage(&self) -> u64 {
+        self.allocations.iter().map(|allocation| allocation.size).sum()
+    }
+
+    /// Estimated bytes of transient image memory the frame would need without aliasing
+    pub fn get_unaliased_memory_usage(&self) -> u64 {
+        self.get_instructions().iter()
+            .map(|instruction| match instruction {
+                Instruction::CreateImage { description, .. } => description.get_size(),
+                _ => 0,
+            })
+            .sum()
     }
 }
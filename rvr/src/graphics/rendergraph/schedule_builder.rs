@@ -1,28 +1,240 @@
+use ash::vk;
 use crate::graphics::{
     *,
     rendergraph::*,
 };
 
 pub struct ScheduleBuilder {
-    schedule: Vec<Instruction>,
+    submissions: Vec<Submission>,
+    allocations: Vec<TransientAllocation>,
+    persistent_image_states: Vec<(u32, ImageLayout, QueueType)>,
 }
 
 impl ScheduleBuilder {
-    pub fn new() -> Self {
+    pub fn new(allocations: Vec<TransientAllocation>) -> Self {
         Self {
-            schedule: Vec::new(),
+            submissions: Vec::new(),
+            allocations,
+            persistent_image_states: Vec::new(),
         }
     }
 
-    pub fn add_image_layout_barrier(&mut self, id: u32, from: ImageLayout, to: ImageLayout) {
-        self.schedule.push(Instruction::ImageLayoutBarrier {
+    /// Starts a new submission unless the current one already goes to the queue
+    pub fn begin_submission(&mut self, queue: QueueType) {
+        if self.submissions.last().map(|submission| submission.get_queue()) != Some(queue) {
+            self.submissions.push(Submission::new(queue));
+        }
+    }
+
+    pub fn get_current_submission(&self) -> usize {
+        self.submissions.len() - 1
+    }
+
+    pub fn get_queue(&self, submission: usize) -> QueueType {
+        self.submissions[submission].get_queue()
+    }
+
+    /// Makes the current submission wait until an earlier one has finished
+    pub fn add_semaphore_wait(&mut self, submission: usize, stage_mask: vk::PipelineStageFlags) {
+        self.submissions[submission].set_signal();
+        self.submissions.last_mut().unwrap().add_wait(submission, stage_mask);
+    }
+
+    /// Rewrites the instructions of every submission, used by optimizations on the finished schedule
+    pub(crate) fn rewrite_instructions<F>(&mut self, rewrite: F)
+        where F: Fn(Vec<Instruction>) -> Vec<Instruction>
+    {
+        for submission in self.submissions.iter_mut() {
+            submission.rewrite(&rewrite);
+        }
+    }
+
+    fn push(&mut self, instruction: Instruction) {
+        if self.submissions.is_empty() {
+            self.submissions.push(Submission::new(QueueType::Graphics));
+        }
+
+        self.submissions.last_mut().unwrap().push(instruction);
+    }
+
+    pub fn add_create_image(&mut self, id: u32, description: ImageDescription, usage: vk::ImageUsageFlags, allocation: u32) {
+        self.push(Instruction::CreateImage {
+            id,
+            description,
+            usage,
+            allocation,
+        });
+    }
+
+    pub fn add_release_image(&mut self, id: u32) {
+        self.push(Instruction::ReleaseImage {
+            id,
+        });
+    }
+
+    pub fn add_create_buffer(&mut self, id: u32, description: BufferDescription, usage: vk::BufferUsageFlags) {
+        self.push(Instruction::CreateBuffer {
+            id,
+            description,
+            usage,
+        });
+    }
+
+    pub fn add_release_buffer(&mut self, id: u32) {
+        self.push(Instruction::ReleaseBuffer {
+            id,
+        });
+    }
+
+    pub fn add_execute_pass(
+        &mut self,
+        id: u32,
+        bind_point: PipelineBindPoint,
+        color_attachments: Vec<ImageSubresourceHandle>,
+        depth_stencil_attachment: Option<ImageSubresourceHandle>,
+        input_attachments: Vec<ImageSubresourceHandle>,
+        resolve_attachments: Vec<(ImageSubresourceHandle, ImageSubresourceHandle)>,
+        attachment_ops: Vec<AttachmentOps>,
+    ) {
+        self.push(Instruction::ExecutePass {
+            id,
+            bind_point,
+            color_attachments,
+            depth_stencil_attachment,
+            input_attachments,
+            resolve_attachments,
+            attachment_ops,
+        });
+    }
+
+    pub fn add_present(&mut self, id: u32) {
+        self.push(Instruction::Present {
+            id,
+        });
+    }
+
+    pub fn add_image_layout_barrier(
+        &mut self,
+        id: u32,
+        subresource: ImageSubresourceRange,
+        from: ImageLayout,
+        to: ImageLayout,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_access_mask: vk::AccessFlags,
+    ) {
+        self.push(Instruction::ImageLayoutBarrier {
+            id,
+            subresource,
+            from,
+            to,
+            src_stage_mask,
+            dst_stage_mask,
+            src_access_mask,
+            dst_access_mask,
+        });
+    }
+
+    pub fn add_buffer_barrier(
+        &mut self,
+        id: u32,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_access_mask: vk::AccessFlags,
+    ) {
+        self.push(Instruction::BufferBarrier {
+            id,
+            src_stage_mask,
+            dst_stage_mask,
+            src_access_mask,
+            dst_access_mask,
+        });
+    }
+
+    /// Appends the release to the submission that used the image so far, which already was built
+    pub fn add_image_ownership_release(
+        &mut self,
+        submission: usize,
+        id: u32,
+        subresource: ImageSubresourceRange,
+        from: ImageLayout,
+        to: ImageLayout,
+        src_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_queue: QueueType,
+    ) {
+        self.submissions[submission].push(Instruction::ImageOwnershipRelease {
             id,
+            subresource,
             from,
             to,
+            src_stage_mask,
+            src_access_mask,
+            dst_queue,
         });
     }
 
+    pub fn add_image_ownership_acquire(
+        &mut self,
+        id: u32,
+        subresource: ImageSubresourceRange,
+        from: ImageLayout,
+        to: ImageLayout,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+        src_queue: QueueType,
+    ) {
+        self.push(Instruction::ImageOwnershipAcquire {
+            id,
+            subresource,
+            from,
+            to,
+            dst_stage_mask,
+            dst_access_mask,
+            src_queue,
+        });
+    }
+
+    /// Appends the release to the submission that used the buffer so far, which already was built
+    pub fn add_buffer_ownership_release(
+        &mut self,
+        submission: usize,
+        id: u32,
+        src_stage_mask: vk::PipelineStageFlags,
+        src_access_mask: vk::AccessFlags,
+        dst_queue: QueueType,
+    ) {
+        self.submissions[submission].push(Instruction::BufferOwnershipRelease {
+            id,
+            src_stage_mask,
+            src_access_mask,
+            dst_queue,
+        });
+    }
+
+    pub fn add_buffer_ownership_acquire(
+        &mut self,
+        id: u32,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+        src_queue: QueueType,
+    ) {
+        self.push(Instruction::BufferOwnershipAcquire {
+            id,
+            dst_stage_mask,
+            dst_access_mask,
+            src_queue,
+        });
+    }
+
+    /// Remembers how the frame leaves a persistent image, the next frame starts from there
+    pub fn add_persistent_image_state(&mut self, id: u32, layout: ImageLayout, queue: QueueType) {
+        self.persistent_image_states.push((id, layout, queue));
+    }
+
     pub fn build(self) -> Schedule {
-        Schedule::new(self.schedule)
+        Schedule::new(self.submissions, self.allocations, self.persistent_image_states)
     }
 }
@@ -0,0 +1,37 @@
+use super::*;
+use crate::Error;
+
+/// Keeps the last compiled schedule, frames whose graph has the same structure skip compiling
+pub struct ScheduleCache {
+    cached: Option<(u64, Schedule)>,
+    compile_count: usize,
+}
+
+impl ScheduleCache {
+    pub fn new() -> Self {
+        Self {
+            cached: None,
+            compile_count: 0,
+        }
+    }
+
+    /// How often a graph had to be compiled, a cache hit doesn't count
+    pub fn get_compile_count(&self) -> usize {
+        self.compile_count
+    }
+
+    /// Returns the schedule of the graph, which only gets compiled if its structure changed since the last call
+    pub fn get_schedule(&mut self, graph: &Graph) -> Result<&Schedule, Error> {
+        let hash = graph.get_structure_hash();
+
+        match &self.cached {
+            Some((cached_hash, _)) if *cached_hash == hash => {},
+            _ => {
+                self.cached = Some((hash, graph.compile_schedule()?));
+                self.compile_count += 1;
+            },
+        }
+
+        Ok(&self.cached.as_ref().unwrap().1)
+    }
+}
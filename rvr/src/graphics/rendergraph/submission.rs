@@ -0,0 +1,95 @@
+use ash::vk;
+use crate::graphics::{
+    *,
+    rendergraph::*,
+};
+
+/// Waiting on the semaphore another submission signals
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SemaphoreWait {
+    pub submission: usize,
+    pub stage_mask: vk::PipelineStageFlags,
+}
+
+/// Instructions that get recorded into one command buffer and submitted to a single queue
+#[derive(Debug, PartialEq)]
+pub struct Submission {
+    queue: QueueType,
+    instructions: Vec<Instruction>,
+    waits: Vec<SemaphoreWait>,
+    signal: bool,
+}
+
+impl Submission {
+    pub fn new(queue: QueueType) -> Self {
+        Self {
+            queue,
+            instructions: Vec::new(),
+            waits: Vec::new(),
+            signal: false,
+        }
+    }
+
+    pub fn get_queue(&self) -> QueueType {
+        self.queue
+    }
+
+    pub fn get_instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn get_waits(&self) -> &[SemaphoreWait] {
+        &self.waits
+    }
+
+    /// Whether an instruction of the submission transitions, hands over or renders to the image
+    pub fn uses_image(&self, image: u32) -> bool {
+        self.instructions.iter().any(|instruction| match instruction {
+            Instruction::ImageLayoutBarrier { id, .. }
+            | Instruction::ImageOwnershipRelease { id, .. }
+            | Instruction::ImageOwnershipAcquire { id, .. }
+            | Instruction::Present { id } => *id == image,
+            Instruction::ExecutePass { color_attachments, depth_stencil_attachment, input_attachments, resolve_attachments, .. } => {
+                color_attachments.iter()
+                    .chain(depth_stencil_attachment.iter())
+                    .chain(input_attachments.iter())
+                    .chain(resolve_attachments.iter().map(|(_, target)| target))
+                    .any(|handle| handle.image.id == image)
+            },
+            Instruction::ExecuteSubpasses { subpasses, .. } => {
+                subpasses.iter().any(|subpass| subpass.get_attachments().iter().any(|handle| handle.image.id == image))
+            },
+            _ => false,
+        })
+    }
+
+    /// Whether a later submission waits on this one
+    pub fn signals(&self) -> bool {
+        self.signal
+    }
+
+    pub(crate) fn push(&mut self, instruction: Instruction) {
+        self.instructions.push(instruction);
+    }
+
+    pub(crate) fn rewrite<F>(&mut self, rewrite: F)
+        where F: FnOnce(Vec<Instruction>) -> Vec<Instruction>
+    {
+        let instructions = std::mem::replace(&mut self.instructions, Vec::new());
+        self.instructions = rewrite(instructions);
+    }
+
+    pub(crate) fn set_signal(&mut self) {
+        self.signal = true;
+    }
+
+    pub(crate) fn add_wait(&mut self, submission: usize, stage_mask: vk::PipelineStageFlags) {
+        match self.waits.iter_mut().find(|wait| wait.submission == submission) {
+            Some(wait) => wait.stage_mask |= stage_mask,
+            None => self.waits.push(SemaphoreWait {
+                submission,
+                stage_mask,
+            }),
+        }
+    }
+}
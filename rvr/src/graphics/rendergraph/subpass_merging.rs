@@ -0,0 +1,240 @@
+use crate::graphics::*;
+use crate::graphics::rendergraph::*;
+use std::collections::{HashMap, HashSet};
+
+/// What the merging needs to know about the images of a graph
+pub(crate) struct MergeImage<'a> {
+    pub description: &'a ImageDescription,
+    pub allocation: Option<u32>,
+}
+
+/// Passes that get recorded into the same render pass
+struct Group {
+    subpasses: Vec<Subpass>,
+    dependencies: Vec<SubpassDependency>,
+    framebuffer: (u32, u32, SampleCount),
+    // images used through the framebuffer with the subresource the framebuffer holds, and every other image the subpasses touch
+    attachments: HashMap<u32, ImageSubresourceRange>,
+    images: HashSet<u32>,
+    allocations: HashSet<u32>,
+    read_buffers: HashSet<u32>,
+    written_buffers: HashSet<u32>,
+    // instructions of later subpasses that get recorded before or after the render pass
+    before: Vec<Instruction>,
+    after: Vec<Instruction>,
+}
+
+impl Group {
+    fn new(subpass: Subpass, pass: &Pass, framebuffer: (u32, u32, SampleCount), images: &HashMap<u32, MergeImage>) -> Self {
+        let mut group = Self {
+            subpasses: Vec::new(),
+            dependencies: Vec::new(),
+            framebuffer,
+            attachments: HashMap::new(),
+            images: HashSet::new(),
+            allocations: HashSet::new(),
+            read_buffers: HashSet::new(),
+            written_buffers: HashSet::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        };
+
+        group.add(subpass, pass, images);
+        group
+    }
+
+    fn add(&mut self, subpass: Subpass, pass: &Pass, images: &HashMap<u32, MergeImage>) {
+        self.attachments.extend(subpass.get_attachments().iter().map(|attachment| (attachment.image.id, attachment.range)));
+        self.images.extend(pass.get_images().iter().map(|image| image.id));
+        self.allocations.extend(pass.get_images().iter().filter_map(|image| images.get(&image.id)?.allocation));
+        self.read_buffers.extend(pass.get_read_buffers().iter().map(|buffer| buffer.id));
+        self.written_buffers.extend(pass.get_written_buffers().iter().map(|buffer| buffer.id));
+        self.subpasses.push(subpass);
+    }
+
+    /// The last subpass that used an image
+    fn get_last_use(&self, id: u32) -> Option<u32> {
+        self.subpasses.iter()
+            .rposition(|subpass| subpass.get_attachments().iter().any(|attachment| attachment.image.id == id))
+            .map(|index| index as u32)
+    }
+
+    /// Adds the pass as the next subpass if everything recorded since the last subpass can be moved out of the render pass
+    /// or expressed as a subpass dependency, leaves the group untouched otherwise
+    fn try_add(&mut self, subpass: &Subpass, pass: &Pass, framebuffer: (u32, u32, SampleCount), pending: &[Instruction], images: &HashMap<u32, MergeImage>) -> bool {
+        if framebuffer != self.framebuffer {
+            return false;
+        }
+
+        // images of the render pass can only be accessed through the framebuffer and vice versa, always with the same subresource
+        let attachments: HashMap<u32, ImageSubresourceRange> = subpass.get_attachments().iter()
+            .map(|attachment| (attachment.image.id, attachment.range))
+            .collect();
+        let touches_attachments = pass.get_images().iter().any(|image| self.attachments.contains_key(&image.id) && !attachments.contains_key(&image.id));
+        let touches_images = attachments.keys().any(|id| self.images.contains(id) && !self.attachments.contains_key(id));
+        let changes_subresource = attachments.iter().any(|(id, range)| self.attachments.get(id).map_or(false, |other| other != range));
+        if touches_attachments || touches_images || changes_subresource {
+            return false;
+        }
+
+        let dst_subpass = self.subpasses.len() as u32;
+        let written_buffers: HashSet<u32> = pass.get_written_buffers().iter().map(|buffer| buffer.id).collect();
+
+        let mut dependencies = Vec::new();
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+
+        for instruction in pending {
+            match instruction {
+                Instruction::ImageLayoutBarrier { id, src_stage_mask, dst_stage_mask, src_access_mask, dst_access_mask, .. } => {
+                    match self.get_last_use(*id) {
+                        // waiting on an earlier subpass, the attachment references take care of the layout
+                        Some(src_subpass) => dependencies.push(SubpassDependency {
+                            src_subpass,
+                            dst_subpass,
+                            src_stage_mask: *src_stage_mask,
+                            dst_stage_mask: *dst_stage_mask,
+                            src_access_mask: *src_access_mask,
+                            dst_access_mask: *dst_access_mask,
+                        }),
+                        None => {
+                            // an aliased image might wait on memory the render pass still uses
+                            let aliased = images.get(id)
+                                .and_then(|image| image.allocation)
+                                .map(|allocation| self.allocations.contains(&allocation))
+                                .unwrap_or(false);
+
+                            if self.images.contains(id) || aliased {
+                                return false;
+                            }
+
+                            before.push(instruction.clone());
+                        },
+                    }
+                },
+                Instruction::BufferBarrier { id, .. } => {
+                    // only barriers on writes from before the render pass can move in front of it
+                    if self.written_buffers.contains(id) || (written_buffers.contains(id) && self.read_buffers.contains(id)) {
+                        return false;
+                    }
+
+                    before.push(instruction.clone());
+                },
+                Instruction::CreateImage { .. } | Instruction::CreateBuffer { .. } => before.push(instruction.clone()),
+                Instruction::ReleaseImage { .. } | Instruction::ReleaseBuffer { .. } => after.push(instruction.clone()),
+                _ => return false,
+            }
+        }
+
+        // a single dependency per pair of subpasses covers all images
+        for dependency in dependencies {
+            match self.dependencies.iter_mut().find(|other| other.src_subpass == dependency.src_subpass && other.dst_subpass == dependency.dst_subpass) {
+                Some(other) => {
+                    other.src_stage_mask |= dependency.src_stage_mask;
+                    other.dst_stage_mask |= dependency.dst_stage_mask;
+                    other.src_access_mask |= dependency.src_access_mask;
+                    other.dst_access_mask |= dependency.dst_access_mask;
+                },
+                None => self.dependencies.push(dependency),
+            }
+        }
+
+        self.before.extend(before);
+        self.after.extend(after);
+        self.add(subpass.clone(), pass, images);
+
+        true
+    }
+
+    fn flush(self, output: &mut Vec<Instruction>) {
+        let Group { mut subpasses, dependencies, before, after, .. } = self;
+
+        output.extend(before);
+
+        if subpasses.len() == 1 {
+            let subpass = subpasses.remove(0);
+            output.push(Instruction::ExecutePass {
+                id: subpass.id,
+                bind_point: PipelineBindPoint::Graphics,
+                color_attachments: subpass.color_attachments,
+                depth_stencil_attachment: subpass.depth_stencil_attachment,
+                input_attachments: subpass.input_attachments,
+                resolve_attachments: subpass.resolve_attachments,
+                attachment_ops: subpass.attachment_ops,
+            });
+        } else {
+            output.push(Instruction::ExecuteSubpasses {
+                subpasses,
+                dependencies,
+            });
+        }
+
+        output.extend(after);
+    }
+}
+
+/// The size and sample count all attachments of a pass share
+fn get_framebuffer(subpass: &Subpass, images: &HashMap<u32, MergeImage>) -> Option<(u32, u32, SampleCount)> {
+    let attachment = *subpass.get_attachments().first()?;
+    let description = images.get(&attachment.image.id)?.description;
+    let (width, height, _) = description.get_mip_extent(attachment.range.base_mip_level);
+    Some((width, height, description.sample_count))
+}
+
+/// Merges consecutive graphics passes with the same framebuffer size into the subpasses of a single render pass,
+/// so attachments written by one pass and read by the next as input attachments can stay in tile memory.
+/// Barriers between the passes become subpass dependencies, barriers on other resources move in front of the render pass.
+pub(crate) fn merge_subpasses(instructions: Vec<Instruction>, passes: &HashMap<u32, &Pass>, images: &HashMap<u32, MergeImage>) -> Vec<Instruction> {
+    let mut output = Vec::new();
+    let mut group: Option<Group> = None;
+    // everything since the last pass of the group
+    let mut pending = Vec::new();
+
+    for instruction in instructions {
+        let subpass = match &instruction {
+            Instruction::ExecutePass { id, bind_point: PipelineBindPoint::Graphics, color_attachments, depth_stencil_attachment, input_attachments, resolve_attachments, attachment_ops }
+                if !color_attachments.is_empty() || depth_stencil_attachment.is_some() => Subpass {
+                    id: *id,
+                    color_attachments: color_attachments.clone(),
+                    depth_stencil_attachment: *depth_stencil_attachment,
+                    input_attachments: input_attachments.clone(),
+                    resolve_attachments: resolve_attachments.clone(),
+                    attachment_ops: attachment_ops.clone(),
+                },
+            _ => {
+                pending.push(instruction);
+                continue;
+            },
+        };
+
+        let pass = passes[&subpass.id];
+        let framebuffer = match get_framebuffer(&subpass, images) {
+            Some(framebuffer) => framebuffer,
+            None => {
+                pending.push(instruction);
+                continue;
+            },
+        };
+
+        if let Some(group) = group.as_mut() {
+            if group.try_add(&subpass, pass, framebuffer, &pending, images) {
+                pending.clear();
+                continue;
+            }
+        }
+
+        if let Some(group) = group.take() {
+            group.flush(&mut output);
+        }
+
+        output.extend(pending.drain(..));
+        group = Some(Group::new(subpass, pass, framebuffer, images));
+    }
+
+    if let Some(group) = group.take() {
+        group.flush(&mut output);
+    }
+
+    output.extend(pending);
+    output
+}
@@ -0,0 +1,69 @@
+use crate::graphics::QueueType;
+
+/// A block of memory shared by transient images whose lifetimes don't overlap
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientAllocation {
+    pub id: u32,
+    pub size: u64,
+    pub images: Vec<u32>,
+}
+
+/// The range of passes, in execution order, an image is alive for, and the queue using it if there is only one
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ImageLifetime {
+    pub id: u32,
+    pub first_use: usize,
+    pub last_use: usize,
+    pub size: u64,
+    pub is_depth: bool,
+    pub queue: Option<QueueType>,
+}
+
+impl ImageLifetime {
+    fn overlaps(&self, other: &ImageLifetime) -> bool {
+        self.first_use <= other.last_use && other.first_use <= self.last_use
+    }
+}
+
+/// Assigns every image to an allocation, images only share an allocation if they are never alive at the same time
+pub(crate) fn assign_allocations(lifetimes: &[ImageLifetime]) -> Vec<TransientAllocation> {
+
+    // place the biggest images first, so smaller ones fill up the gaps
+    let mut lifetimes = lifetimes.to_vec();
+    lifetimes.sort_by(|a, b| b.size.cmp(&a.size).then(a.first_use.cmp(&b.first_use)).then(a.id.cmp(&b.id)));
+
+    let mut allocations: Vec<TransientAllocation> = Vec::new();
+    let mut allocation_lifetimes: Vec<Vec<ImageLifetime>> = Vec::new();
+
+    for lifetime in lifetimes {
+        // depth and color images may require different memory types, so they never alias,
+        // neither do images shared between queues since the queues don't wait for each other to finish with the memory
+        let index = allocation_lifetimes.iter().position(|others| {
+            others.iter().all(|other| {
+                other.is_depth == lifetime.is_depth
+                    && other.queue.is_some()
+                    && other.queue == lifetime.queue
+                    && !other.overlaps(&lifetime)
+            })
+        });
+
+        match index {
+            Some(index) => {
+                let allocation = &mut allocations[index];
+                allocation.size = allocation.size.max(lifetime.size);
+                allocation.images.push(lifetime.id);
+                allocation_lifetimes[index].push(lifetime);
+            },
+            None => {
+                allocations.push(TransientAllocation {
+                    id: allocations.len() as u32,
+                    size: lifetime.size,
+                    images: vec![lifetime.id],
+                });
+                allocation_lifetimes.push(vec![lifetime]);
+            },
+        }
+    }
+
+    allocations
+}
@@ -1,19 +1,23 @@
 use ash::vk;
+use std::collections::HashMap;
 use crate::{
     Error,
     graphics::vulkan::*,
 };
 
+/// Semaphores, command pools and query pools that get reused once the frame using them is finished
 pub struct Resources {
     semaphores: SemaphorePool,
-    fences: FencePool,
+    command_pools: HashMap<u32, Vec<CommandPool>>,
+    query_pools: Vec<QueryPool>,
 }
 
 impl Resources {
-    pub fn new(semaphores: SemaphorePool, fences: FencePool) -> Self {
+    pub fn new(semaphores: SemaphorePool) -> Self {
         Self {
             semaphores,
-            fences,
+            command_pools: HashMap::new(),
+            query_pools: Vec::new(),
         }
     }
 
@@ -25,15 +29,32 @@ impl Resources {
         self.semaphores.return_semaphore(semaphore);
     }
     
-    pub fn get_fence(&mut self) -> Result<Fence, Error> {
-        self.fences.get_fence()
+    /// Returns a reset command pool of the queue family, pools are only created if none is left
+    pub fn get_command_pool(&mut self, device: &Device, queue_family_index: u32) -> Result<CommandPool, Error> {
+        match self.command_pools.get_mut(&queue_family_index).and_then(|command_pools| command_pools.pop()) {
+            Some(mut command_pool) => {
+                command_pool.reset()?;
+                Ok(command_pool)
+            },
+            None => device.create_command_pool(queue_family_index),
+        }
     }
 
-    pub fn return_fence(&mut self, fence: Fence) {
-        self.fences.return_fence(fence);
+    pub fn return_command_pool(&mut self, command_pool: CommandPool) {
+        self.command_pools.entry(command_pool.get_queue_family_index())
+            .or_insert_with(Vec::new)
+            .push(command_pool);
     }
 
-    pub fn return_command_pool(&mut self, command_pool: CommandPool) {
-        drop(command_pool);
+    /// Returns a query pool with at least the given number of timestamp queries
+    pub fn get_query_pool(&mut self, device: &Device, count: u32) -> Result<QueryPool, Error> {
+        match self.query_pools.iter().position(|query_pool| query_pool.get_count() >= count) {
+            Some(index) => Ok(self.query_pools.swap_remove(index)),
+            None => device.create_timestamp_query_pool(count),
+        }
+    }
+
+    pub fn return_query_pool(&mut self, query_pool: QueryPool) {
+        self.query_pools.push(query_pool);
     }
 }
@@ -1,3 +1,5 @@
+use ash::vk;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[allow(non_camel_case_types)]
 pub enum SampleCount {
@@ -7,3 +9,27 @@ pub enum SampleCount {
     Type_8,
     Type_16,
 }
+
+impl SampleCount {
+    pub fn get_count(&self) -> u32 {
+        match self {
+            SampleCount::Type_1 => 1,
+            SampleCount::Type_2 => 2,
+            SampleCount::Type_4 => 4,
+            SampleCount::Type_8 => 8,
+            SampleCount::Type_16 => 16,
+        }
+    }
+}
+
+impl Into<vk::SampleCountFlags> for SampleCount {
+    fn into(self) -> vk::SampleCountFlags {
+        match self {
+            SampleCount::Type_1 => vk::SampleCountFlags::TYPE_1,
+            SampleCount::Type_2 => vk::SampleCountFlags::TYPE_2,
+            SampleCount::Type_4 => vk::SampleCountFlags::TYPE_4,
+            SampleCount::Type_8 => vk::SampleCountFlags::TYPE_8,
+            SampleCount::Type_16 => vk::SampleCountFlags::TYPE_16,
+        }
+    }
+}
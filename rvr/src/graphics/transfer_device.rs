@@ -1,6 +1,48 @@
-use crate::{Error, graphics::vulkan::Device};
-use std::sync::Arc;
-use rvr_assets::shader::ShaderAsset;
+use ash::vk;
+use crate::{Error, graphics::vulkan::*};
+use std::{sync::Arc, ffi::CString};
+use rvr_assets::shader::{self as assets, ShaderAsset};
+
+impl Into<ShaderStageType> for assets::ShaderStageType {
+    fn into(self) -> ShaderStageType {
+        match self {
+            assets::ShaderStageType::Vertex => ShaderStageType::Vertex,
+            assets::ShaderStageType::Geometry => ShaderStageType::Geometry,
+            assets::ShaderStageType::TessellationControl => ShaderStageType::TessellationControl,
+            assets::ShaderStageType::TessellationEvaluation => ShaderStageType::TessellationEvaluation,
+            assets::ShaderStageType::Fragment => ShaderStageType::Fragment,
+            assets::ShaderStageType::Compute => ShaderStageType::Compute,
+        }
+    }
+}
+
+impl Into<DescriptorType> for assets::DescriptorType {
+    fn into(self) -> DescriptorType {
+        match self {
+            assets::DescriptorType::Sampler => DescriptorType::Sampler,
+            assets::DescriptorType::CombinedImageSampler => DescriptorType::CombinedImageSampler,
+            assets::DescriptorType::SampledImage => DescriptorType::SampledImage,
+            assets::DescriptorType::StorageImage => DescriptorType::StorageImage,
+            assets::DescriptorType::UniformTexelBuffer => DescriptorType::UniformTexelBuffer,
+            assets::DescriptorType::StorageTexelBuffer => DescriptorType::StorageTexelBuffer,
+            assets::DescriptorType::UniformBuffer => DescriptorType::UniformBuffer,
+            assets::DescriptorType::StorageBuffer => DescriptorType::StorageBuffer,
+            assets::DescriptorType::UniformBufferDynamic => DescriptorType::UniformBufferDynamic,
+            assets::DescriptorType::StorageBufferDynamic => DescriptorType::StorageBufferDynamic,
+            assets::DescriptorType::InputAttachment => DescriptorType::InputAttachment,
+            assets::DescriptorType::AccelerationStructureNV => DescriptorType::AccelerationStructureNV,
+        }
+    }
+}
+
+// the asset flags and formats use the values of their vulkan counterparts
+fn to_vk_stage_flags(stage_flags: assets::ShaderStageFlags) -> vk::ShaderStageFlags {
+    vk::ShaderStageFlags::from_raw(stage_flags.bits())
+}
+
+fn to_vk_format(format: rvr_assets::Format) -> vk::Format {
+    vk::Format::from_raw(format as i32)
+}
 
 pub struct TransferDevice {
     device: Arc<Device>,
@@ -13,7 +55,29 @@ impl TransferDevice {
         }
     }
 
-    pub fn create_pipeline(&mut self, shader_asset: &ShaderAsset) -> Result<(), Error> {
-        Ok(())
+    /// Converts a shader asset into the description its pipelines get created from
+    pub fn create_shader(&mut self, name: &str, shader_asset: &ShaderAsset) -> Result<ShaderDescription, Error> {
+        let stages = shader_asset.stages.iter()
+            .map(|stage| Ok(ShaderStage::new(stage.spirv.clone(), stage.stage_type.into(), CString::new(stage.entry_point.as_str())?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let attributes = shader_asset.attributes.iter()
+            .map(|attribute| VertexAttribute::new(attribute.location, attribute.stride, to_vk_format(attribute.format)))
+            .collect();
+
+        let descriptor_set_layouts = shader_asset.descriptor_set_layouts.iter()
+            .map(|layout| DescriptorSetLayout {
+                set: layout.set,
+                bindings: layout.bindings.iter()
+                    .map(|binding| DescriptorSetLayoutBinding::new(binding.binding, binding.descriptor_type.into(), binding.count, to_vk_stage_flags(binding.stage_flags)))
+                    .collect(),
+            })
+            .collect();
+
+        let push_constants = shader_asset.push_constants.iter()
+            .map(|push_constant| PushConstant::new(push_constant.offset, push_constant.size, to_vk_stage_flags(push_constant.stage_flags)))
+            .collect();
+
+        Ok(ShaderDescription::new(name.to_owned(), stages, attributes, descriptor_set_layouts, push_constants))
     }
 }
@@ -0,0 +1,116 @@
+use ash::vk;
+use serde_derive::*;
+use std::{fmt, str::FromStr};
+use crate::{Error, graphics::GraphicsError};
+
+/// What happens to validation errors, every mode but `Off` also delivers the messages to `Renderer::take_validation_messages`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationMode {
+    /// Validation layers are not enabled
+    Off,
+    /// Messages get printed
+    Log,
+    /// The next frame that checks for validation errors fails with an error
+    Error,
+    /// The next frame that checks for validation errors panics, meant for tests
+    Panic,
+}
+
+impl ValidationMode {
+    pub fn is_enabled(&self) -> bool {
+        *self != ValidationMode::Off
+    }
+}
+
+impl FromStr for ValidationMode {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Error> {
+        match value.trim().to_lowercase().as_str() {
+            "0" | "off" | "false" => Ok(ValidationMode::Off),
+            "log" => Ok(ValidationMode::Log),
+            "error" => Ok(ValidationMode::Error),
+            "panic" => Ok(ValidationMode::Panic),
+            _ => Err(GraphicsError::InvalidConfiguration("validation mode".to_owned(), value.to_owned()).into()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ValidationSeverity {
+    Verbose,
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<vk::DebugUtilsMessageSeverityFlagsEXT> for ValidationSeverity {
+    fn from(severity: vk::DebugUtilsMessageSeverityFlagsEXT) -> Self {
+        if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            ValidationSeverity::Error
+        } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            ValidationSeverity::Warning
+        } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            ValidationSeverity::Info
+        } else {
+            ValidationSeverity::Verbose
+        }
+    }
+}
+
+/// An object a message is about, with the name it was given through `Device::set_object_name`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValidationObject {
+    pub object_type: vk::ObjectType,
+    pub handle: u64,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValidationMessage {
+    pub severity: ValidationSeverity,
+    pub message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    /// The id of the check that failed, like `VUID-vkCmdDraw-None-02859`
+    pub message_id: String,
+    pub objects: Vec<ValidationObject>,
+    pub text: String,
+}
+
+impl fmt::Display for ValidationMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}: {}", self.severity, self.message_id, self.text)?;
+
+        for object in self.objects.iter() {
+            write!(f, "\n    {:?} 0x{:x}", object.object_type, object.handle)?;
+            if let Some(name) = &object.name {
+                write!(f, " {}", name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_lists_the_objects_of_a_message() {
+        let message = ValidationMessage {
+            severity: ValidationSeverity::from(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR),
+            message_types: vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            message_id: "VUID-vkCmdDraw-None-02859".to_owned(),
+            objects: vec![
+                ValidationObject { object_type: vk::ObjectType::PIPELINE, handle: 0x2a, name: Some("shaders/gbuffer".to_owned()) },
+                ValidationObject { object_type: vk::ObjectType::COMMAND_BUFFER, handle: 0x10, name: None },
+            ],
+            text: "Render pass is incompatible".to_owned(),
+        };
+
+        assert_eq!(message.to_string(), "Error VUID-vkCmdDraw-None-02859: Render pass is incompatible\n    PIPELINE 0x2a shaders/gbuffer\n    COMMAND_BUFFER 0x10");
+        assert_eq!("Panic".parse::<ValidationMode>().unwrap(), ValidationMode::Panic);
+        assert!("strict".parse::<ValidationMode>().is_err());
+    }
+}
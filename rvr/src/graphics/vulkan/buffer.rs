@@ -0,0 +1,88 @@
+use ash::vk;
+use vk_mem::{Allocator, Allocation, AllocationCreateInfo, MemoryUsage};
+use std::sync::Arc;
+use crate::Error;
+
+pub struct Buffer {
+    inner: vk::Buffer,
+    allocation: Allocation,
+    allocator: Arc<Allocator>,
+    size: usize,
+}
+
+impl Buffer {
+    /// Creates a buffer in device local memory
+    pub fn create(allocator: Arc<Allocator>, size: usize, usage: vk::BufferUsageFlags) -> Result<Self, Error> {
+
+        let create_info = vk::BufferCreateInfo::builder()
+            .size(size as u64)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let allocation_create_info = AllocationCreateInfo {
+            usage: MemoryUsage::GpuOnly,
+            ..Default::default()
+        };
+
+        let (inner, allocation, _) = allocator.create_buffer(&create_info, &allocation_create_info)?;
+
+        Ok(Self {
+            inner,
+            allocation,
+            allocator,
+            size,
+        })
+    }
+
+    /// Creates a host visible buffer the device can copy into, used to read results back to the cpu
+    pub fn create_readback(allocator: Arc<Allocator>, size: usize) -> Result<Self, Error> {
+
+        let create_info = vk::BufferCreateInfo::builder()
+            .size(size as u64)
+            .usage(vk::BufferUsageFlags::TRANSFER_DST)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let allocation_create_info = AllocationCreateInfo {
+            usage: MemoryUsage::GpuToCpu,
+            required_flags: vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            ..Default::default()
+        };
+
+        let (inner, allocation, _) = allocator.create_buffer(&create_info, &allocation_create_info)?;
+
+        Ok(Self {
+            inner,
+            allocation,
+            allocator,
+            size,
+        })
+    }
+
+    pub(crate) fn get_inner(&self) -> vk::Buffer {
+        self.inner
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Copies the contents to the host, the device writes have to be finished and made available to the host with a barrier
+    pub fn read(&self) -> Result<Vec<u8>, Error> {
+        let mut data = vec![0u8; self.size];
+
+        // readback buffers are host coherent, so the mapped memory needs no invalidation
+        let pointer = self.allocator.map_memory(&self.allocation)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(pointer, data.as_mut_ptr(), self.size);
+        }
+        self.allocator.unmap_memory(&self.allocation)?;
+
+        Ok(data)
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        self.allocator.destroy_buffer(self.inner, &self.allocation).unwrap();
+    }
+}
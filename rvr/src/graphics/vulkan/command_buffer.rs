@@ -1,6 +1,32 @@
 use ash::{vk, version::DeviceV1_0};
 use std::sync::Arc;
 use crate::Error;
+use super::*;
+
+/// A layout transition and memory dependency of a single image
+#[derive(Debug, Copy, Clone)]
+pub struct ImageBarrier {
+    pub image: vk::Image,
+    pub subresource_range: vk::ImageSubresourceRange,
+    pub old_layout: vk::ImageLayout,
+    pub new_layout: vk::ImageLayout,
+    pub src_access_mask: vk::AccessFlags,
+    pub dst_access_mask: vk::AccessFlags,
+    // vk::QUEUE_FAMILY_IGNORED unless the barrier transfers ownership between queue families
+    pub src_queue_family_index: u32,
+    pub dst_queue_family_index: u32,
+}
+
+/// A memory dependency of a whole buffer
+#[derive(Debug, Copy, Clone)]
+pub struct BufferBarrier {
+    pub buffer: vk::Buffer,
+    pub src_access_mask: vk::AccessFlags,
+    pub dst_access_mask: vk::AccessFlags,
+    // vk::QUEUE_FAMILY_IGNORED unless the barrier transfers ownership between queue families
+    pub src_queue_family_index: u32,
+    pub dst_queue_family_index: u32,
+}
 
 pub struct CommandBuffer {
     inner: vk::CommandBuffer,
@@ -30,6 +56,30 @@ impl CommandBuffer {
         Ok(())
     }
 
+    /// Begins a secondary command buffer, inside a render pass it continues the subpass it gets executed in
+    pub fn begin_secondary(&self, subpass: Option<(vk::RenderPass, u32, vk::Framebuffer)>) -> Result<(), Error> {
+        let (flags, inheritance_info) = match subpass {
+            Some((render_pass, subpass, framebuffer)) => (
+                vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT | vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE,
+                vk::CommandBufferInheritanceInfo::builder()
+                    .render_pass(render_pass)
+                    .subpass(subpass)
+                    .framebuffer(framebuffer),
+            ),
+            None => (vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, vk::CommandBufferInheritanceInfo::builder()),
+        };
+
+        let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(flags)
+            .inheritance_info(&inheritance_info);
+
+        unsafe {
+            self.device.begin_command_buffer(self.inner, &command_buffer_begin_info)?;
+        }
+
+        Ok(())
+    }
+
     pub fn end(&self) -> Result<(), Error> {
         unsafe {
             self.device.end_command_buffer(self.inner)?;
@@ -38,30 +88,223 @@ impl CommandBuffer {
         Ok(())
     }
 
-    pub fn pipeline_barrier(&mut self, image: vk::Image) {
+    /// Begins a render pass whose subpasses get recorded into secondary command buffers
+    pub fn begin_render_pass(&mut self, render_pass: vk::RenderPass, framebuffer: vk::Framebuffer, width: u32, height: u32, clear_values: &[vk::ClearValue]) {
 
-        let image_barrier = vk::ImageMemoryBarrier::builder()
-            .image(image)
-            .old_layout(vk::ImageLayout::UNDEFINED)
-            .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
-            .subresource_range(vk::ImageSubresourceRange {
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(render_pass)
+            .framebuffer(framebuffer)
+            .render_area(vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent: vk::Extent2D {
+                    width,
+                    height,
+                },
+            })
+            .clear_values(clear_values);
+
+        unsafe {
+            self.device.cmd_begin_render_pass(self.inner, &render_pass_begin_info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
+        }
+    }
+
+    pub fn next_subpass(&mut self) {
+        unsafe {
+            self.device.cmd_next_subpass(self.inner, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
+        }
+    }
+
+    pub fn execute_commands(&mut self, command_buffers: &[&CommandBuffer]) {
+        let command_buffers: Vec<vk::CommandBuffer> = command_buffers.iter().map(|command_buffer| command_buffer.inner).collect();
+
+        unsafe {
+            self.device.cmd_execute_commands(self.inner, &command_buffers);
+        }
+    }
+
+    pub fn end_render_pass(&mut self) {
+        unsafe {
+            self.device.cmd_end_render_pass(self.inner);
+        }
+    }
+
+    pub fn bind_pipeline(&mut self, pipeline: &Pipeline) {
+        unsafe {
+            self.device.cmd_bind_pipeline(self.inner, pipeline.get_bind_point(), pipeline.get_inner());
+        }
+    }
+
+    /// Sets viewport and scissor to the whole area, they are dynamic state of every graphics pipeline
+    pub fn set_render_area(&mut self, width: u32, height: u32) {
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: width as f32,
+            height: height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+
+        let scissor = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: vk::Extent2D {
+                width,
+                height,
+            },
+        };
+
+        unsafe {
+            self.device.cmd_set_viewport(self.inner, 0, &[viewport]);
+            self.device.cmd_set_scissor(self.inner, 0, &[scissor]);
+        }
+    }
+
+    pub fn bind_vertex_buffers(&mut self, first_binding: u32, buffers: &[vk::Buffer], offsets: &[vk::DeviceSize]) {
+        unsafe {
+            self.device.cmd_bind_vertex_buffers(self.inner, first_binding, buffers, offsets);
+        }
+    }
+
+    pub fn bind_index_buffer(&mut self, buffer: vk::Buffer, offset: vk::DeviceSize, index_type: vk::IndexType) {
+        unsafe {
+            self.device.cmd_bind_index_buffer(self.inner, buffer, offset, index_type);
+        }
+    }
+
+    pub fn bind_descriptor_sets(&mut self, bind_point: vk::PipelineBindPoint, layout: vk::PipelineLayout, first_set: u32, descriptor_sets: &[vk::DescriptorSet], dynamic_offsets: &[u32]) {
+        unsafe {
+            self.device.cmd_bind_descriptor_sets(
+                self.inner,
+                bind_point,
+                layout,
+                first_set,
+                descriptor_sets,
+                dynamic_offsets,
+            );
+        }
+    }
+
+    pub fn push_constants(&mut self, layout: vk::PipelineLayout, stages: vk::ShaderStageFlags, offset: u32, data: &[u8]) {
+        unsafe {
+            self.device.cmd_push_constants(self.inner, layout, stages, offset, data);
+        }
+    }
+
+    pub fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32) {
+        unsafe {
+            self.device.cmd_draw(self.inner, vertex_count, instance_count, first_vertex, first_instance);
+        }
+    }
+
+    pub fn draw_indexed(&mut self, index_count: u32, instance_count: u32, first_index: u32, vertex_offset: i32, first_instance: u32) {
+        unsafe {
+            self.device.cmd_draw_indexed(self.inner, index_count, instance_count, first_index, vertex_offset, first_instance);
+        }
+    }
+
+    pub fn draw_indirect(&mut self, buffer: vk::Buffer, offset: vk::DeviceSize, draw_count: u32, stride: u32) {
+        unsafe {
+            self.device.cmd_draw_indirect(self.inner, buffer, offset, draw_count, stride);
+        }
+    }
+
+    pub fn draw_indexed_indirect(&mut self, buffer: vk::Buffer, offset: vk::DeviceSize, draw_count: u32, stride: u32) {
+        unsafe {
+            self.device.cmd_draw_indexed_indirect(self.inner, buffer, offset, draw_count, stride);
+        }
+    }
+
+    pub fn dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
+        unsafe {
+            self.device.cmd_dispatch(self.inner, group_count_x, group_count_y, group_count_z);
+        }
+    }
+
+    /// Queries have to be reset outside of render passes before timestamps can be written to them
+    pub fn reset_query_pool(&mut self, query_pool: vk::QueryPool, first_query: u32, query_count: u32) {
+        unsafe {
+            self.device.cmd_reset_query_pool(self.inner, query_pool, first_query, query_count);
+        }
+    }
+
+    pub fn write_timestamp(&mut self, stage: vk::PipelineStageFlags, query_pool: vk::QueryPool, query: u32) {
+        unsafe {
+            self.device.cmd_write_timestamp(self.inner, stage, query_pool, query);
+        }
+    }
+
+    pub fn pipeline_barrier(
+        &mut self,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        buffer_barriers: &[BufferBarrier],
+        image_barriers: &[ImageBarrier],
+    ) {
+
+        let buffer_barriers: Vec<vk::BufferMemoryBarrier> = buffer_barriers.iter()
+            .map(|barrier| vk::BufferMemoryBarrier::builder()
+                .buffer(barrier.buffer)
+                .offset(0)
+                .size(vk::WHOLE_SIZE)
+                .src_access_mask(barrier.src_access_mask)
+                .dst_access_mask(barrier.dst_access_mask)
+                .src_queue_family_index(barrier.src_queue_family_index)
+                .dst_queue_family_index(barrier.dst_queue_family_index)
+                .build())
+            .collect();
+
+        let image_barriers: Vec<vk::ImageMemoryBarrier> = image_barriers.iter()
+            .map(|barrier| vk::ImageMemoryBarrier::builder()
+                .image(barrier.image)
+                .old_layout(barrier.old_layout)
+                .new_layout(barrier.new_layout)
+                .src_access_mask(barrier.src_access_mask)
+                .dst_access_mask(barrier.dst_access_mask)
+                .src_queue_family_index(barrier.src_queue_family_index)
+                .dst_queue_family_index(barrier.dst_queue_family_index)
+                .subresource_range(barrier.subresource_range)
+                .build())
+            .collect();
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                self.inner,
+                src_stage_mask,
+                dst_stage_mask,
+                vk::DependencyFlags::empty(),
+                &[],
+                &buffer_barriers,
+                &image_barriers,
+            );
+        }
+    }
+
+    pub fn copy_image_to_buffer(&mut self, image: vk::Image, width: u32, height: u32, buffer: vk::Buffer) {
+
+        let region = vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(vk::ImageSubresourceLayers {
                 aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
+                mip_level: 0,
                 base_array_layer: 0,
                 layer_count: 1,
             })
+            .image_extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            })
             .build();
 
         unsafe {
-            self.device.cmd_pipeline_barrier(
+            self.device.cmd_copy_image_to_buffer(
                 self.inner,
-                vk::PipelineStageFlags::TOP_OF_PIPE,
-                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
-                vk::DependencyFlags::BY_REGION,
-                &[],
-                &[],
-                &[image_barrier],
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer,
+                &[region],
             );
         }
     }
@@ -6,6 +6,12 @@ use super::*;
 pub struct CommandPool {
     inner: vk::CommandPool,
     device: Arc<ash::Device>,
+    queue_family_index: u32,
+    // command buffers stay allocated after a reset, the next frames record into them again
+    primary_buffers: Vec<vk::CommandBuffer>,
+    secondary_buffers: Vec<vk::CommandBuffer>,
+    used_primary_buffers: usize,
+    used_secondary_buffers: usize,
 }
 
 impl CommandPool {
@@ -21,32 +27,61 @@ impl CommandPool {
         Ok(Self {
             inner,
             device,
+            queue_family_index,
+            primary_buffers: Vec::new(),
+            secondary_buffers: Vec::new(),
+            used_primary_buffers: 0,
+            used_secondary_buffers: 0,
         })
     }
 
-    pub fn allocate_command_buffers(&self, count: u32, primary: bool) -> Result<Vec<CommandBuffer>, Error> {
+    pub fn get_queue_family_index(&self) -> u32 {
+        self.queue_family_index
+    }
+
+    pub fn allocate_command_buffers(&mut self, count: u32, primary: bool) -> Result<Vec<CommandBuffer>, Error> {
 
-        let buffer_level = if primary {
-            vk::CommandBufferLevel::PRIMARY
+        let (buffers, used, buffer_level) = if primary {
+            (&mut self.primary_buffers, &mut self.used_primary_buffers, vk::CommandBufferLevel::PRIMARY)
         } else {
-            vk::CommandBufferLevel::SECONDARY
+            (&mut self.secondary_buffers, &mut self.used_secondary_buffers, vk::CommandBufferLevel::SECONDARY)
         };
 
-        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
-            .command_buffer_count(count)
-            .command_pool(self.inner)
-            .level(buffer_level)
-            .build();
+        let missing = (*used + count as usize).saturating_sub(buffers.len());
+        if missing > 0 {
+            let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
+                .command_buffer_count(missing as u32)
+                .command_pool(self.inner)
+                .level(buffer_level)
+                .build();
 
-        let buffers = unsafe {
-            self.device.allocate_command_buffers(&command_buffer_allocate_info)?
-        };
+            let allocated = unsafe {
+                self.device.allocate_command_buffers(&command_buffer_allocate_info)?
+            };
 
-        let buffers = buffers.into_iter()
-            .map(|inner| CommandBuffer::new(Arc::clone(&self.device), inner))
+            buffers.extend(allocated);
+        }
+
+        let device = &self.device;
+        let command_buffers = buffers[*used..*used + count as usize].iter()
+            .map(|inner| CommandBuffer::new(Arc::clone(device), *inner))
             .collect();
 
-        Ok(buffers)
+        *used += count as usize;
+
+        Ok(command_buffers)
+    }
+
+    /// Moves all command buffers back to the initial state, none of them may still be pending
+    pub fn reset(&mut self) -> Result<(), Error> {
+        unsafe {
+            self.device.reset_command_pool(self.inner, vk::CommandPoolResetFlags::empty())?;
+        }
+
+        self.used_primary_buffers = 0;
+        self.used_secondary_buffers = 0;
+
+        Ok(())
     }
 }
 
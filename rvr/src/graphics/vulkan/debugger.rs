@@ -1,59 +1,195 @@
-use std::ffi::{CStr};
-use std::os::raw::{c_char, c_void};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::sync::Mutex;
 use ash::{
     vk,
     version::{EntryV1_0, InstanceV1_0},
-    extensions::ext::DebugReport,
+    extensions::ext::DebugUtils,
+};
+use crossbeam_channel::{bounded, Sender, Receiver};
+use crate::{
+    Error,
+    graphics::{GraphicsError, ValidationMode, ValidationSeverity, ValidationObject, ValidationMessage},
 };
-use crate::Error;
 use logos::Logos;
 use colour::*;
 
+// messages nobody takes get dropped once the channel is full
+const MAX_QUEUED_MESSAGES: usize = 1024;
+
+/// Receives the messages of the callback, boxed so the address handed to the messenger stays the same
+struct MessageSink {
+    sender: Sender<ValidationMessage>,
+    first_error: Mutex<Option<ValidationMessage>>,
+}
+
+unsafe fn to_string(ptr: *const std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
+
 unsafe extern "system" fn vulkan_debug_callback(
-    _: vk::DebugReportFlagsEXT,
-    _: vk::DebugReportObjectTypeEXT,
-    _: u64,
-    _: usize,
-    _: i32,
-    _: *const c_char,
-    p_message: *const c_char,
-    _: *mut c_void,
-) -> u32 {
-    parse(CStr::from_ptr(p_message).to_str().expect("could not parse debug message"));
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    p_user_data: *mut c_void,
+) -> vk::Bool32 {
+    let callback_data = &*p_callback_data;
+    let sink = &*(p_user_data as *const MessageSink);
+
+    let objects = if callback_data.object_count > 0 && !callback_data.p_objects.is_null() {
+        std::slice::from_raw_parts(callback_data.p_objects, callback_data.object_count as usize)
+    } else {
+        &[]
+    };
+
+    let message = ValidationMessage {
+        severity: message_severity.into(),
+        message_types,
+        message_id: to_string(callback_data.p_message_id_name).unwrap_or_default(),
+        objects: objects.iter()
+            .map(|object| ValidationObject {
+                object_type: object.object_type,
+                handle: object.object_handle,
+                name: to_string(object.p_object_name),
+            })
+            .collect(),
+        text: to_string(callback_data.p_message).unwrap_or_default(),
+    };
+
+    print_message(&message);
+
+    // panicking would unwind into the driver, so errors are only remembered here and raised by `Debugger::check`
+    if message.severity == ValidationSeverity::Error {
+        if let Ok(mut first_error) = sink.first_error.lock() {
+            first_error.get_or_insert_with(|| message.clone());
+        }
+    }
+
+    let _ = sink.sender.try_send(message);
+
     vk::FALSE
 }
 
+fn print_message(message: &ValidationMessage) {
+    if message.severity == ValidationSeverity::Error {
+        red!("{:?} {} ", message.severity, message.message_id);
+    } else {
+        yellow!("{:?} {} ", message.severity, message.message_id);
+    }
+
+    parse(&message.text);
+
+    // the objects the message is about, named ones are much easier to find in a capture
+    for object in message.objects.iter() {
+        println!("    {:?} 0x{:x} {}", object.object_type, object.handle, object.name.as_deref().unwrap_or(""));
+    }
+}
+
+/// Reports validation messages and attaches names and labels to Vulkan objects for debugging tools
 pub struct Debugger {
-    extension_loader: DebugReport,
-    inner: vk::DebugReportCallbackEXT,
+    extension_loader: DebugUtils,
+    inner: vk::DebugUtilsMessengerEXT,
+    mode: ValidationMode,
+    sink: Box<MessageSink>,
+    receiver: Receiver<ValidationMessage>,
 }
 
 impl Debugger {
-    pub fn create<E: EntryV1_0, I: InstanceV1_0>(entry: &E, instance: &I) -> Result<Self, Error> {
-        let debug_info = vk::DebugReportCallbackCreateInfoEXT::builder()
-            .flags(
-                vk::DebugReportFlagsEXT::ERROR
-                | vk::DebugReportFlagsEXT::WARNING
-                | vk::DebugReportFlagsEXT::PERFORMANCE_WARNING,
+    pub fn create<E: EntryV1_0, I: InstanceV1_0>(entry: &E, instance: &I, mode: ValidationMode) -> Result<Self, Error> {
+        let (sender, receiver) = bounded(MAX_QUEUED_MESSAGES);
+        let sink = Box::new(MessageSink {
+            sender,
+            first_error: Mutex::new(None),
+        });
+
+        let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+            .message_severity(
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            )
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
             )
-            .pfn_callback(Some(vulkan_debug_callback));
+            .pfn_user_callback(Some(vulkan_debug_callback))
+            .user_data(&*sink as *const MessageSink as *mut c_void);
 
-        let extension_loader = DebugReport::new(entry, instance);
+        let extension_loader = DebugUtils::new(entry, instance);
         let inner = unsafe {
-            extension_loader.create_debug_report_callback(&debug_info, None)?
+            extension_loader.create_debug_utils_messenger(&debug_info, None)?
         };
 
         Ok(Self {
             extension_loader,
             inner,
+            mode,
+            sink,
+            receiver,
         })
     }
+
+    /// Takes the messages that arrived since the last call
+    pub fn take_messages(&self) -> Vec<ValidationMessage> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Raises the first validation error since the last check, depending on the mode
+    pub fn check(&self) -> Result<(), Error> {
+        let first_error = match self.sink.first_error.lock() {
+            Ok(mut first_error) => first_error.take(),
+            Err(_) => None,
+        };
+
+        match (first_error, self.mode) {
+            (Some(message), ValidationMode::Error) => Err(GraphicsError::ValidationFailed(message.to_string()).into()),
+            (Some(message), ValidationMode::Panic) => panic!("Validation failed: {}", message),
+            _ => Ok(()),
+        }
+    }
+
+    /// Names an object, validation messages and captures show the name next to the handle
+    pub fn set_object_name<H: vk::Handle>(&self, device: vk::Device, handle: H, name: &str) -> Result<(), Error> {
+        let name = CString::new(name)?;
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+            .object_type(H::TYPE)
+            .object_handle(handle.as_raw())
+            .object_name(&name);
+
+        unsafe {
+            self.extension_loader.debug_utils_set_object_name(device, &name_info)?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens a labeled region of commands, every begin needs an end in the same command buffer
+    pub fn begin_label(&self, command_buffer: vk::CommandBuffer, name: &str) -> Result<(), Error> {
+        let name = CString::new(name)?;
+        let label = vk::DebugUtilsLabelEXT::builder().label_name(&name);
+
+        unsafe {
+            self.extension_loader.cmd_begin_debug_utils_label(command_buffer, &label);
+        }
+
+        Ok(())
+    }
+
+    pub fn end_label(&self, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            self.extension_loader.cmd_end_debug_utils_label(command_buffer);
+        }
+    }
 }
 
 impl Drop for Debugger {
     fn drop(&mut self) {
         unsafe {
-            self.extension_loader.destroy_debug_report_callback(self.inner, None);
+            self.extension_loader.destroy_debug_utils_messenger(self.inner, None);
         }
     }
 }
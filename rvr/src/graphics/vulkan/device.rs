@@ -13,7 +13,7 @@ use std::{
 };
 use crate::{
     Error,
-    graphics::GraphicsError,
+    graphics::{GraphicsError, DevicePreference, ValidationMode, ValidationMessage},
     graphics::vulkan::*,
 };
 
@@ -26,7 +26,7 @@ pub struct Device {
     extensions: Extensions,
 
     debugger: Option<ManuallyDrop<Debugger>>,
-    surface: ManuallyDrop<Surface>,
+    surface: Option<ManuallyDrop<Surface>>,
     allocator: ManuallyDrop<Arc<Allocator>>,
 
     inner: Arc<ash::Device>,
@@ -37,7 +37,14 @@ pub struct Device {
 }
 
 impl Device {
-    pub fn create(app_name: &CStr, app_version: u32, window_handle: RawWindowHandle, debugging_enabled: bool) -> Result<Self, Error> {
+    /// Creates the device, passing no window handle creates a headless device without surface and swapchain support
+    pub fn create(
+        app_name: &CStr,
+        app_version: u32,
+        window_handle: Option<RawWindowHandle>,
+        validation: ValidationMode,
+        device_preference: Option<&DevicePreference>,
+    ) -> Result<Self, Error> {
         let entry = ash::Entry::new()?;
 
         let engine_name = CString::new("Rust Vulkan Renderer").unwrap();
@@ -52,13 +59,15 @@ impl Device {
         let mut layers = Layers::new();
         let mut extensions = Extensions::new();
 
-        if debugging_enabled {
+        if validation.is_enabled() {
             layers.enable_validation_layers();
-            extensions.enable_ext_debug_report();
+            extensions.enable_ext_debug_utils();
         }
 
-        extensions.enable_khr_surface();
-        extensions.enable_khr_win32_surface();
+        if let Some(window_handle) = window_handle {
+            extensions.enable_khr_surface();
+            Surface::enable_extensions(&mut extensions, window_handle)?;
+        }
 
         let layer_names_raw = layers.get_names_raw();
         let extension_names_raw = extensions.get_names_raw();
@@ -70,16 +79,22 @@ impl Device {
 
         let instance: ash::Instance = unsafe { entry.create_instance(&create_info, None)? };
 
-        let debugger = if debugging_enabled {
-            Some(ManuallyDrop::new(Debugger::create(&entry, &instance)?))
+        let debugger = if validation.is_enabled() {
+            Some(ManuallyDrop::new(Debugger::create(&entry, &instance, validation)?))
         } else {
             None
         };
 
-        let surface = Surface::create(&entry, &instance, window_handle)?;
+        let surface = match window_handle {
+            Some(window_handle) => Some(Surface::create(&entry, &instance, window_handle)?),
+            None => None,
+        };
 
-        let profiles = unsafe { DeviceProfile::query_device_profiles(&entry, &instance, &surface)? };
-        let device_index = DeviceProfile::find_highest_score(&profiles).ok_or(GraphicsError::NoSuitableDevice)?;
+        let profiles = unsafe { DeviceProfile::query_device_profiles(&entry, &instance, surface.as_ref())? };
+        let device_index = match device_preference {
+            Some(preference) => DeviceProfile::find_preferred(&profiles, preference)?,
+            None => DeviceProfile::find_highest_score(&profiles).ok_or(GraphicsError::NoSuitableDevice)?,
+        };
         let profile = &profiles[device_index];
         let physical_device = profile.get_physical_device();
 
@@ -99,6 +114,9 @@ impl Device {
             .descriptor_binding_sampled_image_update_after_bind(true)
             .descriptor_binding_variable_descriptor_count(true);
 
+        let mut timeline_semaphore_features = vk::PhysicalDeviceTimelineSemaphoreFeatures::builder()
+            .timeline_semaphore(true);
+
         let graphics_queue_index = profile
             .get_graphics_queue_index()
             .ok_or(GraphicsError::NoSuitableGraphicsQueue)? as u32;
@@ -112,30 +130,35 @@ impl Device {
         let graphics_priorities = [1.0];
         let compute_priorities = [0.9];
         let transfer_priorities = [0.8];
-        let device_extension_names_raw = [khr::Swapchain::name().as_ptr()];
-
-        let queue_infos = [
-            vk::DeviceQueueCreateInfo::builder()
-                .queue_family_index(graphics_queue_index as u32)
-                .queue_priorities(&graphics_priorities)
-                .build(),
-            vk::DeviceQueueCreateInfo::builder()
-                .queue_family_index(compute_queue_index as u32)
-                .queue_priorities(&compute_priorities)
-                .build(),
-            vk::DeviceQueueCreateInfo::builder()
-                .queue_family_index(transfer_queue_index as u32)
-                .queue_priorities(&transfer_priorities)
-                .build(),
-        ];
+        let device_extension_names_raw = if surface.is_some() {
+            vec![khr::Swapchain::name().as_ptr()]
+        } else {
+            Vec::new()
+        };
+
+        // queue families may be shared, but every family can only be requested once
+        let mut queue_infos: Vec<vk::DeviceQueueCreateInfo> = Vec::new();
+        for (queue_index, priorities) in [
+            (graphics_queue_index, &graphics_priorities[..]),
+            (compute_queue_index, &compute_priorities[..]),
+            (transfer_queue_index, &transfer_priorities[..]),
+        ].iter() {
+            if queue_infos.iter().all(|info| info.queue_family_index != *queue_index) {
+                queue_infos.push(vk::DeviceQueueCreateInfo::builder()
+                    .queue_family_index(*queue_index)
+                    .queue_priorities(priorities)
+                    .build());
+            }
+        }
 
         let device_create_info = vk::DeviceCreateInfo::builder()
             .push_next(&mut descriptor_features)
+            .push_next(&mut timeline_semaphore_features)
             .queue_create_infos(&queue_infos)
             .enabled_extension_names(&device_extension_names_raw)
             .enabled_features(&features);
 
-        let inner = unsafe { instance.create_device(physical_device, &device_create_info, None)? };
+        let inner = Arc::new(unsafe { instance.create_device(physical_device, &device_create_info, None)? });
         let graphics_queue = unsafe { inner.get_device_queue(graphics_queue_index, 0) };
         let compute_queue = unsafe { inner.get_device_queue(compute_queue_index, 0) };
         let transfer_queue = unsafe { inner.get_device_queue(transfer_queue_index, 0) };
@@ -144,6 +167,7 @@ impl Device {
             graphics_queue_index,
             0,
             graphics_queue,
+            GpuTimeline::create(Arc::clone(&inner))?,
             true,
             true,
             true,
@@ -153,6 +177,7 @@ impl Device {
             compute_queue_index,
             0,
             compute_queue,
+            GpuTimeline::create(Arc::clone(&inner))?,
             false,
             false,
             true,
@@ -162,6 +187,7 @@ impl Device {
             transfer_queue_index,
             0,
             transfer_queue,
+            GpuTimeline::create(Arc::clone(&inner))?,
             false,
             false,
             false,
@@ -170,14 +196,14 @@ impl Device {
 
         let allocator_create_info = AllocatorCreateInfo {
             physical_device: physical_device,
-            device: inner.clone(),
+            device: (*inner).clone(),
             instance: instance.clone(),
             frame_in_use_count: 3,
             ..Default::default()
         };
         let allocator = vk_mem::Allocator::new(&allocator_create_info)?;
 
-        Ok(Self {
+        let device = Self {
             entry,
             instance,
             physical_device,
@@ -186,35 +212,145 @@ impl Device {
             extensions,
             
             debugger,
-            surface: ManuallyDrop::new(surface),
+            surface: surface.map(ManuallyDrop::new),
             allocator: ManuallyDrop::new(Arc::new(allocator)),
 
-            inner: Arc::new(inner),
+            inner,
 
             graphics_queue,
             compute_queue,
             transfer_queue,
-        })
+        };
+
+        for (queue, name) in [
+            (&device.graphics_queue, "Graphics"),
+            (&device.compute_queue, "Compute"),
+            (&device.transfer_queue, "Transfer"),
+        ].iter() {
+            device.set_object_name(queue.get_inner(), &format!("{} Queue", name))?;
+            device.set_object_name(queue.get_timeline().get_inner(), &format!("{} Timeline", name))?;
+        }
+
+        Ok(device)
+    }
+
+    /// Takes the validation messages that arrived since the last call
+    pub fn take_validation_messages(&self) -> Vec<ValidationMessage> {
+        match &self.debugger {
+            Some(debugger) => debugger.take_messages(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Fails or panics if a validation error occurred since the last check and the validation mode asks for it
+    pub fn check_validation(&self) -> Result<(), Error> {
+        match &self.debugger {
+            Some(debugger) => debugger.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Names an object for validation messages and debugging tools, does nothing unless debugging is enabled
+    pub fn set_object_name<H: vk::Handle>(&self, handle: H, name: &str) -> Result<(), Error> {
+        match &self.debugger {
+            Some(debugger) => debugger.set_object_name(self.inner.handle(), handle, name),
+            None => Ok(()),
+        }
+    }
+
+    /// Opens a labeled region of commands that shows up in captures, does nothing unless debugging is enabled
+    pub fn begin_label(&self, command_buffer: &mut CommandBuffer, name: &str) -> Result<(), Error> {
+        match &self.debugger {
+            Some(debugger) => debugger.begin_label(command_buffer.get_inner(), name),
+            None => Ok(()),
+        }
+    }
+
+    pub fn end_label(&self, command_buffer: &mut CommandBuffer) {
+        if let Some(debugger) = &self.debugger {
+            debugger.end_label(command_buffer.get_inner());
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// The extent of the surface, a width of `u32::MAX` means the swapchain decides it
+    pub fn get_surface_extent(&self) -> Result<vk::Extent2D, Error> {
+        let surface = self.surface.as_ref().ok_or(GraphicsError::NoSurface)?;
+
+        Ok(surface.get_physical_device_surface_capabilities(self.physical_device)?.current_extent)
     }
 
-    pub fn create_swapchain(&self, min_image_count: u32, width: u32, height: u32) -> Result<Swapchain, Error> {
+    /// Creates a swapchain for the surface, passing the old swapchain lets it hand over its resources
+    pub fn create_swapchain(
+        &self,
+        min_image_count: u32,
+        present_modes: &[vk::PresentModeKHR],
+        width: u32,
+        height: u32,
+        old_swapchain: Option<&Swapchain>,
+    ) -> Result<Swapchain, Error> {
+        let surface = self.surface.as_ref().ok_or(GraphicsError::NoSurface)?;
+
         Swapchain::new(
             &self.instance,
             Arc::clone(&self.inner),
-            &self.surface,
+            surface,
             self.physical_device,
             min_image_count,
+            present_modes,
             width,
             height,
+            old_swapchain,
         )
     }
 
-    pub fn create_fence_pool(&self) -> Result<FencePool, Error> {
-        Ok(FencePool::new(Arc::clone(&self.inner)))
+    pub fn create_image(&self, description: &ImageCreateDescription) -> Result<Image, Error> {
+        Image::create(Arc::clone(&self.allocator), description)
     }
 
-    pub fn get_fence_status(&self, fence: vk::Fence) -> Result<bool, Error> {
-        Ok(unsafe { self.inner.get_fence_status(fence)? })
+    pub fn create_unbound_image(&self, description: &ImageCreateDescription) -> Result<Image, Error> {
+        Image::create_unbound(Arc::clone(&self.inner), description)
+    }
+
+    pub fn allocate_memory(&self, memory_requirements: &vk::MemoryRequirements) -> Result<Memory, Error> {
+        Memory::allocate(Arc::clone(&self.allocator), memory_requirements)
+    }
+
+    pub fn create_image_view(&self, image: vk::Image, format: vk::Format, subresource_range: vk::ImageSubresourceRange) -> Result<ImageView, Error> {
+        ImageView::create(Arc::clone(&self.inner), image, format, subresource_range)
+    }
+
+    pub fn create_render_pass(&self, render_pass_description: &RenderPassDescription) -> Result<RenderPass, Error> {
+        RenderPass::create(Arc::clone(&self.inner), render_pass_description)
+    }
+
+    pub fn create_framebuffer(&self, render_pass: &RenderPass, attachments: &[&ImageView], width: u32, height: u32) -> Result<Framebuffer, Error> {
+        Framebuffer::create(Arc::clone(&self.inner), render_pass, attachments, width, height)
+    }
+
+    pub fn create_graphics_pipeline(&self, shader_description: &ShaderDescription, render_pass: vk::RenderPass, subpass: u32, color_attachment_count: u32, samples: vk::SampleCountFlags) -> Result<Pipeline, Error> {
+        let pipeline = Pipeline::create(Arc::clone(&self.inner), shader_description, render_pass, subpass, color_attachment_count, samples)?;
+        self.set_object_name(pipeline.get_inner(), shader_description.get_name())?;
+
+        Ok(pipeline)
+    }
+
+    pub fn create_compute_pipeline(&self, shader_description: &ShaderDescription) -> Result<Pipeline, Error> {
+        let pipeline = Pipeline::create_compute(Arc::clone(&self.inner), shader_description)?;
+        self.set_object_name(pipeline.get_inner(), shader_description.get_name())?;
+
+        Ok(pipeline)
+    }
+
+    pub fn create_buffer(&self, size: usize, usage: vk::BufferUsageFlags) -> Result<Buffer, Error> {
+        Buffer::create(Arc::clone(&self.allocator), size, usage)
+    }
+
+    pub fn create_readback_buffer(&self, size: usize) -> Result<Buffer, Error> {
+        Buffer::create_readback(Arc::clone(&self.allocator), size)
     }
 
     pub fn create_semaphore_pool(&self) -> Result<SemaphorePool, Error> {
@@ -225,25 +361,64 @@ impl Device {
         CommandPool::create(Arc::clone(&self.inner), queue_family_index)
     }
 
-    pub fn queue_submit(&self, queue: vk::Queue, command_buffer: &CommandBuffer, wait_stage_masks: &[vk::PipelineStageFlags], wait_semaphores: &[vk::Semaphore], signal_semaphores: &[vk::Semaphore], fence: vk::Fence) -> Result<(), Error> {
+    pub fn create_timestamp_query_pool(&self, count: u32) -> Result<QueryPool, Error> {
+        QueryPool::create_timestamps(Arc::clone(&self.inner), count)
+    }
 
-        let submit_buffers = [command_buffer.get_inner()];
+    /// Nanoseconds per timestamp tick
+    pub fn get_timestamp_period(&self) -> f32 {
+        let properties = unsafe { self.instance.get_physical_device_properties(self.physical_device) };
+        properties.limits.timestamp_period
+    }
 
-        let submit_info = vk::SubmitInfo::builder()
-            .wait_semaphores(wait_semaphores)
-            .signal_semaphores(signal_semaphores)
-            .wait_dst_stage_mask(&wait_stage_masks)
-            .command_buffers(&submit_buffers);
+    /// Queue families without valid timestamp bits can't write timestamps
+    pub fn supports_timestamps(&self, queue: &Queue) -> bool {
+        let queue_families = unsafe { self.instance.get_physical_device_queue_family_properties(self.physical_device) };
+        queue_families[queue.get_family_index() as usize].timestamp_valid_bits > 0
+    }
 
-        unsafe {
-            self.inner.queue_submit(
-                queue,
-                &[submit_info.build()],
-                fence,
-            )?;
-        }
+    /// Submits the command buffer and returns the value of the queue's timeline that signals its completion,
+    /// wait values only matter for timeline semaphores
+    pub fn queue_submit(
+        &self,
+        queue: &Queue,
+        command_buffer: &CommandBuffer,
+        wait_stage_masks: &[vk::PipelineStageFlags],
+        wait_semaphores: &[vk::Semaphore],
+        wait_values: &[u64],
+        signal_semaphores: &[vk::Semaphore],
+    ) -> Result<u64, Error> {
 
-        Ok(())
+        let submit_buffers = [command_buffer.get_inner()];
+        let timeline = queue.get_timeline();
+
+        timeline.submit(|value| {
+            let mut signal_semaphores = signal_semaphores.to_vec();
+            let mut signal_values = vec![0; signal_semaphores.len()];
+            signal_semaphores.push(timeline.get_inner());
+            signal_values.push(value);
+
+            let mut timeline_submit_info = vk::TimelineSemaphoreSubmitInfo::builder()
+                .wait_semaphore_values(wait_values)
+                .signal_semaphore_values(&signal_values);
+
+            let submit_info = vk::SubmitInfo::builder()
+                .push_next(&mut timeline_submit_info)
+                .wait_semaphores(wait_semaphores)
+                .signal_semaphores(&signal_semaphores)
+                .wait_dst_stage_mask(&wait_stage_masks)
+                .command_buffers(&submit_buffers);
+
+            unsafe {
+                self.inner.queue_submit(
+                    queue.get_inner(),
+                    &[submit_info.build()],
+                    vk::Fence::null(),
+                )?;
+            }
+
+            Ok(())
+        })
     }
 
     pub fn wait_idle(&self) -> Result<(), Error> {
@@ -261,8 +436,14 @@ impl Drop for Device {
             self.inner.device_wait_idle().unwrap();
 
             ManuallyDrop::drop(&mut self.allocator);
+            self.graphics_queue.destroy();
+            self.compute_queue.destroy();
+            self.transfer_queue.destroy();
             self.inner.destroy_device(None);
-            ManuallyDrop::drop(&mut self.surface);
+
+            if let Some(ref mut surface) = self.surface {
+                ManuallyDrop::drop(surface);
+            }
 
             if let Some(ref mut debugger) = self.debugger {
                 ManuallyDrop::drop(debugger);
@@ -9,7 +9,7 @@ use std::{
 use super::{
     surface::Surface,
 };
-use crate::Error;
+use crate::{Error, graphics::{GraphicsError, DevicePreference}};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct DeviceIdentifier {
@@ -101,7 +101,7 @@ impl DeviceProfile {
         self.transfer_queue_index
     }
 
-    pub unsafe fn query_device_profiles<E: EntryV1_0, I: InstanceV1_0>(entry: &E, instance: &I, surface: &Surface) -> Result<Vec<DeviceProfile>, Error> {
+    pub unsafe fn query_device_profiles<E: EntryV1_0, I: InstanceV1_0>(entry: &E, instance: &I, surface: Option<&Surface>) -> Result<Vec<DeviceProfile>, Error> {
         let profiles = instance.enumerate_physical_devices()?
             .iter()
             .map(|pdevice| {
@@ -127,7 +127,9 @@ impl DeviceProfile {
 
                 for index in 0..queue_families.len() {
                     let queue_family = queue_families[index];
-                    let surface_support = surface.get_physical_device_surface_support(*pdevice, index as u32);
+                    let surface_support = surface
+                        .map(|surface| surface.get_physical_device_surface_support(*pdevice, index as u32))
+                        .unwrap_or(true);
                     let supports_graphics = queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS);
                     let supports_compute = queue_family.queue_flags.contains(vk::QueueFlags::COMPUTE);
                     let supports_transfer = queue_family.queue_flags.contains(vk::QueueFlags::TRANSFER);
@@ -141,6 +143,10 @@ impl DeviceProfile {
                     }
                 }
 
+                // devices with a single queue family (like software rasterizers) share it for all work
+                let compute_queue_index = compute_queue_index.or(graphics_queue_index);
+                let transfer_queue_index = transfer_queue_index.or(compute_queue_index);
+
                 DeviceProfile {
                     physical_device: *pdevice,
                     device_type,
@@ -157,6 +163,31 @@ impl DeviceProfile {
         Ok(profiles)
     }
 
+    /// Finds the device the preference points to, as long as it has a graphics queue.
+    /// Names are compared case-insensitively, an exact match wins over a device whose name only contains the preference
+    pub fn find_preferred(profiles: &Vec<DeviceProfile>, preference: &DevicePreference) -> Result<usize, Error> {
+        let has_graphics_queue = |index: &usize| profiles[*index].graphics_queue_index.is_some();
+
+        let index = match preference {
+            DevicePreference::Index(index) => Some(*index).filter(|index| *index < profiles.len() && has_graphics_queue(index)),
+            DevicePreference::Name(name) => {
+                let name = name.trim().to_lowercase();
+                let exact = (0..profiles.len())
+                    .filter(has_graphics_queue)
+                    .find(|index| profiles[*index].device_name.to_lowercase() == name);
+
+                exact.or_else(|| (0..profiles.len())
+                    .filter(has_graphics_queue)
+                    .find(|index| profiles[*index].device_name.to_lowercase().contains(&name)))
+            },
+        };
+
+        index.ok_or_else(|| {
+            let devices = profiles.iter().map(|profile| profile.device_name.clone()).collect();
+            GraphicsError::NoPreferredDevice(format!("{:?}", preference), devices).into()
+        })
+    }
+
     pub fn find_highest_score(profiles: &Vec<DeviceProfile>) -> Option<usize> {
         let mut max_value = std::f32::NEG_INFINITY;
         let mut index = None;
@@ -172,3 +203,39 @@ impl DeviceProfile {
         index
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(device_name: &str, graphics_queue_index: Option<usize>) -> DeviceProfile {
+        DeviceProfile {
+            physical_device: vk::PhysicalDevice::null(),
+            device_type: DeviceType::DiscreteGPU,
+            device_identifier: DeviceIdentifier::new([0; vk::UUID_SIZE]),
+            device_name: device_name.to_owned(),
+            graphics_queue_index,
+            transfer_queue_index: graphics_queue_index,
+            compute_queue_index: graphics_queue_index,
+            score: 1.0,
+        }
+    }
+
+    #[test]
+    fn it_finds_preferred_devices_by_part_of_their_name() {
+        let profiles = vec![
+            profile("NVIDIA GeForce RTX 2070", None),
+            profile("llvmpipe (LLVM 10.0.0, 256 bits)", Some(0)),
+            profile("NVIDIA GeForce RTX 2070 SUPER", Some(0)),
+            profile("llvmpipe", Some(0)),
+        ];
+        let find = |name: &str| DeviceProfile::find_preferred(&profiles, &DevicePreference::Name(name.to_owned())).ok();
+
+        assert_eq!(find("LLVMpipe"), Some(3));
+        assert_eq!(find("llvm"), Some(1));
+        assert_eq!(find("rtx 2070"), Some(2));
+        assert_eq!(find("radeon"), None);
+        assert_eq!(DeviceProfile::find_preferred(&profiles, &DevicePreference::Index(0)).ok(), None);
+        assert_eq!(DeviceProfile::find_preferred(&profiles, &DevicePreference::Index(4)).ok(), None);
+    }
+}
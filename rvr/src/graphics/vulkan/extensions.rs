@@ -12,8 +12,8 @@ impl Extensions {
         }
     }
 
-    pub fn enable_ext_debug_report(&mut self) {
-        self.extensions.push(CString::from(ext::DebugReport::name()));
+    pub fn enable_ext_debug_utils(&mut self) {
+        self.extensions.push(CString::from(ext::DebugUtils::name()));
     }
 
     pub fn enable_khr_surface(&mut self) {
@@ -24,6 +24,18 @@ impl Extensions {
         self.extensions.push(CString::from(khr::Win32Surface::name()))
     }
 
+    pub fn enable_khr_xlib_surface(&mut self) {
+        self.extensions.push(CString::from(khr::XlibSurface::name()))
+    }
+
+    pub fn enable_khr_xcb_surface(&mut self) {
+        self.extensions.push(CString::from(khr::XcbSurface::name()))
+    }
+
+    pub fn enable_khr_wayland_surface(&mut self) {
+        self.extensions.push(CString::from(khr::WaylandSurface::name()))
+    }
+
     pub fn get_names_raw(&self) -> Vec<*const i8> {
         let raw: Vec<*const i8> = self.extensions
             .iter()
@@ -0,0 +1,56 @@
+use ash::{vk, version::DeviceV1_0};
+use std::sync::Arc;
+use crate::Error;
+use super::*;
+
+pub struct Framebuffer {
+    inner: vk::Framebuffer,
+    device: Arc<ash::Device>,
+    width: u32,
+    height: u32,
+}
+
+impl Framebuffer {
+    pub fn create(device: Arc<ash::Device>, render_pass: &RenderPass, attachments: &[&ImageView], width: u32, height: u32) -> Result<Self, Error> {
+
+        let attachments: Vec<vk::ImageView> = attachments.iter()
+            .map(|attachment| attachment.get_inner())
+            .collect();
+
+        let create_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(render_pass.get_inner())
+            .attachments(&attachments)
+            .width(width)
+            .height(height)
+            .layers(1);
+
+        let inner = unsafe { device.create_framebuffer(&create_info, None)? };
+
+        Ok(Self {
+            inner,
+            device,
+            width,
+            height,
+        })
+    }
+
+    pub(crate) fn get_inner(&self) -> vk::Framebuffer {
+        self.inner
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_framebuffer(self.inner, None);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use ash::{vk, version::{DeviceV1_0, DeviceV1_2}};
+use std::sync::{Arc, Mutex};
+use crate::Error;
+
+/// A timeline semaphore that every submission to a queue signals with the next higher value,
+/// work on the queue is finished once the semaphore reached the value of its submission
+pub struct GpuTimeline {
+    device: Arc<ash::Device>,
+    inner: vk::Semaphore,
+    // the value of the last submission, also serializes the submissions of different threads
+    last_value: Mutex<u64>,
+}
+
+impl GpuTimeline {
+    pub fn create(device: Arc<ash::Device>) -> Result<Self, Error> {
+        let mut type_create_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0);
+
+        let create_info = vk::SemaphoreCreateInfo::builder()
+            .push_next(&mut type_create_info);
+
+        let inner = unsafe { device.create_semaphore(&create_info, None)? };
+
+        Ok(Self {
+            device,
+            inner,
+            last_value: Mutex::new(0),
+        })
+    }
+
+    pub(crate) fn get_inner(&self) -> vk::Semaphore {
+        self.inner
+    }
+
+    /// The value the last submission signals when it is finished
+    pub fn get_last_value(&self) -> u64 {
+        *self.last_value.lock().unwrap()
+    }
+
+    pub fn get_completed_value(&self) -> Result<u64, Error> {
+        Ok(unsafe { self.device.get_semaphore_counter_value(self.inner)? })
+    }
+
+    pub fn is_completed(&self, value: u64) -> Result<bool, Error> {
+        Ok(self.get_completed_value()? >= value)
+    }
+
+    /// Blocks until the timeline reached the value
+    pub fn wait(&self, value: u64) -> Result<(), Error> {
+        let semaphores = [self.inner];
+        let values = [value];
+        let wait_info = vk::SemaphoreWaitInfo::builder()
+            .semaphores(&semaphores)
+            .values(&values);
+
+        unsafe { self.device.wait_semaphores(&wait_info, std::u64::MAX)? };
+
+        Ok(())
+    }
+
+    /// Hands the next value to the submission, it only becomes the last value if the submission succeeds
+    pub(crate) fn submit<F>(&self, submit: F) -> Result<u64, Error>
+        where F: FnOnce(u64) -> Result<(), Error>
+    {
+        let mut last_value = self.last_value.lock().unwrap();
+        let value = *last_value + 1;
+
+        submit(value)?;
+        *last_value = value;
+
+        Ok(value)
+    }
+
+    /// The timeline must not be used by pending submissions
+    pub(crate) unsafe fn destroy(&mut self) {
+        self.device.destroy_semaphore(self.inner, None);
+    }
+}
@@ -0,0 +1,174 @@
+use ash::{vk, version::DeviceV1_0};
+use vk_mem::{Allocator, Allocation, AllocationCreateInfo, MemoryUsage};
+use std::sync::Arc;
+use crate::Error;
+
+pub fn get_aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM | vk::Format::D32_SFLOAT | vk::Format::X8_D24_UNORM_PACK32 => vk::ImageAspectFlags::DEPTH,
+        vk::Format::D16_UNORM_S8_UINT | vk::Format::D24_UNORM_S8_UINT | vk::Format::D32_SFLOAT_S8_UINT => vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+        vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+enum ImageMemory {
+    Dedicated {
+        allocation: Allocation,
+        allocator: Arc<Allocator>,
+    },
+    Unbound {
+        device: Arc<ash::Device>,
+        memory_requirements: vk::MemoryRequirements,
+    },
+}
+
+/// Everything about an image that gets fixed when it is created
+#[derive(Debug, Copy, Clone)]
+pub struct ImageCreateDescription {
+    pub image_type: vk::ImageType,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub format: vk::Format,
+    pub samples: vk::SampleCountFlags,
+    pub mip_levels: u32,
+    pub array_layers: u32,
+    pub flags: vk::ImageCreateFlags,
+    pub usage: vk::ImageUsageFlags,
+}
+
+impl ImageCreateDescription {
+    /// A single sampled 2D image without mip levels or layers
+    pub fn new_2d(width: u32, height: u32, format: vk::Format, usage: vk::ImageUsageFlags) -> Self {
+        Self {
+            image_type: vk::ImageType::TYPE_2D,
+            width,
+            height,
+            depth: 1,
+            format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            mip_levels: 1,
+            array_layers: 1,
+            flags: vk::ImageCreateFlags::empty(),
+            usage,
+        }
+    }
+}
+
+pub struct Image {
+    inner: vk::Image,
+    memory: ImageMemory,
+    width: u32,
+    height: u32,
+    format: vk::Format,
+    samples: vk::SampleCountFlags,
+}
+
+impl Image {
+    fn get_create_info(description: &ImageCreateDescription) -> vk::ImageCreateInfo {
+        vk::ImageCreateInfo::builder()
+            .flags(description.flags)
+            .image_type(description.image_type)
+            .format(description.format)
+            .extent(vk::Extent3D {
+                width: description.width,
+                height: description.height,
+                depth: description.depth,
+            })
+            .mip_levels(description.mip_levels)
+            .array_layers(description.array_layers)
+            .samples(description.samples)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(description.usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .build()
+    }
+
+    pub fn create(allocator: Arc<Allocator>, description: &ImageCreateDescription) -> Result<Self, Error> {
+
+        let create_info = Self::get_create_info(description);
+
+        let allocation_create_info = AllocationCreateInfo {
+            usage: MemoryUsage::GpuOnly,
+            ..Default::default()
+        };
+
+        let (inner, allocation, _) = allocator.create_image(&create_info, &allocation_create_info)?;
+
+        Ok(Self {
+            inner,
+            memory: ImageMemory::Dedicated {
+                allocation,
+                allocator,
+            },
+            width: description.width,
+            height: description.height,
+            format: description.format,
+            samples: description.samples,
+        })
+    }
+
+    /// Creates an image without memory, it has to be bound to a `Memory` before it can be used
+    pub fn create_unbound(device: Arc<ash::Device>, description: &ImageCreateDescription) -> Result<Self, Error> {
+
+        let create_info = Self::get_create_info(description);
+
+        let (inner, memory_requirements) = unsafe {
+            let inner = device.create_image(&create_info, None)?;
+            (inner, device.get_image_memory_requirements(inner))
+        };
+
+        Ok(Self {
+            inner,
+            memory: ImageMemory::Unbound {
+                device,
+                memory_requirements,
+            },
+            width: description.width,
+            height: description.height,
+            format: description.format,
+            samples: description.samples,
+        })
+    }
+
+    pub(crate) fn get_inner(&self) -> vk::Image {
+        self.inner
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_format(&self) -> vk::Format {
+        self.format
+    }
+
+    pub fn get_samples(&self) -> vk::SampleCountFlags {
+        self.samples
+    }
+
+    /// The memory requirements of an unbound image
+    pub fn get_memory_requirements(&self) -> Option<vk::MemoryRequirements> {
+        match &self.memory {
+            ImageMemory::Dedicated { .. } => None,
+            ImageMemory::Unbound { memory_requirements, .. } => Some(*memory_requirements),
+        }
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        match &self.memory {
+            ImageMemory::Dedicated { allocation, allocator } => allocator.destroy_image(self.inner, allocation).unwrap(),
+            ImageMemory::Unbound { device, .. } => unsafe {
+                device.destroy_image(self.inner, None);
+            },
+        }
+    }
+}
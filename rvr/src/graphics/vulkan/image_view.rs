@@ -0,0 +1,45 @@
+use ash::{vk, version::DeviceV1_0};
+use std::sync::Arc;
+use crate::Error;
+
+pub struct ImageView {
+    inner: vk::ImageView,
+    device: Arc<ash::Device>,
+}
+
+impl ImageView {
+    /// Creates a 2D view of a single mip level and layer of an image
+    pub fn create(device: Arc<ash::Device>, image: vk::Image, format: vk::Format, subresource_range: vk::ImageSubresourceRange) -> Result<Self, Error> {
+
+        let create_info = vk::ImageViewCreateInfo::builder()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(format)
+            .components(vk::ComponentMapping {
+                r: vk::ComponentSwizzle::IDENTITY,
+                g: vk::ComponentSwizzle::IDENTITY,
+                b: vk::ComponentSwizzle::IDENTITY,
+                a: vk::ComponentSwizzle::IDENTITY,
+            })
+            .subresource_range(subresource_range);
+
+        let inner = unsafe { device.create_image_view(&create_info, None)? };
+
+        Ok(Self {
+            inner,
+            device,
+        })
+    }
+
+    pub(crate) fn get_inner(&self) -> vk::ImageView {
+        self.inner
+    }
+}
+
+impl Drop for ImageView {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_image_view(self.inner, None);
+        }
+    }
+}
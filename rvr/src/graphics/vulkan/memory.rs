@@ -0,0 +1,46 @@
+use ash::vk;
+use vk_mem::{Allocator, Allocation, AllocationCreateInfo, MemoryUsage};
+use std::sync::Arc;
+use crate::Error;
+use super::Image;
+
+/// Device memory that is not owned by a single resource, images created unbound can share it
+pub struct Memory {
+    allocation: Allocation,
+    allocator: Arc<Allocator>,
+    size: u64,
+}
+
+impl Memory {
+    pub fn allocate(allocator: Arc<Allocator>, memory_requirements: &vk::MemoryRequirements) -> Result<Self, Error> {
+
+        let allocation_create_info = AllocationCreateInfo {
+            usage: MemoryUsage::GpuOnly,
+            ..Default::default()
+        };
+
+        let (allocation, _) = allocator.allocate_memory(memory_requirements, &allocation_create_info)?;
+
+        Ok(Self {
+            allocation,
+            allocator,
+            size: memory_requirements.size,
+        })
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Binds an unbound image to the start of this memory
+    pub fn bind_image(&self, image: &Image) -> Result<(), Error> {
+        self.allocator.bind_image_memory(image.get_inner(), &self.allocation)?;
+        Ok(())
+    }
+}
+
+impl Drop for Memory {
+    fn drop(&mut self) {
+        self.allocator.free_memory(&self.allocation).unwrap();
+    }
+}
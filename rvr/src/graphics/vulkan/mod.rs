@@ -1,13 +1,18 @@
+mod buffer;
 mod command_buffer;
 mod command_pool;
 mod debugger;
 mod device;
 mod extensions;
-mod fence;
-mod fence_pool;
+mod framebuffer;
+mod gpu_timeline;
+mod image;
+mod image_view;
 mod layers;
+mod memory;
 mod pipeline;
 mod queue;
+mod query_pool;
 mod render_pass;
 mod surface;
 mod device_profile;
@@ -15,16 +20,21 @@ mod swapchain;
 mod semaphore;
 mod semaphore_pool;
 
+pub use buffer::*;
 pub use command_buffer::*;
 pub use command_pool::*;
 pub use debugger::*;
 pub use device::*;
 pub use extensions::*;
-pub use fence::*;
-pub use fence_pool::*;
+pub use framebuffer::*;
+pub use gpu_timeline::*;
+pub use image::*;
+pub use image_view::*;
 pub use layers::*;
+pub use memory::*;
 pub use pipeline::*;
 pub use queue::*;
+pub use query_pool::*;
 pub use render_pass::*;
 pub use surface::*;
 pub use device_profile::*;
@@ -1,7 +1,6 @@
 use std::{sync::Arc, ffi::CString};
 use ash::{vk, Device, version::DeviceV1_0};
 use crate::Error;
-use super::*;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum ShaderStageType {
@@ -83,6 +82,16 @@ pub struct VertexAttribute {
     format: vk::Format,
 }
 
+impl VertexAttribute {
+    pub fn new(location: u32, stride: u32, format: vk::Format) -> Self {
+        Self {
+            location,
+            stride,
+            format,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DescriptorSetLayoutBinding {
     binding: u32,
@@ -91,6 +100,17 @@ pub struct DescriptorSetLayoutBinding {
     stage_flags: vk::ShaderStageFlags,
 }
 
+impl DescriptorSetLayoutBinding {
+    pub fn new(binding: u32, descriptor_type: DescriptorType, count: u32, stage_flags: vk::ShaderStageFlags) -> Self {
+        Self {
+            binding,
+            descriptor_type,
+            count,
+            stage_flags,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DescriptorSetLayout {
     pub set: u32,
@@ -104,16 +124,36 @@ pub struct ShaderStage {
     entry: CString,
 }
 
-#[derive(Debug)]
+impl ShaderStage {
+    pub fn new(spirv: Vec<u32>, stage: ShaderStageType, entry: CString) -> Self {
+        Self {
+            spirv,
+            stage,
+            entry,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
 pub struct PushConstant {
     offset: u32,
     size: u32,
     stage_flags: vk::ShaderStageFlags,
 }
 
+impl PushConstant {
+    pub fn new(offset: u32, size: u32, stage_flags: vk::ShaderStageFlags) -> Self {
+        Self {
+            offset,
+            size,
+            stage_flags,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ShaderDescription {
-    pipeline_bind_point: PipelineBindPoint,
+    name: String,
     stages: Vec<ShaderStage>,
     attributes: Vec<VertexAttribute>,
     descriptor_set_layouts: Vec<DescriptorSetLayout>,
@@ -121,6 +161,35 @@ pub struct ShaderDescription {
     bind_point: PipelineBindPoint,
 }
 
+impl ShaderDescription {
+    /// A shader with a compute stage becomes a compute pipeline, every other one a graphics pipeline
+    pub fn new(name: String, stages: Vec<ShaderStage>, attributes: Vec<VertexAttribute>, descriptor_set_layouts: Vec<DescriptorSetLayout>, push_constants: Vec<PushConstant>) -> Self {
+        let bind_point = if stages.iter().any(|stage| stage.stage == ShaderStageType::Compute) {
+            PipelineBindPoint::Compute
+        } else {
+            PipelineBindPoint::Graphics
+        };
+
+        Self {
+            name,
+            stages,
+            attributes,
+            descriptor_set_layouts,
+            push_constants,
+            bind_point,
+        }
+    }
+
+    /// The asset the shader was loaded from, pipelines created from it carry the same name
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_bind_point(&self) -> PipelineBindPoint {
+        self.bind_point
+    }
+}
+
 unsafe fn create_shader_module(device: &Arc<ash::Device>, shader_stage: &ShaderStage) -> Result<vk::ShaderModule, Error> {
     let shader_create_info = vk::ShaderModuleCreateInfo::builder().code(&shader_stage.spirv);
     let shader_module = device.create_shader_module(&shader_create_info, None)?;
@@ -128,17 +197,87 @@ unsafe fn create_shader_module(device: &Arc<ash::Device>, shader_stage: &ShaderS
     Ok(shader_module)
 }
 
+/// Creates the descriptor set layouts and the pipeline layout of a shader
+unsafe fn create_pipeline_layout(device: &Arc<ash::Device>, shader_description: &ShaderDescription) -> Result<(Vec<vk::DescriptorSetLayout>, vk::PipelineLayout), Error> {
+    let mut descriptor_set_layouts = Vec::new();
+    for descriptor_set_layout in &shader_description.descriptor_set_layouts {
+        let mut bindings = Vec::new();
+        for binding in &descriptor_set_layout.bindings {
+            bindings.push(vk::DescriptorSetLayoutBinding {
+                binding: binding.binding,
+                descriptor_type: binding.descriptor_type.into(),
+                descriptor_count: binding.count,
+                stage_flags: binding.stage_flags.into(),
+                ..Default::default()
+            });
+        }
+
+        // only the last binding of a set can have a variable descriptor count
+        let binding_flags: Vec<vk::DescriptorBindingFlags> = (0..bindings.len())
+            .map(|index| {
+                let flags = vk::DescriptorBindingFlags::PARTIALLY_BOUND | vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING;
+                if index + 1 == bindings.len() {
+                    flags | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT
+                } else {
+                    flags
+                }
+            })
+            .collect();
+
+        let mut next = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder()
+            .binding_flags(&binding_flags);
+
+        let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .push_next(&mut next)
+            .bindings(&bindings);
+        let descriptor_set_layout = device.create_descriptor_set_layout(&descriptor_set_layout_create_info, None)?;
+
+        descriptor_set_layouts.push(descriptor_set_layout);
+    }
+
+    let mut push_constant_ranges = Vec::new();
+    for push_constant in &shader_description.push_constants {
+        push_constant_ranges.push(vk::PushConstantRange {
+            offset: push_constant.offset,
+            size: push_constant.size,
+            stage_flags: push_constant.stage_flags.into(),
+        });
+    }
+
+    let layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(&descriptor_set_layouts)
+        .push_constant_ranges(&push_constant_ranges);
+    let pipeline_layout = device.create_pipeline_layout(&layout_create_info, None)?;
+
+    Ok((descriptor_set_layouts, pipeline_layout))
+}
+
+pub(crate) fn get_push_constant_stages(push_constants: &[PushConstant], offset: u32, size: u32) -> vk::ShaderStageFlags {
+    push_constants.iter()
+        .filter(|push_constant| push_constant.offset < offset + size && offset < push_constant.offset + push_constant.size)
+        .fold(vk::ShaderStageFlags::empty(), |stages, push_constant| stages | push_constant.stage_flags)
+}
+
 pub struct Pipeline {
     inner: vk::Pipeline,
     device: Arc<Device>,
     pipeline_layout: vk::PipelineLayout,
     descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    push_constants: Vec<PushConstant>,
     bind_point: vk::PipelineBindPoint,
 }
 
 impl Pipeline {
-    pub fn create(shader_description: &ShaderDescription, device: Arc<Device>, width: u32, height: u32, compatible_render_pass: &RenderPass) -> Result<Self, Error> {
-        
+    /// Creates a graphics pipeline for a subpass of the render pass, the pipeline renders with the samples of the subpass attachments
+    pub fn create(
+        device: Arc<Device>,
+        shader_description: &ShaderDescription,
+        compatible_render_pass: vk::RenderPass,
+        subpass: u32,
+        color_attachment_count: u32,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Self, Error> {
+
         let mut shader_stage_create_infos = Vec::new();
         let mut shader_modules = Vec::new();
         for shader_stage in shader_description.stages.iter() {
@@ -184,29 +323,11 @@ impl Pipeline {
             ..Default::default()
         };
 
-        let surface_resolution = vk::Extent2D {
-            width,
-            height,
-        };
-
-        let viewports = [vk::Viewport {
-            x: 0.0,
-            y: 0.0,
-            width: surface_resolution.width as f32,
-            height: surface_resolution.height as f32,
-            min_depth: 0.0,
-            max_depth: 1.0,
-        }];
-
-        let scissors = [vk::Rect2D {
-            extent: surface_resolution,
-            ..Default::default()
-        }];
-        
+        // viewport and scissor are dynamic, they get set to the render area when the pipeline is bound
         let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
-            .scissors(&scissors)
-            .viewports(&viewports);
-        
+            .viewport_count(1)
+            .scissor_count(1);
+
         let rasterization_info = vk::PipelineRasterizationStateCreateInfo {
             front_face: vk::FrontFace::COUNTER_CLOCKWISE,
             line_width: 1.0,
@@ -216,7 +337,7 @@ impl Pipeline {
         };
         
         let multisample_state_info = vk::PipelineMultisampleStateCreateInfo::builder()
-            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+            .rasterization_samples(samples);
 
         let noop_stencil_state = vk::StencilOpState {
             fail_op: vk::StencilOp::KEEP,
@@ -236,7 +357,8 @@ impl Pipeline {
             ..Default::default()
         };
         
-        let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+        // every color attachment of the subpass needs a blend state
+        let color_blend_attachment_states = vec![vk::PipelineColorBlendAttachmentState {
             blend_enable: 0,
             src_color_blend_factor: vk::BlendFactor::SRC_COLOR,
             dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_DST_COLOR,
@@ -245,7 +367,7 @@ impl Pipeline {
             dst_alpha_blend_factor: vk::BlendFactor::ZERO,
             alpha_blend_op: vk::BlendOp::ADD,
             color_write_mask: vk::ColorComponentFlags::all(),
-        }];
+        }; color_attachment_count as usize];
         let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
             .logic_op(vk::LogicOp::CLEAR)
             .attachments(&color_blend_attachment_states);
@@ -253,50 +375,8 @@ impl Pipeline {
         let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
         let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_state);
 
-        let mut descriptor_set_layouts = Vec::new();
-        for descriptor_set_layout in &shader_description.descriptor_set_layouts {
-            let mut bindings = Vec::new();
-            for binding in &descriptor_set_layout.bindings {
-                bindings.push(vk::DescriptorSetLayoutBinding {
-                    binding: binding.binding,
-                    descriptor_type: binding.descriptor_type.into(),
-                    descriptor_count: binding.count,
-                    stage_flags: binding.stage_flags.into(),
-                    ..Default::default()
-                });
-            }
-
-            let binding_flags = [
-                vk::DescriptorBindingFlags::PARTIALLY_BOUND
-                | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT
-                | vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING
-            ];
-
-            let mut next = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder()
-                .binding_flags(&binding_flags);
-
-            let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
-                .push_next(&mut next)
-                .bindings(&bindings);
-            let descriptor_set_layout = unsafe { device.create_descriptor_set_layout(&descriptor_set_layout_create_info, None)? };
-
-            descriptor_set_layouts.push(descriptor_set_layout);
-        }
-
-        let mut push_constant_ranges = Vec::new();
-        for push_constant in &shader_description.push_constants {
-            push_constant_ranges.push(vk::PushConstantRange {
-                offset: push_constant.offset,
-                size: push_constant.size,
-                stage_flags: push_constant.stage_flags.into(),
-            });
-        }
-
-        let layout_create_info = vk::PipelineLayoutCreateInfo::builder()
-            .set_layouts(&descriptor_set_layouts)
-            .push_constant_ranges(&push_constant_ranges);
-        let pipeline_layout = unsafe {
-            device.create_pipeline_layout(&layout_create_info, None)?
+        let (descriptor_set_layouts, pipeline_layout) = unsafe {
+            create_pipeline_layout(&device, shader_description)?
         };
 
         let pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
@@ -310,13 +390,10 @@ impl Pipeline {
             .color_blend_state(&color_blend_state)
             .dynamic_state(&dynamic_state_info)
             .layout(pipeline_layout)
-            .render_pass(compatible_render_pass.get_inner());
+            .render_pass(compatible_render_pass)
+            .subpass(subpass);
 
         let create_result = unsafe { device.create_graphics_pipelines(vk::PipelineCache::null(), &[pipeline_create_info.build()], None) };
-        let inner = match create_result {
-            Ok(pipelines) => Ok(pipelines[0]),
-            Err((_, error)) => Err(error),
-        }?;
 
         for module in shader_modules {
             unsafe {
@@ -324,18 +401,87 @@ impl Pipeline {
             }
         }
 
+        let inner = match create_result {
+            Ok(pipelines) => Ok(pipelines[0]),
+            Err((_, error)) => Err(error),
+        }?;
+
         Ok(Self {
             inner,
             device,
             pipeline_layout,
             descriptor_set_layouts,
+            push_constants: shader_description.push_constants.clone(),
             bind_point: shader_description.bind_point.into(),
         })
     }
 
+    /// Creates a compute pipeline from the compute stage of the shader
+    pub fn create_compute(device: Arc<Device>, shader_description: &ShaderDescription) -> Result<Self, Error> {
+        let shader_stage = shader_description.stages.iter()
+            .find(|stage| stage.stage == ShaderStageType::Compute)
+            .expect("shader has no compute stage");
+
+        let module = unsafe {
+            create_shader_module(&device, shader_stage)?
+        };
+
+        let stage_create_info = vk::PipelineShaderStageCreateInfo {
+            module,
+            p_name: shader_stage.entry.as_ptr(),
+            stage: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        };
+
+        let (descriptor_set_layouts, pipeline_layout) = unsafe {
+            create_pipeline_layout(&device, shader_description)?
+        };
+
+        let pipeline_create_info = vk::ComputePipelineCreateInfo::builder()
+            .stage(stage_create_info)
+            .layout(pipeline_layout);
+
+        let create_result = unsafe { device.create_compute_pipelines(vk::PipelineCache::null(), &[pipeline_create_info.build()], None) };
+
+        unsafe {
+            device.destroy_shader_module(module, None);
+        }
+
+        let inner = match create_result {
+            Ok(pipelines) => Ok(pipelines[0]),
+            Err((_, error)) => Err(error),
+        }?;
+
+        Ok(Self {
+            inner,
+            device,
+            pipeline_layout,
+            descriptor_set_layouts,
+            push_constants: shader_description.push_constants.clone(),
+            bind_point: vk::PipelineBindPoint::COMPUTE,
+        })
+    }
+
     pub(crate) fn get_inner(&self) -> vk::Pipeline {
         self.inner
     }
+
+    pub(crate) fn get_layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout
+    }
+
+    pub(crate) fn get_bind_point(&self) -> vk::PipelineBindPoint {
+        self.bind_point
+    }
+
+    pub(crate) fn get_push_constants(&self) -> &[PushConstant] {
+        &self.push_constants
+    }
+
+    /// The stages of all push constant ranges that overlap the bytes, they all have to be named when updating them
+    pub fn get_push_constant_stages(&self, offset: u32, size: u32) -> vk::ShaderStageFlags {
+        get_push_constant_stages(&self.push_constants, offset, size)
+    }
 }
 
 impl Drop for Pipeline {
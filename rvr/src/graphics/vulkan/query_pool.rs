@@ -0,0 +1,55 @@
+use ash::{vk, version::DeviceV1_0};
+use std::sync::Arc;
+use crate::Error;
+
+pub struct QueryPool {
+    device: Arc<ash::Device>,
+    inner: vk::QueryPool,
+    count: u32,
+}
+
+impl QueryPool {
+    pub fn create_timestamps(device: Arc<ash::Device>, count: u32) -> Result<Self, Error> {
+        let create_info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(count);
+
+        let inner = unsafe { device.create_query_pool(&create_info, None)? };
+
+        Ok(Self {
+            device,
+            inner,
+            count,
+        })
+    }
+
+    pub(crate) fn get_inner(&self) -> vk::QueryPool {
+        self.inner
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Reads the written timestamps, returns `None` if not all of them are available yet
+    pub fn get_timestamps(&self, first_query: u32, query_count: u32) -> Result<Option<Vec<u64>>, Error> {
+        let mut timestamps = vec![0u64; query_count as usize];
+        let result = unsafe {
+            self.device.get_query_pool_results(self.inner, first_query, query_count, &mut timestamps, vk::QueryResultFlags::TYPE_64)
+        };
+
+        match result {
+            Ok(()) => Ok(Some(timestamps)),
+            Err(vk::Result::NOT_READY) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_query_pool(self.inner, None);
+        }
+    }
+}
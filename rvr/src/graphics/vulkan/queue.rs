@@ -1,9 +1,11 @@
 use ash::vk;
+use super::GpuTimeline;
 
 pub struct Queue {
     family_index: u32,
     index: u32,
     inner: vk::Queue,
+    timeline: GpuTimeline,
 
     supports_present: bool,
     supports_graphics: bool,
@@ -16,6 +18,7 @@ impl Queue {
         family_index: u32,
         index: u32,
         inner: vk::Queue,
+        timeline: GpuTimeline,
         supports_present: bool,
         supports_graphics: bool,
         supports_compute: bool,
@@ -25,6 +28,7 @@ impl Queue {
             family_index,
             index,
             inner,
+            timeline,
             supports_present,
             supports_graphics,
             supports_compute,
@@ -36,6 +40,11 @@ impl Queue {
         self.inner
     }
 
+    /// The timeline every submission to this queue signals
+    pub fn get_timeline(&self) -> &GpuTimeline {
+        &self.timeline
+    }
+
     pub fn get_family_index(&self) -> u32 {
         self.family_index
     }
@@ -43,4 +52,9 @@ impl Queue {
     pub fn get_index(&self) -> u32 {
         self.index
     }
+
+    /// Has to be called before the device gets destroyed
+    pub(crate) unsafe fn destroy(&mut self) {
+        self.timeline.destroy();
+    }
 }
@@ -2,14 +2,14 @@ use std::sync::Arc;
 use ash::{vk, Device, version::DeviceV1_0};
 use crate::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct RenderPassDescription {
     pub attachments: Vec<AttachmentDescription>,
     pub subpasses: Vec<SubpassDescription>,
     pub dependencies: Vec<SubpassDependencyDescription>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct AttachmentDescription {
     pub format: vk::Format,
     pub samples: vk::SampleCountFlags,
@@ -21,20 +21,23 @@ pub struct AttachmentDescription {
     pub final_layout: vk::ImageLayout,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct AttachmentReferenceDescription {
     pub attachment: u32,
     pub layout: vk::ImageLayout,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct SubpassDescription {
     pub color_attachments: Vec<AttachmentReferenceDescription>,
+    pub input_attachments: Vec<AttachmentReferenceDescription>,
+    /// Either empty or one per color attachment
+    pub resolve_attachments: Vec<AttachmentReferenceDescription>,
     pub preserve_attachments: Vec<u32>,
     pub depth_stencil_attachment: Option<AttachmentReferenceDescription>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct SubpassDependencyDescription {
     pub src_subpass: u32,
     pub dst_subpass: u32,
@@ -42,6 +45,7 @@ pub struct SubpassDependencyDescription {
     pub dst_stage_mask: vk::PipelineStageFlags,
     pub src_access_mask: vk::AccessFlags,
     pub dst_access_mask: vk::AccessFlags,
+    pub dependency_flags: vk::DependencyFlags,
 }
 
 pub struct RenderPass {
@@ -68,7 +72,7 @@ impl RenderPass {
             })
             .collect();
 
-        let subpass_attachments: Vec<(Vec<vk::AttachmentReference>, Vec<u32>, Option<vk::AttachmentReference>)> = render_pass_description.subpasses.iter()
+        let subpass_attachments: Vec<(Vec<vk::AttachmentReference>, Vec<vk::AttachmentReference>, Vec<vk::AttachmentReference>, Vec<u32>, Option<vk::AttachmentReference>)> = render_pass_description.subpasses.iter()
             .map(|subpass| {
                 let color_attachment_refs: Vec<vk::AttachmentReference> = subpass.color_attachments.iter()
                     .map(|color_attachment| {
@@ -79,6 +83,24 @@ impl RenderPass {
                     })
                     .collect();
 
+                let input_attachment_refs: Vec<vk::AttachmentReference> = subpass.input_attachments.iter()
+                    .map(|input_attachment| {
+                        vk::AttachmentReference {
+                            attachment: input_attachment.attachment,
+                            layout: input_attachment.layout,
+                        }
+                    })
+                    .collect();
+
+                let resolve_attachment_refs: Vec<vk::AttachmentReference> = subpass.resolve_attachments.iter()
+                    .map(|resolve_attachment| {
+                        vk::AttachmentReference {
+                            attachment: resolve_attachment.attachment,
+                            layout: resolve_attachment.layout,
+                        }
+                    })
+                    .collect();
+
                 let preserve_attachments = subpass.preserve_attachments.clone();
 
                 let depth_stencil_attachment = if let Some(depth_stencil_attachment) = &subpass.depth_stencil_attachment {
@@ -90,15 +112,16 @@ impl RenderPass {
                     None
                 };
 
-                (color_attachment_refs, preserve_attachments, depth_stencil_attachment)
+                (color_attachment_refs, input_attachment_refs, resolve_attachment_refs, preserve_attachments, depth_stencil_attachment)
             })
             .collect();
 
         let subpasses: Vec<vk::SubpassDescription> = subpass_attachments.iter()
-            .map(|(color_attachment_refs, preserve_attachments, depth_stencil_attachment)| {
+            .map(|(color_attachment_refs, input_attachment_refs, resolve_attachment_refs, preserve_attachments, depth_stencil_attachment)| {
 
                 let mut builder = vk::SubpassDescription::builder()
                     .color_attachments(&color_attachment_refs)
+                    .input_attachments(&input_attachment_refs)
                     .preserve_attachments(&preserve_attachments)
                     .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS);
 
@@ -106,6 +129,10 @@ impl RenderPass {
                     builder = builder.depth_stencil_attachment(depth_stencil_attachment);
                 }
 
+                if !resolve_attachment_refs.is_empty() {
+                    builder = builder.resolve_attachments(&resolve_attachment_refs);
+                }
+
                 builder.build()
             })
             .collect();
@@ -119,7 +146,7 @@ impl RenderPass {
                     dst_stage_mask: dependency.dst_stage_mask,
                     src_access_mask: dependency.src_access_mask,
                     dst_access_mask: dependency.dst_access_mask,
-                    ..Default::default()
+                    dependency_flags: dependency.dependency_flags,
                 }
             })
             .collect();
@@ -8,24 +8,39 @@ use std::sync::Arc;
 
 pub struct SemaphorePool {
     device: Arc<ash::Device>,
+    semaphores: Vec<Semaphore>,
 }
 
 impl SemaphorePool {
     pub fn new(device: Arc<ash::Device>) -> Self {
         Self {
             device,
+            semaphores: Vec::new(),
         }
     }
 
     pub fn get_semaphore(&mut self) -> Result<Semaphore, Error> {
+        if let Some(semaphore) = self.semaphores.pop() {
+            return Ok(semaphore);
+        }
+
         let create_info = vk::SemaphoreCreateInfo::default();
         let inner = unsafe { self.device.create_semaphore(&create_info, None)? };
         Ok(Semaphore::new(inner))
     }
 
+    /// The semaphore must be unsignaled, i.e. every signal it received has been waited on
     pub fn return_semaphore(&mut self, semaphore: Semaphore) {
-        unsafe {
-            self.device.destroy_semaphore(semaphore.get_inner(), None);
+        self.semaphores.push(semaphore);
+    }
+}
+
+impl Drop for SemaphorePool {
+    fn drop(&mut self) {
+        for semaphore in self.semaphores.drain(..) {
+            unsafe {
+                self.device.destroy_semaphore(semaphore.get_inner(), None);
+            }
         }
     }
 }
@@ -6,7 +6,13 @@ use ash::{
     version::{EntryV1_0, InstanceV1_0},
 };
 use raw_window_handle::RawWindowHandle;
-use crate::Error;
+use crate::{
+    Error,
+    graphics::{
+        GraphicsError,
+        vulkan::Extensions,
+    },
+};
 
 pub struct Surface {
     inner: vk::SurfaceKHR,
@@ -14,11 +20,39 @@ pub struct Surface {
 }
 
 impl Surface {
+    #[cfg(target_os = "windows")]
+    pub fn enable_extensions(extensions: &mut Extensions, handle: RawWindowHandle) -> Result<(), Error> {
+        match handle {
+            RawWindowHandle::Windows(_) => extensions.enable_khr_win32_surface(),
+            _ => return Err(GraphicsError::UnsupportedWindowHandle.into()),
+        }
+
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+    pub fn enable_extensions(extensions: &mut Extensions, handle: RawWindowHandle) -> Result<(), Error> {
+        match handle {
+            RawWindowHandle::Xlib(_) => extensions.enable_khr_xlib_surface(),
+            RawWindowHandle::Xcb(_) => extensions.enable_khr_xcb_surface(),
+            RawWindowHandle::Wayland(_) => extensions.enable_khr_wayland_surface(),
+            _ => return Err(GraphicsError::UnsupportedWindowHandle.into()),
+        }
+
+        Ok(())
+    }
+
+    // surfaces on macos need a metal layer, which the window doesn't create yet
+    #[cfg(target_os = "macos")]
+    pub fn enable_extensions(_extensions: &mut Extensions, _handle: RawWindowHandle) -> Result<(), Error> {
+        Err(GraphicsError::UnsupportedWindowHandle.into())
+    }
+
     #[cfg(target_os = "windows")]
     pub fn create<E: EntryV1_0, I: InstanceV1_0>(entry: &E, instance: &I, handle: RawWindowHandle) -> Result<Self, Error> {
         let (hwnd, hinstance) = match handle {
             RawWindowHandle::Windows(windows) => (windows.hwnd, windows.hinstance),
-            _ => panic!("unknown window handle"),
+            _ => return Err(GraphicsError::UnsupportedWindowHandle.into()),
         };
 
         let win32_create_info = vk::Win32SurfaceCreateInfoKHR {
@@ -40,9 +74,59 @@ impl Surface {
         })
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
     pub fn create<E: EntryV1_0, I: InstanceV1_0>(entry: &E, instance: &I, handle: RawWindowHandle) -> Result<Self, Error> {
-        unimplemented!()
+        let inner = match handle {
+            RawWindowHandle::Xlib(xlib) => {
+                let xlib_create_info = vk::XlibSurfaceCreateInfoKHR {
+                    s_type: vk::StructureType::XLIB_SURFACE_CREATE_INFO_KHR,
+                    p_next: ptr::null(),
+                    flags: Default::default(),
+                    dpy: xlib.display as *mut vk::Display,
+                    window: xlib.window as vk::Window,
+                };
+
+                let xlib_surface_loader = khr::XlibSurface::new(entry, instance);
+                unsafe { xlib_surface_loader.create_xlib_surface(&xlib_create_info, None)? }
+            },
+            RawWindowHandle::Xcb(xcb) => {
+                let xcb_create_info = vk::XcbSurfaceCreateInfoKHR {
+                    s_type: vk::StructureType::XCB_SURFACE_CREATE_INFO_KHR,
+                    p_next: ptr::null(),
+                    flags: Default::default(),
+                    connection: xcb.connection as *mut vk::xcb_connection_t,
+                    window: xcb.window as vk::xcb_window_t,
+                };
+
+                let xcb_surface_loader = khr::XcbSurface::new(entry, instance);
+                unsafe { xcb_surface_loader.create_xcb_surface(&xcb_create_info, None)? }
+            },
+            RawWindowHandle::Wayland(wayland) => {
+                let wayland_create_info = vk::WaylandSurfaceCreateInfoKHR {
+                    s_type: vk::StructureType::WAYLAND_SURFACE_CREATE_INFO_KHR,
+                    p_next: ptr::null(),
+                    flags: Default::default(),
+                    display: wayland.display as *mut vk::wl_display,
+                    surface: wayland.surface as *mut vk::wl_surface,
+                };
+
+                let wayland_surface_loader = khr::WaylandSurface::new(entry, instance);
+                unsafe { wayland_surface_loader.create_wayland_surface(&wayland_create_info, None)? }
+            },
+            _ => return Err(GraphicsError::UnsupportedWindowHandle.into()),
+        };
+
+        let extension_loader = khr::Surface::new(entry, instance);
+
+        Ok(Self {
+            inner,
+            extension_loader,
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn create<E: EntryV1_0, I: InstanceV1_0>(_entry: &E, _instance: &I, _handle: RawWindowHandle) -> Result<Self, Error> {
+        Err(GraphicsError::UnsupportedWindowHandle.into())
     }
 
     pub fn get_physical_device_surface_support(&self, physical_device: vk::PhysicalDevice, queue_family_index: u32) -> bool {
@@ -67,6 +151,14 @@ impl Surface {
         Ok(capabilities)
     }
 
+    pub fn get_physical_device_surface_present_modes(&self, physical_device: vk::PhysicalDevice) -> Result<Vec<vk::PresentModeKHR>, Error> {
+        let present_modes = unsafe {
+            self.extension_loader.get_physical_device_surface_present_modes(physical_device, self.inner)?
+        };
+
+        Ok(present_modes)
+    }
+
     pub fn get_inner(&self) -> vk::SurfaceKHR {
         self.inner
     }
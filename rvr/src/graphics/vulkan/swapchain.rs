@@ -19,6 +19,7 @@ pub struct Swapchain {
     extension_loader: khr::Swapchain,
     inner: vk::SwapchainKHR,
     surface_format: vk::SurfaceFormatKHR,
+    extent: vk::Extent2D,
 }
 
 impl Swapchain {
@@ -28,13 +29,16 @@ impl Swapchain {
         surface: &Surface,
         physical_device: vk::PhysicalDevice,
         min_image_count: u32,
+        present_modes: &[vk::PresentModeKHR],
         width: u32,
-        height: u32
+        height: u32,
+        old_swapchain: Option<&Swapchain>,
     ) -> Result<Self, Error> {
 
         let extension_loader = khr::Swapchain::new(instance, &*device);
         let surface_formats = surface.get_physical_device_surface_formats(physical_device)?;
         let surface_capabilities = surface.get_physical_device_surface_capabilities(physical_device)?;
+        let supported_present_modes = surface.get_physical_device_surface_present_modes(physical_device)?;
 
         if surface_formats.len() < 1 {
             return Err(GraphicsError::NoSuitableSurfaceFormat.into());
@@ -54,11 +58,29 @@ impl Swapchain {
             unimplemented!("TODO implement manual gamma correction");
         }
 
-        let surface_resolution = vk::Extent2D {
-            width,
-            height,
+        // the size only matters if the surface lets the swapchain decide its extent
+        let surface_resolution = if surface_capabilities.current_extent.width == std::u32::MAX {
+            vk::Extent2D {
+                width: width.max(surface_capabilities.min_image_extent.width).min(surface_capabilities.max_image_extent.width),
+                height: height.max(surface_capabilities.min_image_extent.height).min(surface_capabilities.max_image_extent.height),
+            }
+        } else {
+            surface_capabilities.current_extent
+        };
+
+        // a max image count of 0 means there is no limit
+        let min_image_count = if surface_capabilities.max_image_count > 0 {
+            min_image_count.max(surface_capabilities.min_image_count).min(surface_capabilities.max_image_count)
+        } else {
+            min_image_count.max(surface_capabilities.min_image_count)
         };
 
+        // FIFO is the only present mode every surface has to support
+        let present_mode = present_modes.iter()
+            .find(|present_mode| supported_present_modes.contains(present_mode))
+            .copied()
+            .unwrap_or(vk::PresentModeKHR::FIFO);
+
         let pre_transform = if surface_capabilities
             .supported_transforms
             .contains(vk::SurfaceTransformFlagsKHR::IDENTITY)
@@ -78,9 +100,10 @@ impl Swapchain {
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
             .pre_transform(pre_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
-            .present_mode(vk::PresentModeKHR::MAILBOX)
+            .present_mode(present_mode)
             .clipped(true)
-            .image_array_layers(1);
+            .image_array_layers(1)
+            .old_swapchain(old_swapchain.map(|swapchain| swapchain.inner).unwrap_or_else(vk::SwapchainKHR::null));
 
         let inner = unsafe { extension_loader.create_swapchain(&swapchain_create_info, None)? };
 
@@ -89,21 +112,20 @@ impl Swapchain {
             extension_loader,
             inner,
             surface_format,
+            extent: surface_resolution,
         })
     }
 
+    /// Returns no image if the swapchain is out of date, a suboptimal swapchain can still be presented to
     pub fn acquire_next_image(&self, timeout: u64, semaphore: vk::Semaphore, fence: vk::Fence) -> Result<Option<u32>, Error> {
         let result = unsafe {
             self.extension_loader.acquire_next_image(self.inner, timeout, semaphore, fence)
         };
 
         match result {
-            Ok((index, false)) => {
+            Ok((index, _)) => {
                 Ok(Some(index))
             },
-            Ok((_, true)) => {
-                Ok(None)
-            }
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
                 Ok(None)
             },
@@ -117,6 +139,11 @@ impl Swapchain {
         self.surface_format.format
     }
 
+    pub fn get_extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Returns whether the swapchain is out of date or suboptimal and should be recreated
     pub fn present(&self, index: u32, queue: vk::Queue, wait_semaphore: vk::Semaphore) -> Result<bool, Error> {
 
         let wait_semaphores = [wait_semaphore];
@@ -130,7 +157,7 @@ impl Swapchain {
         
         unsafe {
             match self.extension_loader.queue_present(queue, &present_info) {
-                Ok(_) => Ok(false),
+                Ok(suboptimal) => Ok(suboptimal),
                 Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(true),
                 Err(e) => Err(e.into()),
             }
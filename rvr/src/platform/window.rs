@@ -10,13 +10,6 @@ use sdl2::{
 use crate::{
     Error,
     RuntimeError,
-    graphics::{
-        ImageFormat,
-        ImageType,
-        ImageLayout,
-        SampleCount,
-        rendergraph::*,
-    }
 };
 
 pub struct Window {
@@ -27,7 +20,8 @@ pub struct Window {
     width: u32,
     height: u32,
     refresh_rate: u32,
-    format: ImageFormat,
+    resized: bool,
+    minimized: bool,
 }
 
 impl Window {
@@ -46,7 +40,6 @@ impl Window {
 
         let width = display_mode.w as u32;
         let height = display_mode.h as u32;
-        let format = display_mode.format.into();
         let refresh_rate = display_mode.refresh_rate as u32;
 
         let mut inner = video_subsystem.window(title, display_mode.w as u32, display_mode.h as u32)
@@ -68,8 +61,9 @@ impl Window {
 
             width,
             height,
-            format,
             refresh_rate,
+            resized: false,
+            minimized: false,
         })
     }
 
@@ -77,17 +71,50 @@ impl Window {
         self.inner.raw_window_handle()
     }
 
-    pub fn get_backbuffer_image_description(&self) -> ImageDescription {
-        ImageDescription::new(self.width, self.height, self.format, ImageType::Type2D, SampleCount::Type_1, ImageLayout::Unknown)
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the new size if the window was resized since the last call
+    pub fn take_resize(&mut self) -> Option<(u32, u32)> {
+        if self.resized {
+            self.resized = false;
+            Some((self.width, self.height))
+        } else {
+            None
+        }
+    }
+
+    /// A minimized window has no area to render to
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
     }
 
+    /// Handles all pending events and returns whether the window should close, while minimized it waits for the next event
     pub fn poll_events(&mut self) -> bool {
+        let mut events = Vec::new();
+        if self.minimized {
+            events.push(self.event_pump.wait_event());
+        }
+        events.extend(self.event_pump.poll_iter());
+
         let mut exit = false;
-        for event in self.event_pump.poll_iter() {
+        for event in events {
             match event {
                 Event::Quit {..} | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
                     exit = true;
                 },
+                Event::Window { win_event: WindowEvent::SizeChanged(width, height), .. } => {
+                    self.width = width as u32;
+                    self.height = height as u32;
+                    self.resized = true;
+                },
+                Event::Window { win_event: WindowEvent::Minimized, .. } => {
+                    self.minimized = true;
+                },
+                Event::Window { win_event: WindowEvent::Restored, .. } | Event::Window { win_event: WindowEvent::Maximized, .. } => {
+                    self.minimized = false;
+                },
                 _ => {}
             }
         }
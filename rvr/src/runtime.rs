@@ -6,7 +6,7 @@ use crate::{
     graphics::{
         Renderer,
         Configuration,
-        rendergraph::{GraphBuilder, ImageHandle},
+        rendergraph::GraphBuilder,
     },
 };
 
@@ -20,8 +20,23 @@ impl Runtime {
         
         let window = Window::new(window_title)?;
         
-        let configuration = Configuration::new();
-        let renderer = Renderer::create(configuration, window.get_window_handle())?;
+        let configuration = Configuration::load("./config.json")?;
+        let (width, height) = window.get_size();
+        let renderer = Renderer::create(configuration, window.get_window_handle(), width, height)?;
+
+        Self::create_with_renderer(Some(window), renderer, application)
+    }
+
+    /// Creates a runtime without a window that renders into an offscreen image of the given size
+    pub fn create_headless(width: u32, height: u32, application: Box<dyn Application>) -> Result<Self, Error> {
+
+        let configuration = Configuration::load("./config.json")?;
+        let renderer = Renderer::create_headless(configuration, width, height)?;
+
+        Self::create_with_renderer(None, renderer, application)
+    }
+
+    fn create_with_renderer(window: Option<Window>, renderer: Renderer, application: Box<dyn Application>) -> Result<Self, Error> {
 
         let transfer_device = renderer.create_transfer_device()?;
 
@@ -45,17 +60,26 @@ impl Runtime {
         self.application.initialize(&mut self.context)?;
         
         loop {
-            let exit = self.context.window.poll_events();
-
-            self.context.assets.update(&mut self.context.renderer)?;
+            let (exit, minimized) = match &mut self.context.window {
+                Some(window) => {
+                    let exit = window.poll_events();
 
-            self.application.update(&mut self.context)?;
+                    // the resize stays pending while minimized and gets applied once the window is restored
+                    if !window.is_minimized() {
+                        if let Some((width, height)) = window.take_resize() {
+                            self.context.renderer.resize(width, height)?;
+                        }
+                    }
 
+                    (exit, window.is_minimized())
+                },
+                None => (false, false),
+            };
 
-            let mut builder = GraphBuilder::new();
-            let backbuffer = builder.import_image("Backbuffer", self.context.window.get_backbuffer_image_description())?;
-            let present_image = self.application.draw(&mut self.context, &mut builder, backbuffer)?;
-            self.context.renderer.render(builder.build(&[present_image]))?;
+            // a minimized window has no extent, so there is no swapchain to render to
+            if !minimized {
+                self.frame()?;
+            }
 
             if exit {
                 break;
@@ -64,4 +88,32 @@ impl Runtime {
 
         Ok(())
     }
+
+    /// Renders the given number of frames and returns the pixels of the last one, requires a headless runtime
+    pub fn run_headless(mut self, frames: usize) -> Result<Vec<u8>, Error> {
+
+        self.application.initialize(&mut self.context)?;
+
+        for _ in 0..frames {
+            self.frame()?;
+        }
+
+        self.context.renderer.read_back()
+    }
+
+    fn frame(&mut self) -> Result<(), Error> {
+        self.context.assets.update(&mut self.context.renderer)?;
+
+        self.application.update(&mut self.context)?;
+
+        // the backbuffer follows the size of the swapchain, which changes with the window
+        let backbuffer_description = self.context.renderer.get_backbuffer_image_description();
+
+        let mut builder = GraphBuilder::new();
+        let backbuffer = builder.import_image("Backbuffer", backbuffer_description)?;
+        let present_image = self.application.draw(&mut self.context, &mut builder, backbuffer)?;
+        self.context.renderer.render(builder.build(&[present_image])?, backbuffer, &[])?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,36 @@
+use std::{thread, panic};
+
+/// Executes every task on its own thread and waits until all of them are finished,
+/// the results are in the order of the tasks
+pub fn fork_join<Task, TaskResult, F>(tasks: Vec<Task>, execute: F) -> Vec<TaskResult>
+    where Task: Send, TaskResult: Send, F: Fn(Task) -> TaskResult + Sync
+{
+    let execute = &execute;
+
+    thread::scope(|scope| {
+        let mut tasks = tasks.into_iter();
+
+        // the calling thread works on the first task instead of just waiting for the others
+        let first = tasks.next();
+        let handles: Vec<_> = tasks
+            .map(|task| scope.spawn(move || execute(task)))
+            .collect();
+
+        let mut results = Vec::with_capacity(handles.len() + 1);
+        results.extend(first.map(execute));
+
+        for handle in handles {
+            // a panic on a worker thread continues on the calling thread
+            results.push(handle.join().unwrap_or_else(|payload| panic::resume_unwind(payload)));
+        }
+
+        results
+    })
+}
+
+/// The number of threads that can run in parallel on this machine
+pub fn get_thread_count() -> usize {
+    thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
@@ -1,3 +1,19 @@
 mod worker;
+mod fork_join;
 
 pub use worker::*;
+pub use fork_join::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_order_of_the_tasks() {
+        let tasks: Vec<Vec<u32>> = (0..8).map(|task| vec![task; 1000]).collect();
+
+        let results = fork_join(tasks, |task| task.iter().sum::<u32>());
+
+        assert_eq!(results, (0..8).map(|task| task * 1000).collect::<Vec<u32>>());
+    }
+}
@@ -1,12 +1,13 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct NodeIndex(usize);
 
 pub struct DirectedGraph<Node> {
     nodes: Vec<Node>,
-    edges: Vec<HashSet<NodeIndex>>,
-    reverse_edges: Vec<HashSet<NodeIndex>>,
+    // ordered, so traversals visit nodes in the order they were added
+    edges: Vec<BTreeSet<NodeIndex>>,
+    reverse_edges: Vec<BTreeSet<NodeIndex>>,
 }
 
 impl<Node> DirectedGraph<Node> {
@@ -21,8 +22,8 @@ impl<Node> DirectedGraph<Node> {
     pub fn add_node(&mut self, node: Node) -> NodeIndex {
         let index = self.nodes.len();
         self.nodes.push(node);
-        self.edges.push(HashSet::new());
-        self.reverse_edges.push(HashSet::new());
+        self.edges.push(BTreeSet::new());
+        self.reverse_edges.push(BTreeSet::new());
         NodeIndex(index)
     }
 
@@ -56,26 +57,40 @@ impl<Node> DirectedGraph<Node> {
         println!("}}");
     }
 
-    pub fn topological_sort(&self, roots: &[NodeIndex]) -> Vec<NodeIndex> {
+    /// Orders the nodes reachable from the roots so every node comes after the nodes it has edges to,
+    /// fails with the nodes of a cycle if there is one
+    pub fn topological_sort(&self, roots: &[NodeIndex]) -> Result<Vec<NodeIndex>, Vec<NodeIndex>> {
         let mut order = Vec::new();
         let mut visited = HashSet::new();
+        let mut path = Vec::new();
 
         for index in roots {
-            self.sort_recursive(index, &mut order, &mut visited);
+            self.sort_recursive(index, &mut order, &mut visited, &mut path)?;
         }
 
-        order
+        Ok(order)
     }
 
-    pub fn sort_recursive(&self, id: &NodeIndex, order: &mut Vec<NodeIndex>, visited: &mut HashSet<NodeIndex>) {
+    fn sort_recursive(&self, id: &NodeIndex, order: &mut Vec<NodeIndex>, visited: &mut HashSet<NodeIndex>, path: &mut Vec<NodeIndex>) -> Result<(), Vec<NodeIndex>> {
         if visited.contains(id) {
-            return;
+            return Ok(());
         }
 
-        self.edges[id.0].iter().for_each(|id| self.sort_recursive(id, order, visited));
+        // reaching a node that is still being visited closes a cycle
+        if let Some(position) = path.iter().position(|node| node == id) {
+            return Err(path[position..].to_vec());
+        }
+
+        path.push(*id);
+        for next in self.edges[id.0].iter() {
+            self.sort_recursive(next, order, visited, path)?;
+        }
+        path.pop();
 
         order.push(*id);
         visited.insert(*id);
+
+        Ok(())
     }
 
     pub fn visit(&self, visitor: &mut impl Visitor<Node = Node>) {
@@ -112,11 +127,28 @@ mod tests {
         graph.add_edge(b, c);
         graph.add_edge(d, a);
 
-        let order = graph.topological_sort(&[a]);
+        let order = graph.topological_sort(&[a]).unwrap();
 
         assert_eq!(vec![c, b, a], order);
     }
 
+    #[test]
+    fn it_finds_a_cycle() {
+        let mut graph = DirectedGraph::new();
+
+        let a = graph.add_node(0);
+        let b = graph.add_node(1);
+        let c = graph.add_node(2);
+
+        graph.add_edge(a, b);
+        graph.add_edge(b, c);
+        graph.add_edge(c, b);
+
+        let cycle = graph.topological_sort(&[a]).unwrap_err();
+
+        assert_eq!(vec![b, c], cycle);
+    }
+
     #[test]
     fn it_replaces_a_node() {
         let mut graph = DirectedGraph::new();